@@ -0,0 +1,69 @@
+//! Comparison of parsing a large response from text and from bytes
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use exetel_api::customer::{CallRecord, Data, RecordPage};
+use std::fmt::Write;
+
+/// Approximate size of the synthetic payload in bytes
+const PAYLOAD_SIZE: usize = 5 * 1024 * 1024;
+
+/// A page of call records of about `PAYLOAD_SIZE` bytes
+fn payload() -> Vec<u8> {
+    let mut records = String::new();
+    let mut count = 0;
+    while records.len() < PAYLOAD_SIZE {
+        if count > 0 {
+            records.push(',');
+        }
+        write!(
+            records,
+            concat!(
+                r#"{{"number":"04{:08}","direction":"outbound","#,
+                r#""time":"2021-03-{:02}T{:02}:{:02}:00+11:00","cost":"${}.{:02}","#,
+                r#""duration":"00:{:02}:{:02}","network":"Mobile"}}"#,
+            ),
+            count,
+            count % 28 + 1,
+            count % 24,
+            count % 60,
+            count % 10,
+            count % 100,
+            count % 60,
+            (count * 7) % 60,
+        )
+        .unwrap();
+        count += 1;
+    }
+
+    let body = format!(
+        r#"{{"data":{{"records":[{}],"page":1,"perPage":{},"total":{}}}}}"#,
+        records, count, count
+    );
+    body.into_bytes()
+}
+
+type Response = Data<RecordPage<CallRecord>>;
+
+fn parse(c: &mut Criterion) {
+    let payload = payload();
+    let mut group = c.benchmark_group("parse call records");
+    group.throughput(Throughput::Bytes(payload.len() as u64));
+    group.sample_size(20);
+
+    // As responses were parsed before: read as text, then parsed
+    group.bench_function("from_str", |b| {
+        b.iter(|| {
+            let text = String::from_utf8(payload.clone()).unwrap();
+            serde_json::from_str::<Response>(&text).unwrap()
+        })
+    });
+
+    // As responses are parsed now: parsed directly from the bytes received
+    group.bench_function("from_slice", |b| {
+        b.iter(|| serde_json::from_slice::<Response>(&payload).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
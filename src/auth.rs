@@ -1,138 +1,2630 @@
 //! Tools for authenticating to the API
 
-use serde::{Serialize, Deserialize};
-use anyhow::Error;
-use std::time::{Duration, SystemTime};
-use std::convert::TryInto;
-use reqwest::Client;
-use reqwest::header::{ORIGIN, REFERER, ACCEPT, CONTENT_TYPE};
-use serde_json;
+use serde::{Serialize, Deserialize, Deserializer};
+use serde_json::Value;
+use async_trait::async_trait;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::convert::{TryFrom, TryInto};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::header::{ORIGIN, REFERER, ACCEPT, AUTHORIZATION, CONTENT_TYPE, DATE};
+use chrono::DateTime;
 use mime::APPLICATION_JSON;
 use std::fmt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::time;
+use std::env;
+use crate::{Endpoints, Error};
+use crate::error::Body;
+use crate::retry::{self, Backoff};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::netrc;
+#[cfg(unix)]
+use std::fs::Permissions;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 
 /// Encapsulation of the authentication tokens used with the API
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Authorization {
+    /// Time according to the server at which the authorization was last refreshed
     last_refreshed: SystemTime,
+    /// Seconds by which the server clock was ahead of the local clock when last refreshed
+    #[serde(default)]
+    clock_skew: i64,
     last_response: Response,
+    #[serde(skip)]
+    endpoints: Endpoints,
 }
 
 impl Authorization {
-    const LOGIN_URL: &'static str = "https://my.exetel.com.au/api/auth/postLogin";
-    const ORIGIN_URL: &'static str = "https://my.exetel.com.au";
-    const REFERER_URL: &'static str = "https://my.exetel.com.au/login";
+    const ACCESS_TOKEN_VAR: &'static str = "EXETEL_ACCESS_TOKEN";
+    const USERNAME_VAR: &'static str = "EXETEL_USERNAME";
+    const PASSWORD_VAR: &'static str = "EXETEL_PASSWORD";
+
+    /// The machine under which credentials are usually stored in a netrc file
+    pub const NETRC_MACHINE: &'static str = "my.exetel.com.au";
 
     /// Authenticate a user with a username and password
-    pub async fn authenticate(username: &str, password: &str) -> Result<Self, Error> {
-        Self::authenticate_with(&mut Client::new(), username, password).await
+    pub async fn authenticate(username: &str, password: &str) -> Result<Login, Error> {
+        Self::authenticate_with(&Client::new(), username, password).await
+    }
+
+    /// Authenticate a user with a password that is wiped from memory once dropped
+    pub async fn authenticate_secret(username: &str, password: &Secret) -> Result<Login, Error> {
+        Self::authenticate_with(&Client::new(), username, password.expose()).await
+    }
+
+    /// Authenticate a user with an existing client and a password that is wiped from memory
+    /// once dropped
+    pub async fn authenticate_secret_with(
+        client: &Client,
+        username: &str,
+        password: &Secret,
+    ) -> Result<Login, Error> {
+        Self::authenticate_with(client, username, password.expose()).await
+    }
+
+    /// Authenticate a user with additional options
+    pub async fn authenticate_opts(
+        username: &str,
+        password: &str,
+        options: AuthOptions,
+    ) -> Result<Login, Error> {
+        Self::authenticate_opts_with(&Client::new(), username, password, options).await
     }
 
     /// Authenticate a user with an existing client
+    ///
+    /// Rejected logins fail with [`Error::Auth`](enum.Error.html#variant.Auth) describing the
+    /// reason. Accounts using two-factor authentication produce a challenge that must be
+    /// completed with a one-time password.
     pub async fn authenticate_with(
+        client: &Client,
+        username: &str,
+        password: &str,
+    ) -> Result<Login, Error> {
+        Self::authenticate_opts_with(client, username, password, AuthOptions::default()).await
+    }
+
+    /// Authenticate a user with an existing client borrowed mutably
+    ///
+    /// The client is only ever used by reference, so a shared client should be given to
+    /// [`authenticate_with`](#method.authenticate_with) instead.
+    #[deprecated(since = "0.1.0", note = "use `authenticate_with`, which takes a shared client")]
+    pub async fn authenticate_with_mut(
         client: &mut Client,
         username: &str,
         password: &str,
-    ) -> Result<Self, Error> {
-        let query = Query::new(username, password);
-        let query = serde_json::to_string(&query)?;
-
-        let response = client
-            .post(Self::LOGIN_URL)
-            .header(ORIGIN, Self::ORIGIN_URL)
-            .header(REFERER, Self::REFERER_URL)
-            .header(ACCEPT, APPLICATION_JSON.essence_str())
-            .header(CONTENT_TYPE, APPLICATION_JSON.essence_str())
+    ) -> Result<Login, Error> {
+        Self::authenticate_with(client, username, password).await
+    }
+
+    /// Authenticate a user with an existing client and additional options
+    pub async fn authenticate_opts_with(
+        client: &Client,
+        username: &str,
+        password: &str,
+        options: AuthOptions,
+    ) -> Result<Login, Error> {
+        let query = Query {
+            persist_login: options.persist_login,
+            ..Query::new(username, password)
+        };
+        Self::login(client, options, &query).await
+    }
+
+    /// Authenticate a user with an existing client against the given endpoints
+    pub async fn authenticate_with_endpoints(
+        client: &Client,
+        endpoints: Endpoints,
+        username: &str,
+        password: &str,
+    ) -> Result<Login, Error> {
+        let options = AuthOptions {
+            endpoints,
+            ..Default::default()
+        };
+        Self::authenticate_opts_with(client, username, password, options).await
+    }
+
+    /// Authenticate a user with an existing client and a previously issued access token
+    pub async fn authenticate_token_with(
+        client: &Client,
+        username: &str,
+        access_token: &Token,
+    ) -> Result<Login, Error> {
+        let query = Query::with_token(username, access_token);
+        Self::login(client, AuthOptions::default(), &query).await
+    }
+
+    /// Submit a login request, giving up once the timeout elapses
+    ///
+    /// Nothing is shared with the login until it completes, so it may be dropped at any time.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "authenticate", skip_all, fields(username = query.username))
+    )]
+    async fn login(
+        client: &Client,
+        options: AuthOptions,
+        query: &Query<'_>,
+    ) -> Result<Login, Error> {
+        let backoff = options.backoff();
+        let login = Self::exchange(client, options.endpoints, backoff, query);
+        match time::timeout(options.timeout, login).await {
+            Ok(login) => login,
+            Err(_) => Err(AuthError::Timeout(options.timeout).into()),
+        }
+    }
+
+    /// Exchange the credentials for an authorization
+    async fn exchange(
+        client: &Client,
+        endpoints: Endpoints,
+        backoff: Backoff,
+        query: &Query<'_>,
+    ) -> Result<Login, Error> {
+        // The body contains the password, so it is wiped once the login is complete
+        let query = Secret::new(serde_json::to_string(query)?);
+
+        let response = retry::retry(backoff, retry::is_transient, || {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(url = %endpoints.login, "sending login request");
+            Self::portal_request(client, &endpoints, &endpoints.login)
+                .body(query.expose().to_owned())
+                .send()
+        })
+        .await?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(status = %response.status(), "received login response");
+        let issued = Self::issued(response.headers());
+        let body = Body::read(response).await?;
+
+        let login = match Self::parse_login(issued, &body, endpoints.clone())? {
+            Exchanged::Authorized(authorization) => Login::Authorized(*authorization),
+            Exchanged::OtpRequired(challenge) => {
+                Login::OtpRequired(OtpChallenge::new(client.clone(), endpoints, challenge))
+            }
+        };
+        Ok(login)
+    }
+
+    /// Serialize a request to log in with a username and password
+    ///
+    /// The body contains the password, so it is returned as a secret.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn login_body(
+        username: &str,
+        password: &str,
+        persist_login: bool,
+    ) -> Result<Secret, Error> {
+        let query = Query {
+            persist_login,
+            ..Query::new(username, password)
+        };
+        Ok(Secret::new(serde_json::to_string(&query)?))
+    }
+
+    /// Interpret the response to a login request issued at the given time
+    pub(crate) fn parse_login(
+        (refreshed, clock_skew): (SystemTime, i64),
+        body: &Body,
+        endpoints: Endpoints,
+    ) -> Result<Exchanged, Error> {
+        let status = body.status;
+        if !status.is_success() {
+            return Err(AuthError::from_failure(status, &body.text()).into());
+        }
+
+        let response = match serde_json::from_slice(&body.bytes) {
+            Ok(response) => response,
+            Err(err) => match serde_json::from_slice::<Failure>(&body.bytes) {
+                Ok(Failure { message: Some(_) }) => {
+                    return Err(AuthError::from_failure(status, &body.text()).into());
+                }
+                _ => return Err(body.deserialize_error(err)),
+            },
+        };
+
+        let login = match response {
+            LoginResponse::Authorized(response) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(expires_in = ?response.expires_in, "parsed token expiry");
+                let authorization = Authorization::new(response, refreshed, endpoints);
+                Exchanged::Authorized(Box::new(authorization.with_clock_skew(clock_skew)))
+            }
+            LoginResponse::OtpRequired(challenge) => Exchanged::OtpRequired(challenge),
+        };
+        Ok(login)
+    }
+
+    /// Authenticate using credentials from the environment
+    ///
+    /// If `EXETEL_ACCESS_TOKEN` is set it is used as the access token without logging in.
+    /// Otherwise the user is authenticated with `EXETEL_USERNAME` and `EXETEL_PASSWORD`.
+    /// Variables that are set but empty are treated as missing.
+    pub async fn from_env() -> Result<Login, Error> {
+        Self::from_env_opts(AuthOptions::default()).await
+    }
+
+    /// Authenticate using credentials from the environment, with additional options
+    async fn from_env_opts(options: AuthOptions) -> Result<Login, Error> {
+        let var = |name| env::var(name).ok().filter(|value: &String| !value.is_empty());
+        if let Some(access_token) = var(Self::ACCESS_TOKEN_VAR) {
+            return Ok(Login::Authorized(Self::from_tokens(access_token, None, None)));
+        }
+
+        let username = var(Self::USERNAME_VAR);
+        let password = var(Self::PASSWORD_VAR).map(Secret::from);
+        match (username, password) {
+            (Some(username), Some(password)) => {
+                let client = Client::new();
+                Self::authenticate_opts_with(&client, &username, password.expose(), options).await
+            }
+            (username, password) => {
+                let mut missing = vec![Self::ACCESS_TOKEN_VAR];
+                if username.is_none() {
+                    missing.push(Self::USERNAME_VAR);
+                }
+                if password.is_none() {
+                    missing.push(Self::PASSWORD_VAR);
+                }
+                Err(AuthError::MissingEnvironment(missing).into())
+            }
+        }
+    }
+
+    /// Authenticate with the credentials for a machine in the netrc file of the current user
+    ///
+    /// The file is read from `$NETRC`, or `~/.netrc` if that is not set. Usually the machine is
+    /// [`NETRC_MACHINE`](#associatedconstant.NETRC_MACHINE).
+    pub async fn from_netrc(machine: &str) -> Result<Login, Error> {
+        match netrc::default_path() {
+            Some(path) => Self::from_netrc_path(path, machine).await,
+            None => Err(AuthError::MissingEnvironment(vec!["NETRC", "HOME"]).into()),
+        }
+    }
+
+    /// Authenticate with the credentials for a machine in the given netrc file
+    ///
+    /// The file must not be accessible by other users.
+    pub async fn from_netrc_path(path: impl AsRef<Path>, machine: &str) -> Result<Login, Error> {
+        Self::from_netrc_path_opts(path.as_ref(), machine, AuthOptions::default()).await
+    }
+
+    /// Authenticate with the credentials for a machine in the given netrc file, with additional
+    /// options
+    async fn from_netrc_path_opts(
+        path: &Path,
+        machine: &str,
+        options: AuthOptions,
+    ) -> Result<Login, Error> {
+        let netrc = match fs::read_to_string(path) {
+            Ok(netrc) => netrc,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(AuthError::NetrcNotFound(path.to_owned()).into());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        #[cfg(unix)]
+        {
+            if fs::metadata(path)?.permissions().mode() & 0o077 != 0 {
+                return Err(AuthError::NetrcInsecure(path.to_owned()).into());
+            }
+        }
+
+        let entry = netrc::find(&netrc, machine);
+        match entry.and_then(|entry| Some((entry.login?, entry.password?))) {
+            Some((username, password)) => {
+                let client = Client::new();
+                let password = Secret::from(password);
+                Self::authenticate_opts_with(&client, &username, password.expose(), options).await
+            }
+            None => Err(AuthError::NetrcMissingMachine(machine.to_owned()).into()),
+        }
+    }
+
+    /// Construct an authorization from a response received at a given time
+    fn new(response: Response, refreshed: SystemTime, endpoints: Endpoints) -> Self {
+        Authorization {
+            last_response: response,
+            last_refreshed: refreshed,
+            clock_skew: 0,
+            endpoints,
+        }
+    }
+
+    /// Record the number of seconds by which the server clock is ahead of the local clock
+    fn with_clock_skew(mut self, clock_skew: i64) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    /// Determine when a response was issued according to the server, along with the number of
+    /// seconds by which the server clock is ahead of the local clock
+    ///
+    /// The local clock is used if the response has no valid `Date` header.
+    pub(crate) fn issued(headers: &HeaderMap) -> (SystemTime, i64) {
+        let now = SystemTime::now();
+        let date = headers
+            .get(DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .and_then(|date| u64::try_from(date.timestamp()).ok());
+        let local = now.duration_since(UNIX_EPOCH).map(|local| local.as_secs());
+
+        match (date, local) {
+            (Some(date), Ok(local)) => {
+                let clock_skew = date as i64 - local as i64;
+                (UNIX_EPOCH + Duration::from_secs(date), clock_skew)
+            }
+            _ => (now, 0),
+        }
+    }
+
+    /// Convert a time according to the server to the local clock
+    ///
+    /// The time is left unchanged if the skew would take it out of the range of `SystemTime`.
+    fn server_to_local(&self, time: SystemTime) -> SystemTime {
+        let skew = Duration::from_secs(self.clock_skew.unsigned_abs());
+        let local = if self.clock_skew >= 0 {
+            time.checked_sub(skew)
+        } else {
+            time.checked_add(skew)
+        };
+        local.unwrap_or(time)
+    }
+
+    /// The endpoints with which the authorization is refreshed and revoked
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    /// Change the endpoints with which the authorization is refreshed and revoked
+    ///
+    /// Endpoints are not saved with the authorization, so this must be set again after loading a
+    /// saved authorization that does not use the default endpoints.
+    pub fn set_endpoints(&mut self, endpoints: Endpoints) {
+        self.endpoints = endpoints;
+    }
+
+    /// Refresh the access token using the refresh token
+    ///
+    /// Fails with [`AuthError::RefreshRejected`] if the refresh token is no longer accepted, in
+    /// which case the user must authenticate again.
+    ///
+    /// [`AuthError::RefreshRejected`]: enum.AuthError.html#variant.RefreshRejected
+    pub async fn refresh(&mut self) -> Result<(), Error> {
+        self.refresh_with(&Client::new()).await
+    }
+
+    /// Refresh the access token with an existing client
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "refresh",
+            skip_all,
+            fields(customer_id = ?self.last_response.account.customer_id()),
+        )
+    )]
+    pub async fn refresh_with(&mut self, client: &Client) -> Result<(), Error> {
+        let query = self.refresh_body()?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(url = %self.endpoints.refresh, "sending refresh request");
+        let response = Self::portal_request(client, &self.endpoints, &self.endpoints.refresh)
             .body(query)
             .send()
-            .await?
-            .text()
             .await?;
 
-        let refreshed = SystemTime::now();
-        let response = serde_json::from_str(&response)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(status = %response.status(), "received refresh response");
+        let issued = Self::issued(response.headers());
+        let body = Body::read(response).await?;
+        self.apply_refresh(issued, &body)
+    }
 
-        let auth = Authorization {
-            last_response: response,
-            last_refreshed: refreshed,
+    /// Serialize a request to refresh the access token
+    pub(crate) fn refresh_body(&self) -> Result<String, Error> {
+        let refresh_token = match &self.last_response.refresh_token {
+            Some(refresh_token) => refresh_token,
+            None => return Err(AuthError::NoRefreshToken.into()),
+        };
+        Ok(serde_json::to_string(&RefreshQuery::new(refresh_token))?)
+    }
+
+    /// Update the authorization from the response to a refresh request issued at the given time
+    pub(crate) fn apply_refresh(
+        &mut self,
+        (refreshed, clock_skew): (SystemTime, i64),
+        body: &Body,
+    ) -> Result<(), Error> {
+        match body.status {
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(AuthError::RefreshRejected.into());
+            }
+            status if !status.is_success() => {
+                return Err(Error::from_response(status, body.text().into_owned()));
+            }
+            _ => {}
+        }
+
+        let mut response: Response = body.parse()?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(expires_in = ?response.expires_in, "parsed token expiry");
+        if response.account.is_empty() {
+            response.account = self.last_response.account.clone();
+        }
+        self.last_response = response;
+        self.last_refreshed = refreshed;
+        self.clock_skew = clock_skew;
+        Ok(())
+    }
+
+    /// Replace the authorization by logging in again with the given credentials
+    ///
+    /// The new session is established against the same endpoints as the existing one. Fails with
+    /// [`AuthError::OtpRequired`] if the login requires a one-time password.
+    ///
+    /// [`AuthError::OtpRequired`]: enum.AuthError.html#variant.OtpRequired
+    pub async fn reauthenticate_with(
+        &mut self,
+        client: &Client,
+        credentials: &dyn CredentialProvider,
+    ) -> Result<(), Error> {
+        let (username, password) = credentials.credentials().await?;
+        let options = AuthOptions {
+            persist_login: self.is_persistent(),
+            endpoints: self.endpoints.clone(),
+            ..Default::default()
+        };
+
+        let login = Self::authenticate_opts_with(client, &username, password.expose(), options);
+        match login.await? {
+            Login::Authorized(authorization) => *self = authorization,
+            Login::OtpRequired(_) => return Err(AuthError::OtpRequired.into()),
+        }
+        Ok(())
+    }
+
+    /// Revoke the authorization, ending the session
+    pub async fn revoke(self) -> Result<(), Error> {
+        self.revoke_with(&Client::new()).await
+    }
+
+    /// Revoke the authorization with an existing client, ending the session
+    ///
+    /// The authorization is consumed even if it could not be revoked.
+    pub async fn revoke_with(self, client: &Client) -> Result<(), Error> {
+        let bearer = self.bearer()?;
+        let endpoints = self.endpoints.clone();
+        drop(self);
+
+        Self::revoke_bearer(client, &endpoints, bearer).await
+    }
+
+    /// Revoke the session identified by an authorization header
+    pub(crate) async fn revoke_bearer(
+        client: &Client,
+        endpoints: &Endpoints,
+        bearer: String,
+    ) -> Result<(), Error> {
+        Self::portal_request(client, endpoints, &endpoints.logout)
+            .header(AUTHORIZATION, bearer)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Get the authorization header for the access token
+    ///
+    /// Fails if the token is not of a type that can be applied to requests.
+    pub(crate) fn bearer(&self) -> Result<String, Error> {
+        match &self.last_response.token_type {
+            TokenType::Bearer => Ok(format!("Bearer {}", self.access_token().expose())),
+            TokenType::Other(token_type) => {
+                Err(AuthError::UnsupportedTokenType(token_type.clone()).into())
+            }
+        }
+    }
+
+    /// Save the authorization to a file
+    ///
+    /// On Unix the file is made readable and writable only by its owner.
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let file = options.open(path)?;
+        #[cfg(unix)]
+        file.set_permissions(Permissions::from_mode(0o600))?;
+
+        serde_json::to_writer_pretty(file, &self.to_stored())?;
+        Ok(())
+    }
+
+    /// Load an authorization previously saved with [`save_to`](#method.save_to)
+    ///
+    /// Fails with [`AuthError::StoredTokenExpired`] if the stored access token has already
+    /// expired.
+    ///
+    /// [`AuthError::StoredTokenExpired`]: enum.AuthError.html#variant.StoredTokenExpired
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let stored: Value = serde_json::from_reader(file)?;
+
+        let auth = if stored.get("version").is_some() {
+            Self::from_stored(StoredAuthorization::from_value(stored)?)?
+        } else {
+            // Authorizations saved before the format was versioned
+            serde_json::from_value(stored)?
         };
+
+        if auth.is_expired() {
+            return Err(AuthError::StoredTokenExpired.into());
+        }
         Ok(auth)
     }
 
-    pub fn access_token(&self) -> &impl fmt::Display {
+    /// Convert the authorization to its persisted form
+    pub fn to_stored(&self) -> StoredAuthorization {
+        let last_refreshed = self
+            .last_refreshed
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        StoredAuthorization {
+            version: StoredAuthorization::VERSION,
+            last_refreshed,
+            token_type: self.last_response.token_type.clone(),
+            expires_in: self.last_response.expires_in,
+            access_token: self.last_response.access_token.clone(),
+            refresh_token: self.last_response.refresh_token.clone(),
+            persist_login: self.last_response.persist_login,
+            account: self.last_response.account.clone(),
+            clock_skew: self.clock_skew,
+        }
+    }
+
+    /// Restore an authorization from its persisted form
+    ///
+    /// Fails with [`AuthError::UnsupportedVersion`] if the stored authorization was produced by a
+    /// newer version of this crate.
+    ///
+    /// [`AuthError::UnsupportedVersion`]: enum.AuthError.html#variant.UnsupportedVersion
+    pub fn from_stored(stored: StoredAuthorization) -> Result<Self, Error> {
+        stored.check_version()?;
+
+        let response = Response {
+            token_type: stored.token_type,
+            expires_in: stored.expires_in,
+            access_token: stored.access_token,
+            refresh_token: stored.refresh_token,
+            persist_login: stored.persist_login,
+            account: stored.account,
+        };
+        let refreshed = UNIX_EPOCH + Duration::from_secs(stored.last_refreshed);
+        let authorization = Authorization::new(response, refreshed, Endpoints::default());
+        Ok(authorization.with_clock_skew(stored.clock_skew))
+    }
+
+    /// Save the refresh token to the credential store of the operating system
+    ///
+    /// Only the refresh token and its metadata are saved, not the access token.
+    #[cfg(feature = "keyring")]
+    pub fn save_to_keyring(&self, service: &str, account: &str) -> Result<(), Error> {
+        let refresh_token = match &self.last_response.refresh_token {
+            Some(refresh_token) => refresh_token.clone(),
+            None => return Err(AuthError::NoRefreshToken.into()),
+        };
+        let entry = KeyringEntry {
+            refresh_token,
+            last_refreshed: self.last_refreshed(),
+            expires_in: self.last_response.expires_in,
+            persist_login: self.last_response.persist_login,
+        };
+        let entry = Secret::new(serde_json::to_string(&entry)?);
+
+        keyring::Entry::new(service, account)
+            .and_then(|keyring| keyring.set_password(entry.expose()))?;
+        Ok(())
+    }
+
+    /// Load a refresh token saved with [`save_to_keyring`](#method.save_to_keyring)
+    ///
+    /// As the access token is not saved, the authorization is refreshed immediately.
+    #[cfg(feature = "keyring")]
+    pub async fn load_from_keyring(service: &str, account: &str) -> Result<Self, Error> {
+        Self::load_from_keyring_at(service, account, Endpoints::default()).await
+    }
+
+    /// Load a refresh token saved in the keyring, refreshing it against the given endpoints
+    #[cfg(feature = "keyring")]
+    async fn load_from_keyring_at(
+        service: &str,
+        account: &str,
+        endpoints: Endpoints,
+    ) -> Result<Self, Error> {
+        let entry = keyring::Entry::new(service, account)
+            .and_then(|keyring| keyring.get_password())
+            .map(Secret::new)?;
+        let entry: KeyringEntry = serde_json::from_str(entry.expose())?;
+
+        let response = Response {
+            token_type: TokenType::Bearer,
+            expires_in: entry.expires_in,
+            access_token: Token::new(String::new()),
+            refresh_token: Some(entry.refresh_token),
+            persist_login: entry.persist_login,
+            account: AccountInfo::default(),
+        };
+        let refreshed = entry.last_refreshed;
+        let mut authorization = Authorization::new(response, refreshed, endpoints);
+        authorization.refresh().await?;
+        Ok(authorization)
+    }
+
+    /// Build a request to the customer portal
+    fn portal_request(client: &Client, endpoints: &Endpoints, url: &str) -> RequestBuilder {
+        client.post(url).headers(Self::portal_headers(endpoints))
+    }
+
+    /// Get the headers sent with each request to the customer portal
+    pub(crate) fn portal_headers(endpoints: &Endpoints) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        // Endpoints containing invalid header values are rejected by the portal anyway
+        if let Ok(origin) = HeaderValue::from_str(&endpoints.origin) {
+            headers.insert(ORIGIN, origin);
+        }
+        if let Ok(referer) = HeaderValue::from_str(&endpoints.referer) {
+            headers.insert(REFERER, referer);
+        }
+        let json = HeaderValue::from_static(APPLICATION_JSON.essence_str());
+        headers.insert(ACCEPT, json.clone());
+        headers.insert(CONTENT_TYPE, json);
+        headers
+    }
+
+    /// Construct an authorization from tokens obtained elsewhere
+    ///
+    /// The token is assumed to have been issued now. If `expires_in` is not known the
+    /// authorization is treated as never expiring, so it will never be refreshed automatically.
+    pub fn from_tokens(
+        access_token: String,
+        refresh_token: Option<String>,
+        expires_in: Option<u64>,
+    ) -> Self {
+        let response = Response {
+            token_type: TokenType::Bearer,
+            expires_in,
+            access_token: access_token.into(),
+            refresh_token: refresh_token.map(Token::from),
+            persist_login: false,
+            account: AccountInfo::default(),
+        };
+
+        Authorization::new(response, SystemTime::now(), Endpoints::default())
+    }
+
+    /// Check if the session was established as a persistent login
+    ///
+    /// Persistent sessions are typically issued longer-lived tokens.
+    pub fn is_persistent(&self) -> bool {
+        self.last_response.persist_login
+    }
+
+    /// The identity of the account that has been authorized
+    pub fn account(&self) -> &AccountInfo {
+        &self.last_response.account
+    }
+
+    pub fn access_token(&self) -> &Token {
         &self.last_response.access_token
     }
 
+    /// The token with which the access token is refreshed, if one was issued
+    pub fn refresh_token(&self) -> Option<&Token> {
+        self.last_response.refresh_token.as_ref()
+    }
+
+    /// The number of seconds for which the access token was issued, if it is known
+    pub fn expires_in(&self) -> Option<u64> {
+        self.last_response.expires_in
+    }
+
+    /// Export the tokens for use by other tools
+    ///
+    /// The tokens can be imported again with [`from_tokens`](#method.from_tokens).
+    pub fn export(&self) -> ExportedTokens {
+        // The remaining time is exported so the expiry is kept when importing the tokens later
+        let remaining = self.remaining().map_or(0, |remaining| remaining.as_secs());
+        let expires_in = self.expires_at().map(|_| remaining);
+        ExportedTokens {
+            access_token: self.last_response.access_token.clone(),
+            refresh_token: self.last_response.refresh_token.clone(),
+            expires_in,
+        }
+    }
+
     pub fn into_client(self) -> Result<crate::Client, Error> {
         self.try_into()
     }
 }
 
 impl Authorization {
-    /// Refresh if the token has les than 5 minutes remaining
+    /// Refresh if the token has less than 5 minutes remaining
     const REFRESH_WINDOW: Duration = Duration::from_secs(5 * 60);
 
-    /// Get the expiry time of the authorization
-    fn expires_at(&self) -> SystemTime {
-        self.last_refreshed + Duration::from_secs(self.last_response.expires_in)
+    /// Get the expiry time of the authorization, if it is known
+    ///
+    /// The expiry is determined relative to the server clock and converted to the local clock,
+    /// so the local clock being fast or slow does not cause early or late refreshes.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        let expires_in = self.last_response.expires_in?;
+        Some(self.server_to_local(self.last_refreshed + Duration::from_secs(expires_in)))
+    }
+
+    /// Get the time remaining until the authorization expires
+    ///
+    /// This is `None` if the authorization has expired or its expiry is not known.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.remaining_at(SystemTime::now())
+    }
+
+    /// Get the time at which the authorization was last issued or refreshed
+    pub fn last_refreshed(&self) -> SystemTime {
+        self.server_to_local(self.last_refreshed)
     }
 
     /// Check if the authorization needs refreshing
+    ///
+    /// An authorization with an unknown expiry never needs refreshing.
+    ///
+    /// This is intended to guard calls to [`refresh`](#method.refresh), which will reset the
+    /// expiry so that this returns `false` again.
     pub fn should_refresh(&self) -> bool {
-        self.expires_at() + Self::REFRESH_WINDOW > SystemTime::now()
+        self.should_refresh_at(SystemTime::now())
+    }
+
+    /// Check if the authorization has already expired
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Get the time remaining from a given time until the authorization expires
+    fn remaining_at(&self, now: SystemTime) -> Option<Duration> {
+        let remaining = self.expires_at()?.duration_since(now).ok()?;
+        Some(remaining).filter(|remaining| *remaining > Duration::ZERO)
+    }
+
+    /// Check if the authorization expires within the refresh window of a given time
+    fn should_refresh_at(&self, now: SystemTime) -> bool {
+        match self.expires_at() {
+            Some(expires_at) => now + Self::REFRESH_WINDOW >= expires_at,
+            None => false,
+        }
+    }
+
+    /// Check if the authorization has expired at a given time
+    fn is_expired_at(&self, now: SystemTime) -> bool {
+        match self.expires_at() {
+            Some(expires_at) => now >= expires_at,
+            None => false,
+        }
+    }
+
+    /// Construct an authorization refreshed at a given time according to the server
+    #[cfg(test)]
+    fn refreshed_at(refreshed: SystemTime, expires_in: Option<u64>, clock_skew: i64) -> Self {
+        let mut authorization = Authorization::from_tokens("access".to_owned(), None, expires_in);
+        authorization.last_refreshed = refreshed;
+        authorization.with_clock_skew(clock_skew)
     }
 }
 
-/// Authentication request
-#[derive(Default, Debug, Serialize)]
-struct Query<'c> {
-    #[serde(rename = "accessToken")]
-    #[serde(skip_serializing_if = "Option::is_some")]
-    access_token: Option<Token>,
-    password: &'c str,
-    #[serde(rename = "persistLogin")]
-    persist_login: bool,
-    username: &'c str,
+/// An authorization shared between several clients
+///
+/// A refresh performed through any client is visible to all of them, and clients that find the
+/// authorization needs refreshing at the same time will only refresh it once.
+#[derive(Clone)]
+pub struct SharedAuthorization {
+    shared: Arc<Shared>,
 }
 
-impl<'c> Query<'c> {
-    fn new(username: &'c str, password: &'c str) -> Self {
-        Query {
-            username,
-            password,
-            ..Default::default()
+struct Shared {
+    authorization: RwLock<Authorization>,
+    /// Endpoints of the authorization when it was shared
+    endpoints: Endpoints,
+    /// Hook invoked after each refresh
+    on_refresh: Mutex<Option<RefreshHook>>,
+}
+
+/// A function invoked with the state of an authorization after it has been refreshed
+type RefreshHook = Arc<dyn Fn(&Authorization) + Send + Sync>;
+
+impl SharedAuthorization {
+    /// Share an authorization
+    pub fn new(authorization: Authorization) -> Self {
+        let endpoints = authorization.endpoints().clone();
+        let authorization = RwLock::new(authorization);
+        SharedAuthorization {
+            shared: Arc::new(Shared {
+                authorization,
+                endpoints,
+                on_refresh: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Access the shared authorization
+    pub async fn read(&self) -> RwLockReadGuard<'_, Authorization> {
+        self.shared.authorization.read().await
+    }
+
+    /// Refresh the shared authorization if it needs refreshing
+    pub async fn refresh_if_needed_with(&self, client: &Client) -> Result<(), Error> {
+        self.bearer(client, None).await.map(drop)
+    }
+
+    /// Set a hook to invoke after each refresh of the authorization
+    ///
+    /// The hook is called with a copy of the refreshed authorization, such as to save it, after
+    /// the authorization has been released for use by other clients. It replaces any previously
+    /// set hook.
+    pub fn on_refresh(&self, hook: impl Fn(&Authorization) + Send + Sync + 'static) {
+        *self.shared.on_refresh.lock().unwrap() = Some(Arc::new(hook));
+    }
+
+    /// The endpoints of the authorization when it was shared
+    pub(crate) fn endpoints(&self) -> &Endpoints {
+        &self.shared.endpoints
+    }
+
+    /// Get the authorization header for the access token, refreshing it if necessary
+    ///
+    /// If the refresh token is rejected and credentials are available, the user is logged in
+    /// again instead.
+    pub(crate) async fn bearer(
+        &self,
+        client: &Client,
+        credentials: Option<&dyn CredentialProvider>,
+    ) -> Result<String, Error> {
+        self.refresh_where(client, credentials, Authorization::should_refresh).await
+    }
+
+    /// Get the authorization header for a new access token after the API rejected `rejected`
+    ///
+    /// The authorization is only refreshed if no other client has refreshed it since.
+    pub(crate) async fn bearer_after_rejecting(
+        &self,
+        client: &Client,
+        credentials: Option<&dyn CredentialProvider>,
+        rejected: &str,
+    ) -> Result<String, Error> {
+        let is_rejected = |authorization: &Authorization| {
+            authorization.bearer().map_or(true, |bearer| bearer == rejected)
+        };
+        self.refresh_where(client, credentials, is_rejected).await
+    }
+
+    /// Get the authorization header for the access token, refreshing it if `needs_refresh`
+    async fn refresh_where(
+        &self,
+        client: &Client,
+        credentials: Option<&dyn CredentialProvider>,
+        needs_refresh: impl Fn(&Authorization) -> bool,
+    ) -> Result<String, Error> {
+        {
+            let authorization = self.read().await;
+            if !needs_refresh(&authorization) {
+                return authorization.bearer();
+            }
+        }
+
+        // Another client may have refreshed the authorization while waiting for the lock
+        let mut authorization = self.shared.authorization.write().await;
+        if !needs_refresh(&authorization) {
+            return authorization.bearer();
+        }
+        match (authorization.refresh_with(client).await, credentials) {
+            (Ok(()), _) => {}
+            (Err(err), Some(credentials)) if AuthError::is_refresh_failure(&err) => {
+                authorization.reauthenticate_with(client, credentials).await?;
+            }
+            (Err(err), _) => return Err(err),
+        }
+        let bearer = authorization.bearer()?;
+
+        let hook = self.shared.on_refresh.lock().unwrap().clone();
+        if let Some(hook) = hook {
+            let refreshed = authorization.clone();
+            drop(authorization);
+            hook(&refreshed);
         }
+        Ok(bearer)
     }
 }
 
-/// Authentication response
+impl From<Authorization> for SharedAuthorization {
+    fn from(authorization: Authorization) -> Self {
+        SharedAuthorization::new(authorization)
+    }
+}
+
+/// A source of credentials with which a user can be logged in again
+///
+/// A client configured with a credential provider will use it to log in again if the refresh
+/// token has been rejected.
+#[async_trait]
+pub trait CredentialProvider: Send + Sync {
+    /// Get the username and password of the user
+    async fn credentials(&self) -> Result<(String, Secret), Error>;
+}
+
+/// A fixed username and password
+#[derive(Debug)]
+pub struct StaticCredentials {
+    username: String,
+    password: Secret,
+}
+
+impl StaticCredentials {
+    /// Always provide the given username and password
+    pub fn new(username: String, password: Secret) -> Self {
+        StaticCredentials { username, password }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for StaticCredentials {
+    async fn credentials(&self) -> Result<(String, Secret), Error> {
+        let password = Secret::new(self.password.expose().to_owned());
+        Ok((self.username.clone(), password))
+    }
+}
+
+/// Tokens exported for use by other tools
+///
+/// This serializes to a JSON object with the fields `access_token`, `refresh_token` and
+/// `expires_in`. The refresh token and expiry are omitted if they are not known.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTokens {
+    /// The access token to use as a bearer token
+    pub access_token: Token,
+    /// The token with which the access token is refreshed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<Token>,
+    /// The number of seconds remaining until the access token expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+}
+
+impl From<ExportedTokens> for Authorization {
+    fn from(tokens: ExportedTokens) -> Self {
+        Authorization::from_tokens(
+            tokens.access_token.expose().to_owned(),
+            tokens.refresh_token.map(|token| token.expose().to_owned()),
+            tokens.expires_in,
+        )
+    }
+}
+
+/// The persisted form of an authorization
+///
+/// Unlike [`Authorization`](struct.Authorization.html), this format is versioned so that
+/// authorizations saved by one release of this crate can be loaded by later releases.
 #[derive(Debug, Serialize, Deserialize)]
-struct Response {
-    /// The type of token being used
+pub struct StoredAuthorization {
+    /// Version of the format
+    version: u32,
+    /// Seconds since the Unix epoch at which the authorization was last refreshed
+    last_refreshed: u64,
     token_type: TokenType,
-    /// Number of seconds until the token expires and should be refreshed
-    expires_in: u64,
-    /// The actual authorization token
+    expires_in: Option<u64>,
     access_token: Token,
-    /// A token that can be used to refresh the authorization
-    refresh_token: Token,
-    #[serde(rename = "persistLogin")]
+    refresh_token: Option<Token>,
     persist_login: bool,
+    #[serde(default)]
+    account: AccountInfo,
+    /// Seconds by which the server clock was ahead of the local clock when last refreshed
+    #[serde(default)]
+    clock_skew: i64,
 }
 
-/// A descriptor of the manner in which the token should be used
-#[derive(Debug, Serialize, Deserialize)]
-enum TokenType {
-    /// The token should be applied using a 'Bearer' header
-    Bearer,
+impl StoredAuthorization {
+    /// The latest version of the format
+    const VERSION: u32 = 1;
+
+    /// Deserialize a stored authorization, checking its version before its contents
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } = Version::deserialize(&value)?;
+        if version > Self::VERSION {
+            return Err(AuthError::UnsupportedVersion(version).into());
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// The version of the format
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn check_version(&self) -> Result<(), AuthError> {
+        match self.version {
+            1 => Ok(()),
+            version => Err(AuthError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Options controlling how a user is authenticated
+#[derive(Debug, Clone)]
+pub struct AuthOptions {
+    /// Request a persistent session, as with the 'remember me' option of the web portal
+    pub persist_login: bool,
+    /// Endpoints against which to authenticate
+    pub endpoints: Endpoints,
+    /// Number of times to retry after failing to connect or timing out
+    ///
+    /// Logins rejected by the server are never retried.
+    pub retries: u32,
+    /// Delay before the first retry, doubling with each subsequent retry
+    pub backoff: Duration,
+    /// Time after which to give up on the login, including any retries
+    pub timeout: Duration,
+}
+
+impl AuthOptions {
+    fn backoff(&self) -> Backoff {
+        Backoff {
+            retries: self.retries,
+            delay: self.backoff,
+        }
+    }
+}
+
+impl Default for AuthOptions {
+    fn default() -> Self {
+        AuthOptions {
+            persist_login: false,
+            endpoints: Endpoints::default(),
+            retries: 0,
+            backoff: Duration::from_secs(1),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The outcome of authenticating a user
+#[derive(Debug)]
+pub enum Login {
+    /// The user has been authorized
+    Authorized(Authorization),
+    /// The user must provide a one-time password to complete the login
+    OtpRequired(OtpChallenge),
+}
+
+/// A request for a one-time password to complete a login
+#[derive(Debug)]
+pub struct OtpChallenge {
+    client: Client,
+    endpoints: Endpoints,
+    challenge: Challenge,
+    completed: bool,
+}
+
+impl OtpChallenge {
+    fn new(client: Client, endpoints: Endpoints, challenge: Challenge) -> Self {
+        OtpChallenge {
+            client,
+            endpoints,
+            challenge,
+            completed: false,
+        }
+    }
+
+    /// The message describing how the one-time password was delivered, if any
+    pub fn message(&self) -> Option<&str> {
+        self.challenge.message()
+    }
+
+    /// Complete the login with a one-time password
+    ///
+    /// An incorrect code fails with [`AuthError::InvalidOtp`] and may be retried. Once the login
+    /// has been completed the challenge can not be submitted again.
+    ///
+    /// [`AuthError::InvalidOtp`]: enum.AuthError.html#variant.InvalidOtp
+    pub async fn submit(&mut self, code: &str) -> Result<Authorization, Error> {
+        if self.completed {
+            return Err(AuthError::ChallengeCompleted.into());
+        }
+
+        let query = self.challenge.body(code)?;
+
+        let endpoints = &self.endpoints;
+        let response = Authorization::portal_request(&self.client, endpoints, &endpoints.otp)
+            .body(query)
+            .send()
+            .await?;
+
+        let issued = Authorization::issued(response.headers());
+        let body = Body::read(response).await?;
+        let authorization = self.challenge.parse(issued, &body, self.endpoints.clone())?;
+        self.completed = true;
+        Ok(authorization)
+    }
+}
+
+/// A login that has been exchanged but may not yet have been completed
+pub(crate) enum Exchanged {
+    Authorized(Box<Authorization>),
+    OtpRequired(Challenge),
+}
+
+/// Authentication request
+#[derive(Default, Debug, Serialize)]
+struct Query<'c> {
+    #[serde(rename = "accessToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_token: Option<&'c Token>,
+    password: &'c str,
+    #[serde(rename = "persistLogin")]
+    persist_login: bool,
+    username: &'c str,
+}
+
+impl<'c> Query<'c> {
+    fn new(username: &'c str, password: &'c str) -> Self {
+        Query {
+            username,
+            password,
+            ..Default::default()
+        }
+    }
+
+    fn with_token(username: &'c str, access_token: &'c Token) -> Self {
+        Query {
+            username,
+            access_token: Some(access_token),
+            ..Default::default()
+        }
+    }
+}
+
+/// Token refresh request
+#[derive(Debug, Serialize)]
+struct RefreshQuery<'c> {
+    #[serde(rename = "refreshToken")]
+    refresh_token: &'c Token,
+}
+
+impl<'c> RefreshQuery<'c> {
+    fn new(refresh_token: &'c Token) -> Self {
+        RefreshQuery { refresh_token }
+    }
+}
+
+/// One-time password request
+#[derive(Debug, Serialize)]
+struct OtpQuery<'c> {
+    #[serde(rename = "otpToken")]
+    otp_token: &'c Token,
+    code: &'c str,
+}
+
+impl<'c> OtpQuery<'c> {
+    fn new(otp_token: &'c Token, code: &'c str) -> Self {
+        OtpQuery { otp_token, code }
+    }
+}
+
+/// Response to a login request
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LoginResponse {
+    Authorized(Response),
+    OtpRequired(Challenge),
+}
+
+/// Login response requesting a one-time password
+#[derive(Debug, Deserialize)]
+pub(crate) struct Challenge {
+    /// Token identifying the login being completed
+    #[serde(rename = "otpToken")]
+    otp_token: Token,
+    /// Description of how the one-time password was delivered
+    #[serde(default)]
+    message: Option<String>,
+}
+
+impl Challenge {
+    /// The message describing how the one-time password was delivered, if any
+    pub(crate) fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Serialize a request to complete the login with a one-time password
+    pub(crate) fn body(&self, code: &str) -> Result<String, Error> {
+        Ok(serde_json::to_string(&OtpQuery::new(&self.otp_token, code))?)
+    }
+
+    /// Interpret the response to a one-time password issued at the given time
+    pub(crate) fn parse(
+        &self,
+        (refreshed, clock_skew): (SystemTime, i64),
+        body: &Body,
+        endpoints: Endpoints,
+    ) -> Result<Authorization, Error> {
+        match body.status {
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED => {
+                return Err(AuthError::InvalidOtp.into());
+            }
+            status if !status.is_success() => {
+                return Err(Error::from_response(status, body.text().into_owned()));
+            }
+            _ => {}
+        }
+
+        let response = body.parse()?;
+        let authorization = Authorization::new(response, refreshed, endpoints);
+        Ok(authorization.with_clock_skew(clock_skew))
+    }
+}
+
+/// Authentication response
+#[derive(Clone, Serialize, Deserialize)]
+struct Response {
+    /// The type of token being used
+    token_type: TokenType,
+    /// Number of seconds until the token expires and should be refreshed
+    expires_in: Option<u64>,
+    /// The actual authorization token
+    access_token: Token,
+    /// A token that can be used to refresh the authorization
+    refresh_token: Option<Token>,
+    #[serde(rename = "persistLogin")]
+    persist_login: bool,
+    /// Identity of the account that has been authorized
+    #[serde(flatten)]
+    account: AccountInfo,
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("token_type", &self.token_type)
+            .field("expires_in", &self.expires_in)
+            .field("access_token", &self.access_token)
+            .field("refresh_token", &self.refresh_token.as_ref().map(|_| Redacted))
+            .field("persist_login", &self.persist_login)
+            .field("account", &self.account)
+            .finish()
+    }
+}
+
+/// The identity of an authorized account
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    #[serde(default, deserialize_with = "string_or_number")]
+    customer_id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl AccountInfo {
+    /// The identifier of the customer
+    pub fn customer_id(&self) -> Option<&str> {
+        self.customer_id.as_deref()
+    }
+
+    /// The display name of the customer
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The email address of the customer
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// Other details of the account included with the authorization
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.rest
+    }
+
+    fn is_empty(&self) -> bool {
+        self.customer_id.is_none()
+            && self.name.is_none()
+            && self.email.is_none()
+            && self.rest.is_empty()
+    }
+}
+
+/// Deserialize an identifier that may be given as either a string or a number
+fn string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        String(String),
+        Number(u64),
+    }
+
+    let id: Option<Id> = Deserialize::deserialize(deserializer)?;
+    Ok(id.map(|id| match id {
+        Id::String(id) => id,
+        Id::Number(id) => id.to_string(),
+    }))
+}
+
+/// Authorization saved in the credential store of the operating system
+#[cfg(feature = "keyring")]
+#[derive(Serialize, Deserialize)]
+struct KeyringEntry {
+    refresh_token: Token,
+    last_refreshed: SystemTime,
+    expires_in: Option<u64>,
+    persist_login: bool,
+}
+
+/// Authentication failure response
+#[derive(Debug, Deserialize)]
+struct Failure {
+    /// Description of the failure
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// A descriptor of the manner in which the token should be used
+///
+/// Token types are matched without regard to case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+enum TokenType {
+    /// The token should be applied using a 'Bearer' header
+    Bearer,
+    /// A type of token that can not be applied to requests
+    Other(String),
+}
+
+impl From<String> for TokenType {
+    fn from(token_type: String) -> Self {
+        if token_type.eq_ignore_ascii_case("Bearer") {
+            TokenType::Bearer
+        } else {
+            TokenType::Other(token_type)
+        }
+    }
+}
+
+impl From<TokenType> for String {
+    fn from(token_type: TokenType) -> String {
+        match token_type {
+            TokenType::Bearer => "Bearer".to_owned(),
+            TokenType::Other(token_type) => token_type,
+        }
+    }
 }
 
 /// An authorization token
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// The value of the token is redacted from its `Debug` output.
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(transparent)]
-struct Token(String);
+pub struct Token(String);
 
-impl fmt::Display for Token {
+impl Token {
+    /// Number of characters of a token that may be shown when debugging
+    const VISIBLE_PREFIX: usize = 8;
+
+    /// Wrap an existing token
+    pub fn new(token: String) -> Self {
+        Token(token)
+    }
+
+    /// Get the secret value of the token
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Token {
+    fn from(token: String) -> Self {
+        Token(token)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Token {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&self.0, f)
+        // Short tokens would be mostly revealed by their prefix
+        if self.0.chars().count() > 2 * Self::VISIBLE_PREFIX {
+            let prefix: String = self.0.chars().take(Self::VISIBLE_PREFIX).collect();
+            write!(f, "Token(\"{}…{:?}\")", prefix, Redacted)
+        } else {
+            write!(f, "Token(\"{:?}\")", Redacted)
+        }
+    }
+}
+
+/// A secret, such as a password, that is wiped from memory when dropped
+///
+/// Memory is only wiped when the `zeroize` feature is enabled.
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap an existing secret
+    pub fn new(secret: String) -> Self {
+        Secret(secret)
+    }
+
+    /// Get the value of the secret
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret(secret)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret({:?})", Redacted)
+    }
+}
+
+/// Placeholder for secret values in debugging output
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+/// Errors arising from authenticating to the API
+#[derive(Debug)]
+pub enum AuthError {
+    /// The refresh token was rejected and the user must authenticate again
+    RefreshRejected,
+    /// The authorization has no refresh token with which to refresh it
+    NoRefreshToken,
+    /// A stored authorization has expired and the user must authenticate again
+    StoredTokenExpired,
+    /// The one-time password was not accepted
+    InvalidOtp,
+    /// The one-time password challenge has already been completed
+    ChallengeCompleted,
+    /// The username or password was not accepted
+    InvalidCredentials,
+    /// The account has been locked and can not be used to log in
+    AccountLocked,
+    /// Authentication failed for some other reason
+    Unexpected(String),
+    /// The environment variables needed to authenticate were not set
+    MissingEnvironment(Vec<&'static str>),
+    /// The access token is of a type that can not be applied to requests
+    UnsupportedTokenType(String),
+    /// A stored authorization uses a version of the format that is not supported
+    UnsupportedVersion(u32),
+    /// The login requires a one-time password, which could not be provided
+    OtpRequired,
+    /// The login did not complete within the given time
+    Timeout(Duration),
+    /// The netrc file does not exist
+    NetrcNotFound(PathBuf),
+    /// The netrc file has no login and password for the machine
+    NetrcMissingMachine(String),
+    /// The netrc file is accessible by other users
+    NetrcInsecure(PathBuf),
+}
+
+impl AuthError {
+    /// Check if an error indicates the authorization can not be refreshed
+    fn is_refresh_failure(err: &Error) -> bool {
+        matches!(
+            err,
+            Error::Auth(AuthError::RefreshRejected) | Error::Auth(AuthError::NoRefreshToken)
+        )
+    }
+
+    /// Interpret the body of a failed authentication response
+    fn from_failure(status: StatusCode, body: &str) -> Self {
+        let message = serde_json::from_str::<Failure>(body)
+            .ok()
+            .and_then(|failure| failure.message);
+        let reason = message.as_deref().unwrap_or_default().to_lowercase();
+
+        if status == StatusCode::LOCKED || reason.contains("locked") {
+            AuthError::AccountLocked
+        } else if status == StatusCode::UNAUTHORIZED
+            || reason.contains("invalid")
+            || reason.contains("incorrect")
+        {
+            AuthError::InvalidCredentials
+        } else {
+            AuthError::Unexpected(message.unwrap_or_else(|| format!("{}: {}", status, body)))
+        }
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthError::RefreshRejected => write!(f, "refresh token was rejected"),
+            AuthError::NoRefreshToken => write!(f, "no refresh token available"),
+            AuthError::StoredTokenExpired => write!(f, "stored authorization has expired"),
+            AuthError::InvalidOtp => write!(f, "invalid one-time password"),
+            AuthError::ChallengeCompleted => write!(f, "login has already been completed"),
+            AuthError::InvalidCredentials => write!(f, "invalid username or password"),
+            AuthError::AccountLocked => write!(f, "account is locked"),
+            AuthError::Unexpected(message) => write!(f, "authentication failed: {}", message),
+            AuthError::MissingEnvironment(missing) => {
+                write!(f, "missing environment variables: {}", missing.join(", "))
+            }
+            AuthError::UnsupportedTokenType(token_type) => {
+                write!(f, "unsupported token type: {}", token_type)
+            }
+            AuthError::OtpRequired => write!(f, "a one-time password is required"),
+            AuthError::UnsupportedVersion(version) => {
+                write!(f, "unsupported stored authorization version: {}", version)
+            }
+            AuthError::Timeout(timeout) => {
+                write!(f, "login timed out after {} seconds", timeout.as_secs_f64())
+            }
+            AuthError::NetrcNotFound(path) => {
+                write!(f, "netrc file not found: {}", path.display())
+            }
+            AuthError::NetrcMissingMachine(machine) => {
+                write!(f, "no credentials in netrc file for machine: {}", machine)
+            }
+            AuthError::NetrcInsecure(path) => {
+                write!(f, "netrc file is accessible by other users: {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use reqwest::Method;
+
+    const REFRESH_PATH: &str = "/api/auth/postRefreshToken";
+
+    /// An authorization issued by a mock server, with the given refresh token
+    fn issued_by(server: &MockServer, refresh_token: &str) -> Authorization {
+        let tokens = ("old-access".to_owned(), Some(refresh_token.to_owned()), Some(3600));
+        let mut authorization = Authorization::from_tokens(tokens.0, tokens.1, tokens.2);
+        authorization.set_endpoints(server.endpoints());
+        authorization
+    }
+
+    /// An arbitrary time at which tokens are issued
+    fn issued() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_600_000_000)
+    }
+
+    #[test]
+    fn refresh_window_boundaries() {
+        let authorization = Authorization::refreshed_at(issued(), Some(3600), 0);
+        let window_opens = issued() + Duration::from_secs(3600) - Authorization::REFRESH_WINDOW;
+        let second = Duration::from_secs(1);
+
+        assert!(!authorization.should_refresh_at(issued()));
+        assert!(!authorization.should_refresh_at(window_opens - second));
+        assert!(authorization.should_refresh_at(window_opens));
+        assert!(authorization.should_refresh_at(window_opens + second));
+    }
+
+    #[test]
+    fn expiry_boundaries() {
+        let authorization = Authorization::refreshed_at(issued(), Some(3600), 0);
+        let expiry = issued() + Duration::from_secs(3600);
+        let second = Duration::from_secs(1);
+
+        assert_eq!(authorization.expires_at(), Some(expiry));
+        assert!(!authorization.is_expired_at(expiry - second));
+        assert!(authorization.is_expired_at(expiry));
+        assert!(authorization.is_expired_at(expiry + second));
+        assert_eq!(authorization.remaining_at(expiry - second), Some(second));
+        assert_eq!(authorization.remaining_at(expiry), None);
+    }
+
+    #[test]
+    fn expiry_accounts_for_clock_skew() {
+        // The server clock is a minute ahead, so the token expires a minute earlier locally
+        let authorization = Authorization::refreshed_at(issued(), Some(3600), 60);
+        let expiry = issued() + Duration::from_secs(3600 - 60);
+        let second = Duration::from_secs(1);
+
+        assert_eq!(authorization.expires_at(), Some(expiry));
+        assert!(!authorization.is_expired_at(expiry - second));
+        assert!(authorization.is_expired_at(expiry));
+        assert!(!authorization.should_refresh_at(expiry - Authorization::REFRESH_WINDOW - second));
+        assert!(authorization.should_refresh_at(expiry - Authorization::REFRESH_WINDOW));
+
+        // The server clock is a minute behind, so the token expires a minute later locally
+        let authorization = Authorization::refreshed_at(issued(), Some(3600), -60);
+        let expiry = issued() + Duration::from_secs(3600 + 60);
+
+        assert_eq!(authorization.expires_at(), Some(expiry));
+        assert!(!authorization.is_expired_at(expiry - second));
+        assert!(authorization.is_expired_at(expiry));
+    }
+
+    #[test]
+    fn extreme_clock_skew_does_not_overflow() {
+        // The server clock is so far behind that the expiry can't be represented locally
+        let authorization = Authorization::refreshed_at(issued(), Some(3600), i64::MIN);
+        let expiry = issued() + Duration::from_secs(3600);
+        assert_eq!(authorization.expires_at(), Some(expiry));
+        assert!(authorization.is_expired_at(expiry));
+        assert!(authorization.should_refresh_at(expiry));
+
+        // The server clock is so far ahead that the token expired long ago
+        let authorization = Authorization::refreshed_at(issued(), Some(3600), i64::MAX);
+        assert!(authorization.expires_at().unwrap() < issued());
+        assert!(authorization.is_expired_at(issued()));
+        assert!(authorization.should_refresh_at(issued()));
+    }
+
+    /// Headers of a response from a server with a clock `skew` seconds ahead of the local clock
+    fn headers_skewed_by(skew: i64) -> HeaderMap {
+        let date = chrono::Utc::now() + chrono::Duration::seconds(skew);
+        let mut headers = HeaderMap::new();
+        headers.insert(DATE, HeaderValue::from_str(&date.to_rfc2822()).unwrap());
+        headers
+    }
+
+    #[test]
+    fn clock_skew_is_measured_from_date_header() {
+        for &skew in [5 * 60, -5 * 60, 0].iter() {
+            let (issued, clock_skew) = Authorization::issued(&headers_skewed_by(skew));
+            assert!((clock_skew - skew).abs() <= 1, "{} measured as {}", skew, clock_skew);
+
+            // The token is valid for an hour by the local clock regardless of the skew
+            let authorization = Authorization::refreshed_at(issued, Some(3600), clock_skew);
+            let remaining = authorization.remaining().unwrap();
+            assert!(remaining > Duration::from_secs(3598), "{}: {:?}", skew, remaining);
+            assert!(remaining <= Duration::from_secs(3600), "{}: {:?}", skew, remaining);
+            assert!(!authorization.should_refresh());
+        }
+    }
+
+    #[test]
+    fn missing_date_header_assumes_no_skew() {
+        let (issued, clock_skew) = Authorization::issued(&HeaderMap::new());
+        assert_eq!(clock_skew, 0);
+        assert!(SystemTime::now().duration_since(issued).unwrap() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn unknown_expiry_never_expires() {
+        let authorization = Authorization::refreshed_at(issued(), None, 0);
+        let later = issued() + Duration::from_secs(365 * 24 * 60 * 60);
+
+        assert_eq!(authorization.expires_at(), None);
+        assert!(!authorization.should_refresh_at(later));
+        assert!(!authorization.is_expired_at(later));
+    }
+
+    /// The current time, to a whole second as times are saved
+    fn now() -> SystemTime {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        UNIX_EPOCH + Duration::from_secs(now.as_secs())
+    }
+
+    #[test]
+    fn save_and_load_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("authorization.json");
+        let authorization = Authorization::refreshed_at(now(), Some(3600), 30);
+
+        authorization.save_to(&path).unwrap();
+        let loaded = Authorization::load_from(&path).unwrap();
+
+        assert_eq!(loaded.last_refreshed, authorization.last_refreshed);
+        assert_eq!(loaded.last_refreshed(), authorization.last_refreshed());
+        assert_eq!(loaded.expires_at(), authorization.expires_at());
+        assert_eq!(loaded.clock_skew, 30);
+        assert_eq!(loaded.access_token().expose(), "access");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saved_file_is_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("authorization.json");
+        File::create(&path).unwrap().set_permissions(Permissions::from_mode(0o644)).unwrap();
+
+        Authorization::refreshed_at(now(), Some(3600), 0).save_to(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    /// The path of a fixture for a stored authorization
+    fn stored_fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/auth").join(name)
+    }
+
+    #[test]
+    fn stored_fixtures_keep_loading() {
+        let stored = fs::read_to_string(stored_fixture("stored-v1.json")).unwrap();
+        let stored = StoredAuthorization::from_value(serde_json::from_str(&stored).unwrap());
+        let stored = stored.unwrap();
+        assert_eq!(stored.version(), 1);
+
+        let authorization = Authorization::from_stored(stored).unwrap();
+        let refreshed = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        assert_eq!(authorization.last_refreshed, refreshed);
+        assert_eq!(authorization.clock_skew, -5);
+        assert_eq!(authorization.expires_in(), Some(3600));
+        assert_eq!(authorization.access_token().expose(), "stored-access");
+        assert_eq!(authorization.refresh_token().unwrap().expose(), "stored-refresh");
+        assert!(authorization.is_persistent());
+        assert_eq!(authorization.account().customer_id(), Some("1234567"));
+        assert_eq!(authorization.account().email(), Some("jane@example.com"));
+
+        // The stored access token expired long ago
+        let err = Authorization::load_from(stored_fixture("stored-v1.json")).unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::StoredTokenExpired)), "{:?}", err);
+    }
+
+    #[test]
+    fn unversioned_fixture_keeps_loading() {
+        let authorization = Authorization::load_from(stored_fixture("unversioned.json")).unwrap();
+        let refreshed = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+
+        assert_eq!(authorization.last_refreshed, refreshed);
+        assert_eq!(authorization.clock_skew, 0);
+        assert_eq!(authorization.expires_in(), None);
+        assert_eq!(authorization.access_token().expose(), "legacy-access");
+        assert_eq!(authorization.refresh_token().unwrap().expose(), "legacy-refresh");
+    }
+
+    #[test]
+    fn future_fixture_is_unsupported() {
+        let err = Authorization::load_from(stored_fixture("stored-v2.json")).unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::UnsupportedVersion(2))), "{:?}", err);
+    }
+
+    #[test]
+    fn loading_expired_authorization_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("authorization.json");
+        let refreshed = now() - Duration::from_secs(2 * 3600);
+
+        Authorization::refreshed_at(refreshed, Some(3600), 0).save_to(&path).unwrap();
+        let err = Authorization::load_from(&path).unwrap_err();
+
+        assert!(matches!(err, Error::Auth(AuthError::StoredTokenExpired)), "{:?}", err);
+    }
+
+    #[test]
+    fn failures_are_classified() {
+        let cases = [
+            (400, r#"{"message": "Invalid username or password"}"#, "invalid"),
+            (400, r#"{"message": "Incorrect password"}"#, "invalid"),
+            (401, r#"{}"#, "invalid"),
+            (423, r#"{}"#, "locked"),
+            (403, r#"{"message": "Your account has been locked"}"#, "locked"),
+        ];
+        for (status, body, expected) in cases.iter() {
+            let status = StatusCode::from_u16(*status).unwrap();
+            let err = AuthError::from_failure(status, body);
+            match (*expected, &err) {
+                ("invalid", AuthError::InvalidCredentials) => {}
+                ("locked", AuthError::AccountLocked) => {}
+                _ => panic!("{} {}: unexpected {:?}", status, body, err),
+            }
+        }
+    }
+
+    #[test]
+    fn unexpected_failures_keep_their_message() {
+        let body = r#"{"message": "Service unavailable"}"#;
+        let err = AuthError::from_failure(StatusCode::SERVICE_UNAVAILABLE, body);
+        let expected = "Service unavailable";
+        assert!(matches!(&err, AuthError::Unexpected(m) if m == expected), "{:?}", err);
+
+        let err = AuthError::from_failure(StatusCode::INTERNAL_SERVER_ERROR, "oops");
+        let expected = "500 Internal Server Error: oops";
+        assert!(matches!(&err, AuthError::Unexpected(m) if m == expected), "{:?}", err);
+    }
+
+    #[test]
+    fn token_types_are_matched_without_case() {
+        let parse = |text: &str| serde_json::from_value(Value::from(text)).unwrap();
+
+        assert!(matches!(parse("Bearer"), TokenType::Bearer));
+        assert!(matches!(parse("bearer"), TokenType::Bearer));
+        assert!(matches!(parse("MAC"), TokenType::Other(ref other) if other == "MAC"));
+
+        let round_trip = |text: &str| serde_json::to_value(parse(text)).unwrap();
+        assert_eq!(round_trip("bearer"), "Bearer");
+        assert_eq!(round_trip("MAC"), "MAC");
+    }
+
+    #[test]
+    fn debug_redacts_tokens() {
+        let long = Token::new("abcdefgh-secret-remainder-of-token".to_owned());
+        assert_eq!(format!("{:?}", long), "Token(\"abcdefgh…<redacted>\")");
+
+        let short = Token::new("short-token".to_owned());
+        assert_eq!(format!("{:?}", short), "Token(\"<redacted>\")");
+
+        let authorization = Authorization::from_tokens(
+            "access-token-that-is-long".to_owned(),
+            Some("refresh-token-that-is-long".to_owned()),
+            Some(3600),
+        );
+        let debug = format!("{:?}", authorization);
+        assert!(!debug.contains("access-token-that-is-long"), "{}", debug);
+        assert!(!debug.contains("refresh-token"), "{}", debug);
+        assert!(!format!("{:?}", Secret::new("hunter2".to_owned())).contains("hunter2"));
+    }
+
+    const LOGIN_PATH: &str = "/api/auth/postLogin";
+    const OTP_PATH: &str = "/api/auth/postLoginOtp";
+
+    const AUTHORIZED: &str = r#"{
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "access_token": "new-access",
+        "refresh_token": "new-refresh",
+        "persistLogin": false
+    }"#;
+
+    #[test]
+    fn otp_challenge_is_deserialized() {
+        let body = r#"{"otpToken": "otp-token", "message": "Code sent to 04xx xxx 123"}"#;
+        match serde_json::from_str(body).unwrap() {
+            LoginResponse::OtpRequired(challenge) => {
+                assert_eq!(challenge.otp_token.expose(), "otp-token");
+                assert_eq!(challenge.message(), Some("Code sent to 04xx xxx 123"));
+            }
+            response => panic!("unexpected response: {:?}", response),
+        }
+
+        match serde_json::from_str(AUTHORIZED).unwrap() {
+            LoginResponse::Authorized(_) => {}
+            response => panic!("unexpected response: {:?}", response),
+        }
+    }
+
+    #[tokio::test]
+    async fn otp_challenge_retries_wrong_code_and_completes_once() {
+        let server = MockServer::start().await;
+        let challenge = r#"{"otpToken": "otp-token"}"#;
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, challenge));
+        let wrong = r#"{"message": "Invalid code"}"#;
+        server.respond(Method::POST, OTP_PATH, MockResponse::json(400, wrong));
+        server.respond(Method::POST, OTP_PATH, MockResponse::json(200, AUTHORIZED));
+
+        let client = Client::new();
+        let login = Authorization::authenticate_with_endpoints(
+            &client,
+            server.endpoints(),
+            "user",
+            "password",
+        );
+        let mut challenge = match login.await.unwrap() {
+            Login::OtpRequired(challenge) => challenge,
+            login => panic!("unexpected login: {:?}", login),
+        };
+        assert_eq!(challenge.message(), None);
+
+        let err = challenge.submit("000000").await.unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::InvalidOtp)), "{:?}", err);
+
+        let authorization = challenge.submit("123456").await.unwrap();
+        assert_eq!(authorization.access_token().expose(), "new-access");
+
+        let err = challenge.submit("123456").await.unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::ChallengeCompleted)), "{:?}", err);
+
+        let received = server.received();
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[1].json()["otpToken"], "otp-token");
+        assert_eq!(received[1].json()["code"], "000000");
+        assert_eq!(received[2].json()["code"], "123456");
+    }
+
+    #[test]
+    fn query_serializes_access_token_only_when_present() {
+        let query = serde_json::to_value(Query::new("user", "pw")).unwrap();
+        let expected = serde_json::json!({
+            "password": "pw",
+            "persistLogin": false,
+            "username": "user",
+        });
+        assert_eq!(query, expected);
+
+        let token = Token::new("access".to_owned());
+        let query = serde_json::to_value(Query::with_token("user", &token)).unwrap();
+        let expected = serde_json::json!({
+            "accessToken": "access",
+            "password": "",
+            "persistLogin": false,
+            "username": "user",
+        });
+        assert_eq!(query, expected);
+    }
+
+    #[tokio::test]
+    async fn persistent_login_is_requested() {
+        let server = MockServer::start().await;
+        let body = AUTHORIZED.replace(r#""persistLogin": false"#, r#""persistLogin": true"#);
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, &body));
+
+        let options = AuthOptions {
+            persist_login: true,
+            endpoints: server.endpoints(),
+            ..Default::default()
+        };
+        let client = Client::new();
+        let login = Authorization::authenticate_opts_with(&client, "user", "pw", options);
+        let authorization = match login.await.unwrap() {
+            Login::Authorized(authorization) => authorization,
+            login => panic!("unexpected login: {:?}", login),
+        };
+
+        assert!(authorization.is_persistent());
+        assert_eq!(server.received()[0].json()["persistLogin"], true);
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_are_shared() {
+        let server = MockServer::start().await;
+        let response = MockResponse::json(200, AUTHORIZED).delay(Duration::from_millis(100));
+        server.respond(Method::POST, REFRESH_PATH, response);
+
+        // Expiring within the refresh window
+        let mut authorization = Authorization::from_tokens(
+            "old-access".to_owned(),
+            Some("old-refresh".to_owned()),
+            Some(60),
+        );
+        authorization.set_endpoints(server.endpoints());
+        let shared = SharedAuthorization::new(authorization);
+        let refreshes = Arc::new(Mutex::new(0));
+        let counted = refreshes.clone();
+        shared.on_refresh(move |_| *counted.lock().unwrap() += 1);
+
+        let client = Client::new();
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let shared = shared.clone();
+                let client = client.clone();
+                tokio::spawn(async move { shared.bearer(&client, None).await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), "Bearer new-access");
+        }
+
+        assert_eq!(server.received().len(), 1);
+        assert_eq!(*refreshes.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn refresh_replaces_tokens() {
+        let server = MockServer::start().await;
+        let body = r#"{
+            "token_type": "Bearer",
+            "expires_in": 7200,
+            "access_token": "new-access",
+            "refresh_token": "new-refresh",
+            "persistLogin": false
+        }"#;
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, body));
+
+        let mut authorization = issued_by(&server, "old-refresh");
+        authorization.refresh_with(&Client::new()).await.unwrap();
+
+        assert_eq!(authorization.access_token().expose(), "new-access");
+        assert_eq!(authorization.refresh_token().unwrap().expose(), "new-refresh");
+        assert_eq!(authorization.expires_in(), Some(7200));
+        assert!(!authorization.should_refresh());
+
+        let received = server.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].json()["refreshToken"], "old-refresh");
+    }
+
+    #[tokio::test]
+    async fn refresh_with_expired_refresh_token_is_rejected() {
+        let server = MockServer::start().await;
+        let body = r#"{"message": "Refresh token has expired"}"#;
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(401, body));
+
+        let mut authorization = issued_by(&server, "expired-refresh");
+        let err = authorization.refresh_with(&Client::new()).await.unwrap_err();
+
+        assert!(matches!(err, Error::Auth(AuthError::RefreshRejected)), "{:?}", err);
+        assert_eq!(authorization.access_token().expose(), "old-access");
+    }
+
+    #[tokio::test]
+    async fn refresh_with_malformed_response_fails_to_deserialize() {
+        let server = MockServer::start().await;
+        let body = r#"{"access_token": 42}"#;
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, body));
+
+        let mut authorization = issued_by(&server, "old-refresh");
+        let err = authorization.refresh_with(&Client::new()).await.unwrap_err();
+
+        match err {
+            Error::Deserialize { path, body: kept, .. } => {
+                assert_eq!(path, REFRESH_PATH);
+                assert_eq!(kept, body);
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert_eq!(authorization.access_token().expose(), "old-access");
+    }
+
+    const LOGOUT_PATH: &str = "/api/auth/postLogout";
+
+    #[tokio::test]
+    async fn revoke_sends_the_bearer_token_to_the_portal() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, LOGOUT_PATH, MockResponse::empty(204));
+
+        let authorization = issued_by(&server, "old-refresh");
+        authorization.revoke_with(&Client::new()).await.unwrap();
+
+        let received = server.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].path, LOGOUT_PATH);
+        assert_eq!(received[0].headers[AUTHORIZATION], "Bearer old-access");
+        assert_eq!(received[0].headers[ORIGIN], server.url().as_str());
+        assert_eq!(received[0].headers[REFERER], format!("{}/login", server.url()).as_str());
+        assert_eq!(received[0].headers[CONTENT_TYPE], "application/json");
+        assert!(received[0].body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failure_to_revoke_is_reported() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, LOGOUT_PATH, MockResponse::json(500, "{}"));
+
+        let authorization = issued_by(&server, "old-refresh");
+        let err = authorization.revoke_with(&Client::new()).await.unwrap_err();
+        assert!(matches!(err, Error::Http(_)), "{:?}", err);
+        assert_eq!(server.count(LOGOUT_PATH), 1);
+    }
+
+    #[tokio::test]
+    async fn client_logout_revokes_the_session() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, LOGOUT_PATH, MockResponse::empty(200));
+
+        let authorization = issued_by(&server, "old-refresh");
+        let client = crate::Client::builder(authorization).build().unwrap();
+        client.logout().await.unwrap();
+
+        let received = server.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].headers[AUTHORIZATION], "Bearer old-access");
+    }
+
+    /// Serializes tests that change the environment, which is shared by every test
+    async fn lock_environment() -> tokio::sync::MutexGuard<'static, ()> {
+        static ENVIRONMENT: std::sync::OnceLock<tokio::sync::Mutex<()>> =
+            std::sync::OnceLock::new();
+        ENVIRONMENT.get_or_init(Default::default).lock().await
+    }
+
+    /// Set the variables read by `from_env`, removing those that are not given
+    fn set_environment(access_token: Option<&str>, username: Option<&str>, password: Option<&str>) {
+        let vars = [
+            (Authorization::ACCESS_TOKEN_VAR, access_token),
+            (Authorization::USERNAME_VAR, username),
+            (Authorization::PASSWORD_VAR, password),
+        ];
+        for (name, value) in vars.iter() {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+    }
+
+    /// Authenticate from the environment against a mock server
+    async fn login_from_env(server: &MockServer) -> Result<Login, Error> {
+        let options = AuthOptions { endpoints: server.endpoints(), ..Default::default() };
+        Authorization::from_env_opts(options).await
+    }
+
+    #[tokio::test]
+    async fn access_token_is_taken_from_the_environment() {
+        let _environment = lock_environment().await;
+        let server = MockServer::start().await;
+        set_environment(Some("env-access"), Some("user"), Some("password"));
+
+        let login = login_from_env(&server).await.unwrap();
+        match login {
+            Login::Authorized(authorization) => {
+                assert_eq!(authorization.access_token().expose(), "env-access");
+                assert!(authorization.refresh_token().is_none());
+            }
+            login => panic!("unexpected login: {:?}", login),
+        }
+        assert!(server.received().is_empty());
+        set_environment(None, None, None);
+    }
+
+    #[tokio::test]
+    async fn credentials_are_taken_from_the_environment() {
+        let _environment = lock_environment().await;
+        let server = MockServer::start().await;
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, AUTHORIZED));
+        set_environment(Some(""), Some("env-user"), Some("env-password"));
+
+        let login = login_from_env(&server).await.unwrap();
+        assert!(matches!(login, Login::Authorized(_)), "{:?}", login);
+        let received = server.received();
+        assert_eq!(received[0].json()["username"], "env-user");
+        assert_eq!(received[0].json()["password"], "env-password");
+        set_environment(None, None, None);
+    }
+
+    #[tokio::test]
+    async fn missing_variables_are_named() {
+        let _environment = lock_environment().await;
+        let server = MockServer::start().await;
+        let cases = [
+            ((None, None, None), vec!["EXETEL_ACCESS_TOKEN", "EXETEL_USERNAME", "EXETEL_PASSWORD"]),
+            ((None, Some("user"), None), vec!["EXETEL_ACCESS_TOKEN", "EXETEL_PASSWORD"]),
+            ((None, None, Some("password")), vec!["EXETEL_ACCESS_TOKEN", "EXETEL_USERNAME"]),
+            ((Some(""), Some("user"), Some("")), vec!["EXETEL_ACCESS_TOKEN", "EXETEL_PASSWORD"]),
+        ];
+        for ((access_token, username, password), expected) in cases.iter() {
+            set_environment(*access_token, *username, *password);
+            let err = login_from_env(&server).await.unwrap_err();
+            match err {
+                Error::Auth(AuthError::MissingEnvironment(missing)) => {
+                    assert_eq!(&missing, expected)
+                }
+                err => panic!("unexpected error: {:?}", err),
+            }
+        }
+        assert!(server.received().is_empty());
+        set_environment(None, None, None);
+    }
+
+    /// A credential store kept in memory and shared by every entry, in place of that of the
+    /// operating system
+    #[cfg(feature = "keyring")]
+    mod memory_keyring {
+        use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+        use std::any::Any;
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex, Once};
+
+        /// A service whose entries always fail, as if the keyring were locked
+        pub(super) const LOCKED: &str = "locked-service";
+
+        type Store = Arc<Mutex<HashMap<(String, String), String>>>;
+
+        struct Builder(Store);
+
+        #[derive(Debug)]
+        struct Entry {
+            store: Store,
+            key: (String, String),
+        }
+
+        impl CredentialBuilderApi for Builder {
+            fn build(
+                &self,
+                _target: Option<&str>,
+                service: &str,
+                user: &str,
+            ) -> keyring::Result<Box<Credential>> {
+                let key = (service.to_owned(), user.to_owned());
+                Ok(Box::new(Entry { store: self.0.clone(), key }))
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+
+        impl Entry {
+            fn check(&self) -> keyring::Result<()> {
+                match self.key.0.as_str() {
+                    LOCKED => Err(keyring::Error::NoStorageAccess("keyring is locked".into())),
+                    _ => Ok(()),
+                }
+            }
+        }
+
+        impl CredentialApi for Entry {
+            fn set_password(&self, password: &str) -> keyring::Result<()> {
+                self.check()?;
+                self.store.lock().unwrap().insert(self.key.clone(), password.to_owned());
+                Ok(())
+            }
+
+            fn get_password(&self) -> keyring::Result<String> {
+                self.check()?;
+                let store = self.store.lock().unwrap();
+                store.get(&self.key).cloned().ok_or(keyring::Error::NoEntry)
+            }
+
+            fn delete_password(&self) -> keyring::Result<()> {
+                self.check()?;
+                let removed = self.store.lock().unwrap().remove(&self.key);
+                removed.map(drop).ok_or(keyring::Error::NoEntry)
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+
+        /// Use the store in memory for every entry created from now on
+        pub(super) fn install() {
+            static INSTALL: Once = Once::new();
+            INSTALL.call_once(|| {
+                let store = Store::default();
+                keyring::set_default_credential_builder(Box::new(Builder(store)));
+            });
+        }
+
+        /// Set the password of an entry directly
+        pub(super) fn set(service: &str, account: &str, password: &str) {
+            install();
+            keyring::Entry::new(service, account).unwrap().set_password(password).unwrap();
+        }
+
+        /// Get the password of an entry directly
+        pub(super) fn get(service: &str, account: &str) -> String {
+            install();
+            keyring::Entry::new(service, account).unwrap().get_password().unwrap()
+        }
+    }
+
+    #[cfg(feature = "keyring")]
+    #[tokio::test]
+    async fn keyring_keeps_only_the_refresh_token_and_refreshes_on_load() {
+        memory_keyring::install();
+        let server = MockServer::start().await;
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, AUTHORIZED));
+
+        let authorization = issued_by(&server, "kept-refresh");
+        authorization.save_to_keyring("exetel-test", "saved").unwrap();
+        let saved = memory_keyring::get("exetel-test", "saved");
+        assert!(saved.contains("kept-refresh"), "{}", saved);
+        assert!(!saved.contains("old-access"), "{}", saved);
+
+        let endpoints = server.endpoints();
+        let loaded = Authorization::load_from_keyring_at("exetel-test", "saved", endpoints);
+        let loaded = loaded.await.unwrap();
+        assert_eq!(loaded.access_token().expose(), "new-access");
+        assert_eq!(loaded.refresh_token().unwrap().expose(), "new-refresh");
+        assert_eq!(loaded.endpoints(), &server.endpoints());
+        assert_eq!(server.received()[0].json()["refreshToken"], "kept-refresh");
+    }
+
+    #[cfg(feature = "keyring")]
+    #[test]
+    fn keyring_needs_a_refresh_token() {
+        memory_keyring::install();
+        let authorization = Authorization::from_tokens("access".to_owned(), None, Some(3600));
+        let err = authorization.save_to_keyring("exetel-test", "no-refresh").unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::NoRefreshToken)), "{:?}", err);
+    }
+
+    #[cfg(feature = "keyring")]
+    #[tokio::test]
+    async fn keyring_failures_are_reported() {
+        memory_keyring::install();
+        let server = MockServer::start().await;
+        let load = |service, account| {
+            Authorization::load_from_keyring_at(service, account, server.endpoints())
+        };
+
+        let err = load("exetel-test", "missing").await.unwrap_err();
+        assert!(matches!(err, Error::Keyring(keyring::Error::NoEntry)), "{:?}", err);
+
+        let locked = memory_keyring::LOCKED;
+        let err = load(locked, "any").await.unwrap_err();
+        assert!(matches!(err, Error::Keyring(keyring::Error::NoStorageAccess(_))), "{:?}", err);
+        let authorization = issued_by(&server, "refresh");
+        let err = authorization.save_to_keyring(locked, "any").unwrap_err();
+        assert!(matches!(err, Error::Keyring(_)), "{:?}", err);
+
+        memory_keyring::set("exetel-test", "corrupt", "not json");
+        let err = load("exetel-test", "corrupt").await.unwrap_err();
+        assert!(matches!(err, Error::Json(_)), "{:?}", err);
+        assert!(server.received().is_empty());
+    }
+
+    #[tokio::test]
+    async fn concurrent_logins_share_a_client() {
+        let server = MockServer::start().await;
+        let delay = Duration::from_millis(200);
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, AUTHORIZED).delay(delay));
+
+        let client = Client::new();
+        let endpoints = server.endpoints();
+        let started = std::time::Instant::now();
+        let (first, second) = tokio::join!(
+            Authorization::authenticate_with_endpoints(&client, endpoints.clone(), "one", "pw"),
+            Authorization::authenticate_with_endpoints(&client, endpoints, "two", "pw"),
+        );
+        assert!(started.elapsed() < 2 * delay, "logins took {:?}", started.elapsed());
+
+        for login in [first, second].iter() {
+            match login {
+                Ok(Login::Authorized(authorization)) => {
+                    assert_eq!(authorization.access_token().expose(), "new-access");
+                }
+                login => panic!("unexpected login: {:?}", login),
+            }
+        }
+        let mut usernames: Vec<_> = server
+            .received()
+            .iter()
+            .map(|received| received.json()["username"].as_str().unwrap().to_owned())
+            .collect();
+        usernames.sort();
+        assert_eq!(usernames, ["one", "two"]);
+    }
+
+    #[tokio::test]
+    async fn account_is_read_from_the_login() {
+        let server = MockServer::start().await;
+        let login = fs::read_to_string(stored_fixture("login.json")).unwrap();
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, &login));
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, AUTHORIZED));
+
+        let client = Client::new();
+        let login = Authorization::authenticate_with_endpoints(
+            &client,
+            server.endpoints(),
+            "user",
+            "password",
+        );
+        let mut authorization = match login.await.unwrap() {
+            Login::Authorized(authorization) => authorization,
+            login => panic!("unexpected login: {:?}", login),
+        };
+        let account = authorization.account();
+        assert_eq!(account.customer_id(), Some("1234567"));
+        assert_eq!(account.name(), Some("Jane Citizen"));
+        assert_eq!(account.email(), Some("jane@example.com"));
+        assert_eq!(account.extra()["accountStatus"], "Active");
+        assert_eq!(account.extra()["isBusiness"], false);
+        assert_eq!(account.extra().len(), 2);
+
+        // A refresh without account details keeps those from the login
+        authorization.refresh().await.unwrap();
+        assert_eq!(authorization.access_token().expose(), "new-access");
+        assert_eq!(authorization.account().customer_id(), Some("1234567"));
+    }
+
+    /// Write a netrc file that only the current user can read
+    fn write_netrc(dir: &Path, contents: &str) -> PathBuf {
+        let path = dir.join("netrc");
+        fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        path
+    }
+
+    /// Authenticate from a netrc file against a mock server
+    async fn login_from_netrc(server: &MockServer, path: &Path) -> Result<Login, Error> {
+        let options = AuthOptions { endpoints: server.endpoints(), ..Default::default() };
+        Authorization::from_netrc_path_opts(path, Authorization::NETRC_MACHINE, options).await
+    }
+
+    #[tokio::test]
+    async fn credentials_are_taken_from_the_netrc_machine() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, AUTHORIZED));
+        let dir = tempfile::tempdir().unwrap();
+        let netrc = format!(
+            "machine example.com login other password wrong\n\
+             machine {} login user password secret\n\
+             default login anonymous password guest\n",
+            Authorization::NETRC_MACHINE,
+        );
+        let path = write_netrc(dir.path(), &netrc);
+
+        match login_from_netrc(&server, &path).await.unwrap() {
+            Login::Authorized(authorization) => {
+                assert_eq!(authorization.access_token().expose(), "new-access");
+            }
+            login => panic!("unexpected login: {:?}", login),
+        }
+        let received = server.received();
+        assert_eq!(received[0].json()["username"], "user");
+        assert_eq!(received[0].json()["password"], "secret");
+    }
+
+    #[tokio::test]
+    async fn netrc_default_is_used_without_a_machine() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, AUTHORIZED));
+        let dir = tempfile::tempdir().unwrap();
+        let netrc = "machine example.com login other password wrong\n\
+                     default login user password secret\n";
+        let path = write_netrc(dir.path(), netrc);
+
+        login_from_netrc(&server, &path).await.unwrap();
+        assert_eq!(server.received()[0].json()["username"], "user");
+    }
+
+    #[tokio::test]
+    async fn netrc_without_the_machine_is_reported() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_netrc(dir.path(), "machine example.com login other password wrong\n");
+
+        let err = login_from_netrc(&server, &path).await.unwrap_err();
+        let machine = Authorization::NETRC_MACHINE;
+        let missing = matches!(
+            &err,
+            Error::Auth(AuthError::NetrcMissingMachine(missing)) if missing == machine
+        );
+        assert!(missing, "{:?}", err);
+
+        let err = login_from_netrc(&server, &dir.path().join("missing")).await.unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::NetrcNotFound(_))), "{:?}", err);
+        assert!(server.received().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn netrc_readable_by_others_is_rejected() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let machine = Authorization::NETRC_MACHINE;
+        let netrc = format!("machine {} login user password secret\n", machine);
+        let path = write_netrc(dir.path(), &netrc);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let err = login_from_netrc(&server, &path).await.unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::NetrcInsecure(_))), "{:?}", err);
+        assert!(server.received().is_empty());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn auth_spans_record_no_secrets() {
+        use crate::mock::Capture;
+
+        let server = MockServer::start().await;
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, AUTHORIZED));
+        let refreshed = AUTHORIZED.replace("new-access", "refreshed-access");
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, &refreshed));
+        let capture = Capture::default();
+        let _subscriber = tracing::subscriber::set_default(capture.clone());
+
+        let client = Client::new();
+        let login = Authorization::authenticate_with_endpoints(
+            &client,
+            server.endpoints(),
+            "user",
+            "hunter2",
+        );
+        let mut authorization = match login.await.unwrap() {
+            Login::Authorized(authorization) => authorization,
+            login => panic!("unexpected login: {:?}", login),
+        };
+        authorization.refresh().await.unwrap();
+
+        let authenticate = capture.span("authenticate").unwrap();
+        assert_eq!(authenticate.field("username"), Some("\"user\""));
+        assert!(capture.span("refresh").is_some());
+        assert!(!capture.events().is_empty());
+
+        let secrets = ["hunter2", "new-access", "new-refresh", "refreshed-access"];
+        for value in capture.values() {
+            for secret in secrets.iter() {
+                assert!(!value.contains(secret), "{} recorded in {}", secret, value);
+            }
+        }
+    }
+
+    #[test]
+    fn exported_tokens_import_again() {
+        let authorization = Authorization::from_tokens(
+            "exported-access".to_owned(),
+            Some("exported-refresh".to_owned()),
+            Some(3600),
+        );
+        let exported = serde_json::to_string(&authorization.export()).unwrap();
+        let imported: ExportedTokens = serde_json::from_str(&exported).unwrap();
+        let imported = Authorization::from(imported);
+
+        assert_eq!(imported.access_token().expose(), "exported-access");
+        assert_eq!(imported.refresh_token().unwrap().expose(), "exported-refresh");
+        let expires_in = imported.expires_in().unwrap();
+        assert!((3599..=3600).contains(&expires_in), "{}", expires_in);
+
+        // Tokens without a refresh token or expiry are exported without them
+        let authorization = Authorization::from_tokens("exported-access".to_owned(), None, None);
+        let exported = serde_json::to_value(authorization.export()).unwrap();
+        assert_eq!(exported, serde_json::json!({"access_token": "exported-access"}));
+        let imported: ExportedTokens = serde_json::from_value(exported).unwrap();
+        let imported = Authorization::from(imported);
+        assert!(imported.refresh_token().is_none());
+        assert_eq!(imported.expires_at(), None);
+    }
+
+    #[tokio::test]
+    async fn stalled_login_times_out() {
+        let server = MockServer::start().await;
+        let stalled = MockResponse::json(200, AUTHORIZED).delay(Duration::from_secs(5));
+        server.respond(Method::POST, LOGIN_PATH, stalled);
+
+        let timeout = Duration::from_millis(100);
+        let options = AuthOptions {
+            endpoints: server.endpoints(),
+            retries: 3,
+            timeout,
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+        let client = Client::new();
+        let login = Authorization::authenticate_opts_with(&client, "user", "pw", options);
+        let err = login.await.unwrap_err();
+
+        let timed_out = matches!(err, Error::Auth(AuthError::Timeout(after)) if after == timeout);
+        assert!(timed_out, "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.count(LOGIN_PATH), 1);
     }
 }
@@ -0,0 +1,298 @@
+//! A blocking client for use without an async runtime
+//!
+//! This mirrors the async API, sharing its queries and the interpretation of their responses.
+//! Queries made with the blocking client are not retried or rate limited.
+
+use crate::{customer, ApiVersion, Endpoints, Error, HttpRequest, HttpResponse, Query};
+use crate::auth::{Challenge, Exchanged};
+use crate::transport;
+use crate::error::Body;
+use reqwest::blocking::{RequestBuilder, Response};
+use std::convert::{TryFrom, TryInto};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+
+/// Encapsulation of the authentication tokens used with the API
+///
+/// This dereferences to the async [`Authorization`](../struct.Authorization.html), which
+/// provides access to the tokens and their persistence.
+#[derive(Debug, Clone)]
+pub struct Authorization(crate::Authorization);
+
+impl Authorization {
+    /// Authenticate a user with a username and password
+    pub fn authenticate(username: &str, password: &str) -> Result<Login, Error> {
+        Self::authenticate_with(&reqwest::blocking::Client::new(), username, password)
+    }
+
+    /// Authenticate a user with an existing client
+    pub fn authenticate_with(
+        client: &reqwest::blocking::Client,
+        username: &str,
+        password: &str,
+    ) -> Result<Login, Error> {
+        let endpoints = Endpoints::default();
+        let query = crate::Authorization::login_body(username, password, false)?;
+        let response = portal_request(client, &endpoints, &endpoints.login)
+            .body(query.expose().to_owned())
+            .send()?;
+
+        let issued = crate::Authorization::issued(response.headers());
+        let body = read(response)?;
+        let login = match crate::Authorization::parse_login(issued, &body, endpoints.clone())? {
+            Exchanged::Authorized(authorization) => {
+                Login::Authorized(Authorization(*authorization))
+            }
+            Exchanged::OtpRequired(challenge) => Login::OtpRequired(OtpChallenge {
+                client: client.clone(),
+                endpoints,
+                challenge,
+                completed: false,
+            }),
+        };
+        Ok(login)
+    }
+
+    /// Refresh the access token using the refresh token
+    pub fn refresh(&mut self) -> Result<(), Error> {
+        self.refresh_with(&reqwest::blocking::Client::new())
+    }
+
+    /// Refresh the access token with an existing client
+    pub fn refresh_with(&mut self, client: &reqwest::blocking::Client) -> Result<(), Error> {
+        let query = self.0.refresh_body()?;
+        let endpoints = self.0.endpoints();
+        let response = portal_request(client, endpoints, &endpoints.refresh).body(query).send()?;
+
+        let issued = crate::Authorization::issued(response.headers());
+        let body = read(response)?;
+        self.0.apply_refresh(issued, &body)
+    }
+
+    /// Construct a client using the authorization
+    pub fn into_client(self) -> Result<Client, Error> {
+        self.try_into()
+    }
+
+    /// Get the underlying authorization
+    pub fn into_inner(self) -> crate::Authorization {
+        self.0
+    }
+}
+
+impl From<crate::Authorization> for Authorization {
+    fn from(authorization: crate::Authorization) -> Self {
+        Authorization(authorization)
+    }
+}
+
+impl Deref for Authorization {
+    type Target = crate::Authorization;
+
+    fn deref(&self) -> &crate::Authorization {
+        &self.0
+    }
+}
+
+impl DerefMut for Authorization {
+    fn deref_mut(&mut self) -> &mut crate::Authorization {
+        &mut self.0
+    }
+}
+
+/// The outcome of authenticating a user
+#[derive(Debug)]
+pub enum Login {
+    /// The user has been authorized
+    Authorized(Authorization),
+    /// The user must provide a one-time password to complete the login
+    OtpRequired(OtpChallenge),
+}
+
+/// A request for a one-time password to complete a login
+#[derive(Debug)]
+pub struct OtpChallenge {
+    client: reqwest::blocking::Client,
+    endpoints: Endpoints,
+    challenge: Challenge,
+    completed: bool,
+}
+
+impl OtpChallenge {
+    /// The message describing how the one-time password was delivered, if any
+    pub fn message(&self) -> Option<&str> {
+        self.challenge.message()
+    }
+
+    /// Complete the login with a one-time password
+    ///
+    /// See [`OtpChallenge::submit`](../struct.OtpChallenge.html#method.submit).
+    pub fn submit(&mut self, code: &str) -> Result<Authorization, Error> {
+        if self.completed {
+            return Err(crate::AuthError::ChallengeCompleted.into());
+        }
+
+        let query = self.challenge.body(code)?;
+        let endpoints = &self.endpoints;
+        let response = portal_request(&self.client, endpoints, &endpoints.otp)
+            .body(query)
+            .send()?;
+
+        let issued = crate::Authorization::issued(response.headers());
+        let body = read(response)?;
+        let authorization = self.challenge.parse(issued, &body, self.endpoints.clone())?;
+        self.completed = true;
+        Ok(Authorization(authorization))
+    }
+}
+
+/// A blocking client used to interact with the Exetel API
+///
+/// The authorization is refreshed as needed before each request.
+pub struct Client {
+    authorization: Mutex<Authorization>,
+    inner: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// The authorization used by the client
+    pub fn authorization(&self) -> MutexGuard<'_, Authorization> {
+        // The authorization is only replaced once a refresh has succeeded
+        self.authorization.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Query exetel for the given object
+    pub fn execute<Q: Query>(&self, query: &Q) -> Result<Q::Response, Error> {
+        let HttpRequest { method, url, headers, body } = {
+            let mut authorization = self.authorization();
+            if authorization.should_refresh() {
+                authorization.refresh_with(&self.inner)?;
+            }
+            let api = &authorization.endpoints().api;
+            transport::build_request(api, &ApiVersion::default(), &authorization, query)?
+        };
+
+        let request = self.inner.request(method, url).headers(headers);
+        let request = match body {
+            Some(body) => request.body(body),
+            None => request,
+        };
+        let response = request.send()?;
+
+        let response = HttpResponse {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.bytes()?.to_vec(),
+        };
+        transport::parse_response(query, response)
+    }
+
+    pub fn services(&self) -> Result<customer::Services, Error> {
+        self.execute(&customer::GetServices).map(|data| data.unwrap())
+    }
+}
+
+impl TryFrom<Authorization> for Client {
+    type Error = Error;
+
+    fn try_from(authorization: Authorization) -> Result<Self, Error> {
+        let inner = reqwest::blocking::Client::builder().build()?;
+        Ok(Client {
+            authorization: Mutex::new(authorization),
+            inner,
+        })
+    }
+}
+
+/// Build a request to the customer portal
+fn portal_request(
+    client: &reqwest::blocking::Client,
+    endpoints: &Endpoints,
+    url: &str,
+) -> RequestBuilder {
+    client.post(url).headers(crate::Authorization::portal_headers(endpoints))
+}
+
+/// Read the body of a response
+fn read(response: Response) -> Result<Body, Error> {
+    let path = response.url().path().to_owned();
+    let status = response.status();
+    let bytes = response.bytes()?;
+    Ok(Body { path, status, bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use reqwest::Method;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Start a mock server running on its own thread, outside of any async runtime
+    fn serve(script: impl FnOnce(&MockServer) + Send + 'static) -> MockServer {
+        let (started, server) = mpsc::channel();
+        thread::spawn(move || {
+            let mut runtime = tokio::runtime::Builder::new()
+                .basic_scheduler()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let server = MockServer::start().await;
+                script(&server);
+                started.send(server).unwrap();
+                std::future::pending::<()>().await
+            })
+        });
+        server.recv().unwrap()
+    }
+
+    const REFRESHED: &str = r#"{
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "access_token": "new-access",
+        "refresh_token": "new-refresh",
+        "persistLogin": false
+    }"#;
+
+    #[test]
+    fn services_are_queried_after_refreshing() {
+        let server = serve(|server| {
+            let services = include_str!("../fixtures/services.json");
+            server.respond(Method::GET, "/api/v1/service", MockResponse::json(200, services));
+            let refreshed = MockResponse::json(200, REFRESHED);
+            server.respond(Method::POST, "/api/auth/postRefreshToken", refreshed);
+        });
+        let mut authorization = crate::Authorization::from_tokens(
+            "old-access".to_owned(),
+            Some("old-refresh".to_owned()),
+            Some(0),
+        );
+        authorization.set_endpoints(server.endpoints());
+        let client = Authorization::from(authorization).into_client().unwrap();
+
+        let services = client.services().unwrap();
+        assert!(services.iter().next().is_some());
+        assert_eq!(client.authorization().access_token().expose(), "new-access");
+
+        let received = server.received();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].json()["refreshToken"], "old-refresh");
+        assert_eq!(received[1].path, "/api/v1/service");
+        assert_eq!(received[1].headers["authorization"], "Bearer new-access");
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let server = serve(|server| {
+            server.respond(Method::GET, "/api/v1/service", MockResponse::json(401, "{}"));
+        });
+        let mut authorization = crate::Authorization::from_tokens("access".to_owned(), None, None);
+        authorization.set_endpoints(server.endpoints());
+        let client = Authorization::from(authorization).into_client().unwrap();
+
+        let err = client.services().unwrap_err();
+        assert!(matches!(err, Error::ReauthenticationRequired), "{:?}", err);
+    }
+}
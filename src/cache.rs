@@ -0,0 +1,88 @@
+//! Caching of responses to queries
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The manner in which a client caches responses to `GET` queries
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    ttl: Duration,
+}
+
+impl CachePolicy {
+    /// Replay responses for the given time after they were received
+    pub fn ttl(ttl: Duration) -> Self {
+        CachePolicy { ttl }
+    }
+}
+
+/// Responses kept by URL
+pub(crate) struct Cache<T> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Arc<Slot<T>>>>,
+}
+
+/// The response for a URL, locked while it is being fetched
+type Slot<T> = tokio::sync::Mutex<Option<Entry<T>>>;
+
+struct Entry<T> {
+    received: Instant,
+    response: T,
+}
+
+impl<T: Clone> Cache<T> {
+    pub(crate) fn new(policy: CachePolicy) -> Self {
+        Cache {
+            ttl: policy.ttl,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Get the cached response for a URL, or fetch it if it has expired
+    ///
+    /// Callers that miss the cache for the same URL at the same time share a single fetch.
+    pub(crate) async fn get_or_fetch<F, E>(&self, url: &str, fetch: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        let slot = self.slot(url);
+        let mut entry = slot.lock().await;
+        if let Some(entry) = &*entry {
+            if entry.received.elapsed() < self.ttl {
+                return Ok(entry.response.clone());
+            }
+        }
+
+        let response = fetch.await?;
+        *entry = Some(Entry {
+            received: Instant::now(),
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+
+    /// Discard the responses for a URL, regardless of its query string
+    pub(crate) fn invalidate(&self, url: &str) {
+        self.entries().retain(|key, _| {
+            let query = key.strip_prefix(url);
+            !matches!(query, Some(query) if query.is_empty() || query.starts_with('?'))
+        });
+    }
+
+    /// Discard all responses
+    pub(crate) fn invalidate_all(&self) {
+        self.entries().clear();
+    }
+
+    fn slot(&self, url: &str) -> Arc<Slot<T>> {
+        self.entries().entry(url.to_owned()).or_default().clone()
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<String, Arc<Slot<T>>>> {
+        // The map is never left partially updated
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -1,63 +1,484 @@
 //! Abstract wrapper around the reqwest client
 
-use reqwest;
-use reqwest::{Url, IntoUrl, RequestBuilder};
-use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, HeaderMap};
-use mime::{APPLICATION_JSON, TEXT_PLAIN};
-use crate::Authorization;
-use anyhow::Error;
+use reqwest::{Method, Url, RequestBuilder, Response, StatusCode};
+use reqwest::header::{HeaderMap, ACCEPT, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE};
+use mime::Mime;
+use crate::{ApiVersion, Authorization, CredentialProvider, Endpoints, Error, SharedAuthorization};
+use crate::error::Body;
+use crate::retry::RetryPolicy;
+use crate::transport::{self, HttpRequest};
+use crate::rate_limit::{Budget, RateLimitInfo, RateLimiter};
+use crate::cache::{Cache, CachePolicy};
+use crate::fixture::{FixtureSet, Fixtures, Recorder};
+use crate::middleware::{Middleware, Next};
+use crate::observer::RequestObserver;
+use crate::paginate::{PagedQuery, Pages};
+use tokio::time;
+use chrono::NaiveDate;
+use std::time::{Duration, Instant};
 use std::convert::TryFrom;
 use serde::{Serialize, de::DeserializeOwned};
-use serde_json;
+use serde_json::Value;
 use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::future::{self, Future};
+use std::path::PathBuf;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::RwLockReadGuard;
 
 use crate::customer;
 
 /// A client used to interact with the Exetel API
+///
+/// The authorization is refreshed as needed before each request. Clones of a client share its
+/// connections, authorization, rate limit and cache.
+#[derive(Clone)]
 pub struct Client {
-    authorization: Authorization,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    authorization: SharedAuthorization,
     inner: reqwest::Client,
+    api: String,
+    version: ApiVersion,
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    retry: Option<RetryPolicy>,
+    rate_limit: Option<Arc<RateLimiter>>,
+    rate_limit_wait: Option<Duration>,
+    budget: Option<Budget>,
+    requests_made: AtomicU64,
+    cache: Option<Arc<Cache<Received>>>,
+    observers: Vec<Box<dyn RequestObserver>>,
+    middleware: Vec<Box<dyn Middleware>>,
 }
 
 impl Client {
     /// Query exetel for the given object
-    async fn query<Q: Query>(&self, query: &Q) -> Result<Q::Response, Error> {
-        if let Some(body) = query.body() {
-            self.post(query.url()?, body).await
-        } else {
-            self.get(query.url()?).await
+    pub async fn execute<Q: Query>(&self, query: &Q) -> Result<Q::Response, Error> {
+        self.execute_with(query, QueryOptions::default()).await
+    }
+
+    /// Query exetel for the given object, with options for the query
+    pub async fn execute_with<Q: Query>(
+        &self,
+        query: &Q,
+        options: QueryOptions,
+    ) -> Result<Q::Response, Error> {
+        self.fetch(query, options).await?.body.parse()
+    }
+
+    /// Query exetel for the given object, along with the details of the response
+    pub async fn execute_full<Q: Query>(
+        &self,
+        query: &Q,
+    ) -> Result<ApiResponse<Q::Response>, Error> {
+        let started = Instant::now();
+        let Received { headers, body } = self.fetch(query, QueryOptions::default()).await?;
+        Ok(ApiResponse {
+            body: body.parse()?,
+            status: body.status,
+            headers,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Get the response to a query from the cache or the API, within its deadline
+    async fn fetch<Q: Query>(&self, query: &Q, options: QueryOptions) -> Result<Received, Error> {
+        let request = self.request_for(query)?;
+        let cache = self.shared.cache.as_ref().filter(|_| !options.no_cache);
+        let received = async {
+            match cache {
+                Some(cache) if request.method == Method::GET => {
+                    let key = request.url.to_string();
+                    cache.get_or_fetch(&key, self.send_query(query, request)).await
+                }
+                _ => self.send_query(query, request).await,
+            }
+        };
+
+        match options.deadline {
+            Some(deadline) => {
+                let started = Instant::now();
+                time::timeout(deadline, received)
+                    .await
+                    .map_err(|_| Error::Deadline { elapsed: started.elapsed() })?
+            }
+            None => received.await,
+        }
+    }
+
+    /// Discard cached responses for a path relative to the base URL and version of the API
+    ///
+    /// Responses for the path are discarded regardless of their query parameters.
+    pub fn invalidate(&self, path: &str) {
+        if let Some(cache) = &self.shared.cache {
+            cache.invalidate(&join(&self.shared.api, &self.shared.version, path));
+        }
+    }
+
+    /// The number of requests sent by the client and its clones, including retries
+    pub fn requests_made(&self) -> u64 {
+        self.shared.requests_made.load(Ordering::Relaxed)
+    }
+
+    /// Discard all cached responses
+    pub fn invalidate_all(&self) {
+        if let Some(cache) = &self.shared.cache {
+            cache.invalidate_all();
         }
     }
 
-    async fn post<Q, R>(&self, url: impl IntoUrl, query: &Q) -> Result<R, Error>
+    /// Get the response from a path relative to the base URL and version of the API
+    ///
+    /// This is useful for exploring endpoints that are not yet modelled by this crate.
+    pub async fn get_raw(&self, path: &str) -> Result<RawResponse, Error> {
+        let query = RawQuery { path, body: None };
+        let request = self.request_for(&query)?;
+        self.send_query(&query, request).await.map(RawResponse::from)
+    }
+
+    /// Post a body to a path relative to the base URL and version of the API and get the response
+    pub async fn post_raw(&self, path: &str, body: &Value) -> Result<RawResponse, Error> {
+        let query = RawQuery { path, body: Some(body) };
+        let request = self.request_for(&query)?;
+        self.send_query(&query, request).await.map(RawResponse::from)
+    }
+
+    /// Download the body of a response from a path relative to the base URL and version of the API
+    ///
+    /// The body is written to the writer as it is received, rather than being kept in memory. The
+    /// request is retried according to the retry policy, but never once the body has been written.
+    pub async fn download<W>(&self, path: &str, writer: W) -> Result<DownloadInfo, Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.download_as(path, None, writer).await
+    }
+
+    /// Download the PDF of an invoice
+    ///
+    /// The PDF is written to the writer as it is received. If the API responds with anything other
+    /// than a PDF, such as a login page, nothing is written.
+    pub async fn invoice_pdf<W>(
+        &self,
+        invoice: &customer::InvoiceId,
+        writer: W,
+    ) -> Result<customer::InvoicePdf, Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let path = format!("/invoice/{}/pdf", invoice);
+        let info = self.download_as(&path, Some(APPLICATION_PDF), writer).await?;
+        Ok(info.into())
+    }
+
+    /// Download the body of a response, requiring it to be of the given type of content if any
+    async fn download_as<W>(
+        &self,
+        path: &str,
+        expected: Option<&'static str>,
+        mut writer: W,
+    ) -> Result<DownloadInfo, Error>
     where
-        Q: Serialize,
-        R: DeserializeOwned,
+        W: AsyncWrite + Unpin,
     {
-        let query = serde_json::to_string(query)?;
-        let request = self.inner
-            .post(url)
-            .body(query)
-            .header(CONTENT_TYPE, APPLICATION_JSON.essence_str());
-        self.request(request).await
+        let query = RawQuery { path, body: None };
+        let url = self.url(&query)?;
+        let request_id = Uuid::new_v4();
+        let request = self.shared.inner.get(url).header(REQUEST_ID, request_id.to_string());
+        let request = match expected {
+            Some(expected) => request.header(ACCEPT, expected),
+            None => request,
+        };
+        let receive = |response| future::ready(Ok(response));
+        let mut response = self.request(request, request_id, true, receive).await?;
+
+        let headers = response.headers().clone();
+        let failed = |err: Error| Error::Request {
+            request_id,
+            server_request_id: request_id_of(&headers),
+            headers: Some(Box::new(headers.clone())),
+            source: Box::new(err),
+        };
+        let mut info = DownloadInfo::from_headers(response.headers());
+        if let Some(expected) = expected {
+            let received = info.content_type.as_deref().and_then(|ty| ty.parse::<Mime>().ok());
+            if received.as_ref().map(Mime::essence_str) != Some(expected) {
+                let received = info.content_type.clone();
+                return Err(failed(Error::UnexpectedContentType { expected, received }));
+            }
+        }
+        while let Some(chunk) = response.chunk().await.map_err(|err| failed(err.into()))? {
+            writer.write_all(&chunk).await.map_err(|err| failed(err.into()))?;
+            info.length += chunk.len() as u64;
+        }
+        writer.flush().await.map_err(|err| failed(err.into()))?;
+        Ok(info)
+    }
+
+    /// Send a query, without parsing its response
+    ///
+    /// The authorization of the request is never recorded when tracing.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "exetel.request",
+            skip_all,
+            fields(
+                path = %query.path(),
+                method = %query.method(),
+                request_id = tracing::field::Empty,
+                status = tracing::field::Empty,
+                elapsed_ms = tracing::field::Empty,
+            ),
+        )
+    )]
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    async fn send_query<Q: Query>(
+        &self,
+        query: &Q,
+        request: HttpRequest,
+    ) -> Result<Received, Error> {
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
+        let HttpRequest { method, url, headers, body } = request;
+        let idempotent = Q::IDEMPOTENT || Self::is_idempotent(&method);
+        let request_id = Uuid::new_v4();
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("request_id", tracing::field::display(request_id));
+
+        let request = self
+            .shared
+            .inner
+            .request(method, url)
+            .headers(headers)
+            .header(REQUEST_ID, request_id.to_string());
+        let request = match body {
+            Some(body) => {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(body = %String::from_utf8_lossy(&body), "request body");
+                request.body(body)
+            }
+            None => request,
+        };
+        let received = self.request(request, request_id, idempotent, Received::read).await;
+
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            span.record("elapsed_ms", elapsed_ms);
+            if let Ok(received) = &received {
+                let status = received.body.status.as_u16();
+                span.record("status", status);
+                let size = received.body.bytes.len();
+                tracing::debug!(status, elapsed_ms, size, "request completed");
+            }
+        }
+        received
+    }
+
+    /// Construct the request for a query, without its authorization
+    fn request_for<Q: Query>(&self, query: &Q) -> Result<HttpRequest, Error> {
+        transport::unauthorized_request(&self.shared.api, &self.shared.version, query)
+    }
+
+    /// Get the URL of a query, using the version of the client unless the query chooses its own
+    fn url<Q: Query>(&self, query: &Q) -> Result<Url, Error> {
+        url(&self.shared.api, &self.shared.version, query)
+    }
+
+    /// Check if requests with a method may be safely repeated
+    fn is_idempotent(method: &Method) -> bool {
+        matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE)
+    }
+
+    /// Send a request, retrying it according to the retry policy if it is idempotent
+    ///
+    /// Successful responses are received with `receive`.
+    async fn request<R, F, T>(
+        &self,
+        request: RequestBuilder,
+        request_id: Uuid,
+        idempotent: bool,
+        receive: R,
+    ) -> Result<T, Error>
+    where
+        R: Fn(Response) -> F,
+        F: Future<Output = Result<T, Error>>,
+    {
+        let mut attempts = 1;
+        let policy = match &self.shared.retry {
+            Some(policy) if idempotent => policy,
+            _ => return self.send(request, request_id, &mut attempts, &receive).await,
+        };
+
+        loop {
+            let attempt = match request.try_clone() {
+                Some(attempt) => attempt,
+                None => return self.send(request, request_id, &mut attempts, &receive).await,
+            };
+
+            let err = match self.send(attempt, request_id, &mut attempts, &receive).await {
+                Ok(response) => return Ok(response),
+                Err(err) => err,
+            };
+            if !policy.should_retry(&err) {
+                return Err(err);
+            }
+            if attempts >= policy.max_attempts {
+                return Err(Error::RetriesExhausted { attempts, source: Box::new(err) });
+            }
+
+            let delay = policy.delay(&err, attempts);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(attempts, ?delay, error = %err, "retrying request");
+            time::delay_for(delay).await;
+            attempts += 1;
+        }
+    }
+
+    /// Send a request, sending it again once if it is rate limited briefly enough or the access
+    /// token is rejected
+    ///
+    /// A rejected access token is refreshed before the request is sent again. The number of
+    /// attempts is incremented if the request is sent again.
+    async fn send<R, F, T>(
+        &self,
+        request: RequestBuilder,
+        request_id: Uuid,
+        attempts: &mut u32,
+        receive: &R,
+    ) -> Result<T, Error>
+    where
+        R: Fn(Response) -> F,
+        F: Future<Output = Result<T, Error>>,
+    {
+        let retry = request.try_clone();
+        let bearer = self.bearer().await?;
+        let err = match self.send_once(request, &bearer, request_id, *attempts, receive).await {
+            Err(err) => err,
+            received => return received,
+        };
+        let retry = match retry {
+            Some(retry) => retry,
+            None => return Err(err),
+        };
+
+        let bearer = match err.inner() {
+            Error::RateLimited(RateLimitInfo { retry_after: Some(wait), .. })
+                if self.shared.rate_limit_wait.is_some_and(|ceiling| *wait <= ceiling) =>
+            {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(?wait, "waiting for rate limit");
+                time::delay_for(*wait).await;
+                self.bearer().await?
+            }
+            Error::ReauthenticationRequired => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("access token rejected, refreshing");
+                let credentials = self.shared.credentials.as_deref();
+                self.shared
+                    .authorization
+                    .bearer_after_rejecting(&self.shared.inner, credentials, &bearer)
+                    .await?
+            }
+            _ => return Err(err),
+        };
+        *attempts += 1;
+        self.send_once(retry, &bearer, request_id, *attempts, receive).await
+    }
+
+    /// Send a request once, reporting its outcome to the observers
+    ///
+    /// Failures of the request are given the identifiers of the request.
+    async fn send_once<R, F, T>(
+        &self,
+        request: RequestBuilder,
+        bearer: &str,
+        request_id: Uuid,
+        attempt: u32,
+        receive: &R,
+    ) -> Result<T, Error>
+    where
+        R: Fn(Response) -> F,
+        F: Future<Output = Result<T, Error>>,
+    {
+        if let Some(budget) = &self.shared.budget {
+            if !budget.spend() {
+                return Err(Error::BudgetExhausted);
+            }
+        }
+        if let Some(rate_limit) = &self.shared.rate_limit {
+            rate_limit.acquire().await;
+        }
+        self.shared.requests_made.fetch_add(1, Ordering::Relaxed);
+
+        let request = request
+            .header(AUTHORIZATION, bearer)
+            .build()?;
+        let path = request.url().path().to_owned();
+
+        let started = Instant::now();
+        let mut seen = Seen::default();
+        let received = self.exchange(request, &mut seen, receive).await;
+        let elapsed = started.elapsed();
+
+        for observer in &self.shared.observers {
+            if let Some(status) = seen.status {
+                observer.on_response(&path, status, elapsed, attempt);
+            }
+            if let Err(err) = &received {
+                observer.on_error(&path, &err.kind(), attempt);
+            }
+        }
+
+        received.map_err(|err| Error::Request {
+            request_id,
+            server_request_id: seen.headers.as_ref().and_then(request_id_of),
+            headers: seen.headers.map(Box::new),
+            source: Box::new(err),
+        })
     }
 
-    async fn get<R>(&self, url: impl IntoUrl) -> Result<R, Error>
+    /// Send a request and receive its response, noting what is seen of it as it is received
+    async fn exchange<R, F, T>(
+        &self,
+        request: reqwest::Request,
+        seen: &mut Seen,
+        receive: &R,
+    ) -> Result<T, Error>
     where
-        R: DeserializeOwned,
+        R: Fn(Response) -> F,
+        F: Future<Output = Result<T, Error>>,
     {
-        self.request(self.inner.get(url).header(CONTENT_TYPE, TEXT_PLAIN.essence_str())).await
+        #[cfg(feature = "tracing")]
+        tracing::debug!("sending request");
+        let next = Next::new(&self.shared.inner, &self.shared.middleware);
+        let response = next.run(request).await?;
+
+        let status = response.status();
+        seen.status = Some(status);
+        seen.headers = Some(response.headers().clone());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%status, "received response");
+        transport::check_status(status, response.headers())?;
+        if !status.is_success() {
+            return Err(transport::failure(&Body::read(response).await?));
+        }
+        receive(response).await
     }
 
-    async fn request<R: DeserializeOwned>(&self, request: RequestBuilder) -> Result<R, Error> {
-        let response = request
-            .header(ACCEPT, APPLICATION_JSON.essence_str())
-            .send()
-            .await?
-            .text()
-            .await?;
-        let response = serde_json::from_str(&response)?;
-        Ok(response)
+    /// Get the authorization header for the access token, refreshing it if necessary
+    async fn bearer(&self) -> Result<String, Error> {
+        let credentials = self.shared.credentials.as_deref();
+        self.shared.authorization.bearer(&self.shared.inner, credentials).await
     }
 }
 
@@ -65,37 +486,1030 @@ impl TryFrom<Authorization> for Client {
     type Error = Error;
 
     fn try_from(authorization: Authorization) -> Result<Self, Error> {
-        let mut headers = HeaderMap::new();
-        let bearer = format!("Bearer {}", authorization.access_token());
-        headers.insert(AUTHORIZATION, bearer.parse()?);
-        let inner = reqwest::ClientBuilder::new().default_headers(headers).build()?;
+        Client::builder(authorization).build()
+    }
+}
+
+impl TryFrom<SharedAuthorization> for Client {
+    type Error = Error;
+
+    fn try_from(authorization: SharedAuthorization) -> Result<Self, Error> {
+        Client::shared_builder(authorization).build()
+    }
+}
+
+/// A builder used to configure a client
+pub struct ClientBuilder {
+    authorization: BuilderAuthorization,
+    endpoints: Option<Endpoints>,
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    retry: Option<RetryPolicy>,
+    rate_limit: Option<(u32, Duration)>,
+    rate_limit_wait: Option<Duration>,
+    budget: Option<(u32, Option<Duration>)>,
+    cache: Option<CachePolicy>,
+    observers: Vec<Box<dyn RequestObserver>>,
+    middleware: Vec<Box<dyn Middleware>>,
+    record_to: Option<PathBuf>,
+    base_url: Option<Url>,
+    version: ApiVersion,
+    http: reqwest::ClientBuilder,
+}
+
+/// The authorization with which a client will be built
+enum BuilderAuthorization {
+    Owned(Box<Authorization>),
+    Shared(SharedAuthorization),
+}
+
+impl ClientBuilder {
+    fn new(authorization: BuilderAuthorization) -> Self {
+        ClientBuilder {
+            authorization,
+            endpoints: None,
+            credentials: None,
+            retry: None,
+            rate_limit: None,
+            rate_limit_wait: None,
+            budget: None,
+            cache: None,
+            observers: Vec::new(),
+            middleware: Vec::new(),
+            record_to: None,
+            base_url: None,
+            version: ApiVersion::default(),
+            http: reqwest::ClientBuilder::new(),
+        }
+    }
+
+    /// Use different endpoints for the client and its authorization
+    ///
+    /// By default the endpoints of the authorization are used. A shared authorization continues
+    /// to be refreshed using its own endpoints.
+    pub fn endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = Some(endpoints);
+        self
+    }
+
+    /// Log in again with the given credentials if the refresh token is rejected
+    pub fn credentials(mut self, credentials: impl CredentialProvider + 'static) -> Self {
+        self.credentials = Some(Arc::new(credentials));
+        self
+    }
+
+    /// Retry idempotent queries that fail transiently
+    ///
+    /// Queries are not retried by default.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Send at most the given number of requests in each interval
+    ///
+    /// Requests beyond the limit wait until the next interval, in the order they were made.
+    /// Requests are not limited by default.
+    pub fn rate_limit(mut self, requests: u32, interval: Duration) -> Self {
+        self.rate_limit = Some((requests, interval));
+        self
+    }
+
+    /// Wait and send a request again once if the API rate limits it for no longer than `ceiling`
+    ///
+    /// Rate limited requests fail immediately by default.
+    pub fn wait_on_rate_limit(mut self, ceiling: Duration) -> Self {
+        self.rate_limit_wait = Some(ceiling);
+        self
+    }
+
+    /// Fail every request once the given number of requests have been sent
+    ///
+    /// Each attempt at a request counts towards the budget. Requests are not limited by default.
+    pub fn max_requests(mut self, requests: NonZeroU32) -> Self {
+        self.budget = Some((requests.get(), None));
+        self
+    }
+
+    /// Fail every request once the given number of requests have been sent in an interval
+    ///
+    /// Unlike with [`rate_limit`](#method.rate_limit), requests beyond the limit fail rather
+    /// than waiting for the next interval.
+    pub fn max_requests_per(mut self, interval: Duration, requests: u32) -> Self {
+        self.budget = Some((requests, Some(interval)));
+        self
+    }
+
+    /// Cache the responses to `GET` queries
+    ///
+    /// Responses are not cached by default.
+    pub fn cache(mut self, policy: CachePolicy) -> Self {
+        self.cache = Some(policy);
+        self
+    }
+
+    /// Report the outcome of every request to an observer
+    ///
+    /// Any number of observers may be added, and they are called in the order they were added.
+    pub fn observer(mut self, observer: impl RequestObserver + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Pass every request through middleware before it is sent
+    ///
+    /// Middleware is run in the order it was added.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Record the body of every response to a directory, such as to build fixtures
+    ///
+    /// Each body is written to its own file, named for its path and the order in which it was
+    /// received, and listed in an index. Anything resembling an access token is redacted. The
+    /// responses can be replayed by an offline client with
+    /// [`FixtureSet::load`](struct.FixtureSet.html#method.load).
+    pub fn record_to(mut self, dir: PathBuf) -> Self {
+        self.record_to = Some(dir);
+        self
+    }
+
+    /// Send queries to a different base URL for the API
+    ///
+    /// This takes precedence over the API endpoint given with [`endpoints`](#method.endpoints). The
+    /// version of the API is still appended to the base URL.
+    pub fn base_url(mut self, base_url: Url) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Send queries to a different version of the API
+    ///
+    /// Queries are sent to version 1 by default, unless they choose their own version.
+    pub fn api_version(mut self, version: ApiVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Give up on requests that take longer than the given time to complete
+    ///
+    /// Requests never time out by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.timeout(timeout);
+        self
+    }
+
+    /// Give up on requests that take longer than the given time to connect
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http = self.http.connect_timeout(timeout);
+        self
+    }
+
+    /// Close connections that have been idle for longer than the given time
+    ///
+    /// Idle connections are closed after 90 seconds by default, or never if `None` is given.
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.http = self.http.pool_idle_timeout(timeout);
+        self
+    }
+
+    /// Keep at most the given number of idle connections open to each host
+    ///
+    /// The number of idle connections is not limited by default. Giving zero disables pooling.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.http = self.http.pool_max_idle_per_host(max);
+        self
+    }
+
+    /// Send TCP keep-alive probes on connections at the given interval
+    ///
+    /// Keep-alive probes are not sent by default.
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.http = self.http.tcp_keepalive(interval);
+        self
+    }
+
+    /// Identify the client to the API with the given user agent
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.http = self.http.user_agent(user_agent);
+        self
+    }
+
+    /// Ask for responses to be sent uncompressed, such as to read them through a proxy
+    ///
+    /// Responses are compressed by default with the encodings enabled by the `gzip` and `brotli`
+    /// features, and are decompressed transparently.
+    #[cfg_attr(not(any(feature = "gzip", feature = "brotli")), allow(unused_mut))]
+    pub fn no_compression(mut self) -> Self {
+        #[cfg(feature = "gzip")]
+        {
+            self.http = self.http.no_gzip();
+        }
+        #[cfg(feature = "brotli")]
+        {
+            self.http = self.http.no_brotli();
+        }
+        self
+    }
+
+    /// Send requests through a proxy
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.http = self.http.proxy(proxy);
+        self
+    }
+
+    /// Construct the client
+    pub fn build(self) -> Result<Client, Error> {
+        let ClientBuilder {
+            authorization,
+            endpoints,
+            credentials,
+            retry,
+            rate_limit,
+            rate_limit_wait,
+            budget,
+            cache,
+            observers,
+            mut middleware,
+            record_to,
+            base_url,
+            version,
+            http,
+        } = self;
+        let authorization = match authorization {
+            BuilderAuthorization::Owned(mut authorization) => {
+                if let Some(endpoints) = &endpoints {
+                    authorization.set_endpoints(endpoints.clone());
+                }
+                SharedAuthorization::new(*authorization)
+            }
+            BuilderAuthorization::Shared(authorization) => authorization,
+        };
+
+        let inner = http.build()?;
+        let api = match base_url {
+            Some(base_url) => base_url.as_str().trim_end_matches('/').to_owned(),
+            None => endpoints.as_ref().unwrap_or_else(|| authorization.endpoints()).api.clone(),
+        };
+
+        let rate_limit = rate_limit
+            .map(|(requests, interval)| Arc::new(RateLimiter::new(requests, interval)));
+
+        let cache = cache.map(|policy| Arc::new(Cache::new(policy)));
+
+        // Responses are recorded as they were received, before any other middleware sees them
+        if let Some(dir) = record_to {
+            middleware.push(Box::new(Recorder::new(dir)));
+        }
 
-        Ok(Client { authorization, inner })
+        let shared = Shared {
+            authorization,
+            inner,
+            api,
+            version,
+            credentials,
+            retry,
+            rate_limit,
+            rate_limit_wait,
+            budget: budget.map(|(requests, interval)| Budget::new(requests, interval)),
+            requests_made: AtomicU64::new(0),
+            cache,
+            observers,
+            middleware,
+        };
+        Ok(Client { shared: Arc::new(shared) })
     }
 }
 
 impl Client {
+    /// Construct a client that responds to queries from fixtures rather than the API
+    ///
+    /// The client never makes a connection, and fails any query for which there is no fixture.
+    pub fn offline(fixtures: FixtureSet) -> Result<Self, Error> {
+        let authorization = Authorization::from_tokens("offline".to_owned(), None, None);
+        Client::builder(authorization)
+            .base_url(Url::parse(OFFLINE_URL).expect("offline URL is valid"))
+            .middleware(Fixtures(fixtures))
+            .build()
+    }
+
+    /// Configure a new client
+    pub fn builder(authorization: Authorization) -> ClientBuilder {
+        ClientBuilder::new(BuilderAuthorization::Owned(Box::new(authorization)))
+    }
+
+    /// Configure a new client using an authorization shared with other clients
+    pub fn shared_builder(authorization: SharedAuthorization) -> ClientBuilder {
+        ClientBuilder::new(BuilderAuthorization::Shared(authorization))
+    }
+
+    /// The authorization shared by the client
+    pub fn shared_authorization(&self) -> &SharedAuthorization {
+        &self.shared.authorization
+    }
+
+    /// The authorization used by the client
+    pub async fn authorization(&self) -> RwLockReadGuard<'_, Authorization> {
+        self.shared.authorization.read().await
+    }
+
+    /// Set a hook to invoke after each refresh of the authorization
+    ///
+    /// See [`SharedAuthorization::on_refresh`](struct.SharedAuthorization.html#method.on_refresh).
+    pub fn on_token_refresh(&self, hook: impl Fn(&Authorization) + Send + Sync + 'static) {
+        self.shared.authorization.on_refresh(hook)
+    }
+
+    /// Revoke the authorization and discard the client
+    ///
+    /// The client is discarded even if the authorization could not be revoked. Other clients
+    /// sharing the authorization will no longer be able to make requests.
+    pub async fn logout(self) -> Result<(), Error> {
+        let (bearer, endpoints) = {
+            let authorization = self.shared.authorization.read().await;
+            (authorization.bearer()?, authorization.endpoints().clone())
+        };
+        Authorization::revoke_bearer(&self.shared.inner, &endpoints, bearer).await
+    }
+
     pub async fn services(&self) -> Result<customer::Services, Error> {
-        self.query(&customer::GetServices).await.map(|data| data.unwrap())
+        self.execute(&customer::GetServices).await.map(|data| data.unwrap())
+    }
+
+    /// Query the usage of the mobile service with the given identifier
+    pub async fn mobile_usage(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<customer::MobileUsage, Error> {
+        let query = customer::GetMobileUsage::new(service_id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query the usage of a mobile service
+    pub async fn mobile_usage_for(
+        &self,
+        service: &customer::MobileService,
+    ) -> Result<customer::MobileUsage, Error> {
+        let query = customer::GetMobileUsage::from(service);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query every page of a paged query, fetching each page as the previous one is consumed
+    pub fn paginate<Q>(&self, query: Q) -> Pages<Q>
+    where
+        Q: PagedQuery + Send + Sync + 'static,
+        Q::Response: Send,
+        Q::Item: Send,
+    {
+        Pages::new(self.clone(), query)
+    }
+
+    /// Query the invoices of the account, optionally only those issued within a range of dates
+    pub async fn invoices(
+        &self,
+        range: Option<customer::DateRange>,
+    ) -> Result<Vec<customer::Invoice>, Error> {
+        let query = customer::GetInvoices::new(range);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query the payments made to the account, optionally only those made within a range of dates
+    pub async fn payments(
+        &self,
+        range: Option<customer::DateRange>,
+    ) -> Result<Vec<customer::Payment>, Error> {
+        let query = customer::GetPayments::new(range);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query the balance of the account and a summary of its next bill
+    pub async fn balance(&self) -> Result<customer::AccountBalance, Error> {
+        self.execute(&customer::GetBalance).await.map(|data| data.unwrap())
+    }
+
+    /// Query the methods of payment stored for the account
+    pub async fn payment_methods(&self) -> Result<Vec<customer::PaymentMethod>, Error> {
+        self.execute(&customer::GetPaymentMethods).await.map(|data| data.unwrap())
+    }
+
+    /// Query the contact details of the account
+    pub async fn contact(&self) -> Result<customer::ContactDetails, Error> {
+        self.execute(&customer::GetContact).await.map(|data| data.unwrap())
+    }
+
+    /// Change some of the contact details of the account, returning them once changed
+    ///
+    /// Any cached contact details are discarded.
+    pub async fn update_contact(
+        &self,
+        update: customer::ContactUpdate,
+    ) -> Result<customer::ContactDetails, Error> {
+        let query = customer::UpdateContact::new(update);
+        let contact = self.execute(&query).await?;
+        self.invalidate(&customer::GetContact.path());
+        Ok(contact.unwrap())
+    }
+
+    /// Query the support tickets of the account
+    pub async fn tickets(
+        &self,
+        filter: customer::TicketFilter,
+    ) -> Result<Vec<customer::TicketSummary>, Error> {
+        let query = customer::GetTickets::new(filter);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query a support ticket, along with its messages
+    pub async fn ticket(&self, id: customer::TicketId) -> Result<customer::Ticket, Error> {
+        let query = customer::GetTicket::new(id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Create a support ticket, returning the number identifying it
+    ///
+    /// The ticket is checked before it is sent, failing with
+    /// [`Error::InvalidInput`](enum.Error.html#variant.InvalidInput) if it would be rejected.
+    pub async fn create_ticket(
+        &self,
+        ticket: customer::NewTicket,
+    ) -> Result<customer::TicketId, Error> {
+        let query = customer::CreateTicket::new(ticket)?;
+        self.execute(&query).await.map(|data| data.unwrap().id)
+    }
+
+    /// Reply to a support ticket
+    ///
+    /// The reply is checked before it is sent, as for [`create_ticket`](#method.create_ticket).
+    pub async fn reply_ticket(&self, id: customer::TicketId, body: &str) -> Result<(), Error> {
+        let query = customer::ReplyTicket::new(id, body)?;
+        self.execute(&query).await?;
+        self.invalidate(&customer::GetTicket::new(id).path());
+        Ok(())
+    }
+
+    /// Query the details of a service, including those of its connection
+    pub async fn service_detail(
+        &self,
+        id: customer::ServiceId,
+    ) -> Result<customer::ServiceDetail, Error> {
+        let query = customer::GetServiceDetail::new(id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query the plans to which a service can be changed
+    pub async fn available_plans(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<Vec<customer::PlanOption>, Error> {
+        let query = customer::GetAvailablePlans::new(service_id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Request a change to the plan of a service
+    ///
+    /// Fails with [`Error::PlanChangePending`](enum.Error.html#variant.PlanChangePending) if the
+    /// API rejects the request because another change has not yet taken effect.
+    pub async fn request_plan_change(
+        &self,
+        service_id: customer::ServiceId,
+        plan_id: customer::PlanId,
+        effective: customer::PlanChangeTiming,
+    ) -> Result<customer::PlanChangeReceipt, Error> {
+        let query = customer::RequestPlanChange::new(service_id, plan_id, effective);
+        let receipt = self.execute(&query).await.map_err(|err| {
+            err.map_inner(|err| match err {
+                Error::Api { status: StatusCode::CONFLICT, .. } => Error::PlanChangePending,
+                err => err,
+            })
+        })?;
+        self.invalidate(&customer::GetServices.path());
+        Ok(receipt.unwrap())
+    }
+
+    /// Query the add-ons that can be bought for a service
+    pub async fn addons(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<Vec<customer::Addon>, Error> {
+        let query = customer::GetAddons::new(service_id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Buy an add-on for a service, charging the account
+    ///
+    /// The purchase must be confirmed for the same add-on. Fails with
+    /// [`Error::PaymentDeclined`](enum.Error.html#variant.PaymentDeclined) if the account can not
+    /// be charged.
+    pub async fn purchase_addon(
+        &self,
+        service_id: customer::ServiceId,
+        addon_id: customer::AddonId,
+        confirm: customer::PurchaseConfirmation,
+    ) -> Result<customer::PurchaseReceipt, Error> {
+        let query = customer::PurchaseAddon::new(service_id, addon_id, confirm)?;
+        let receipt = self.execute(&query).await.map_err(|err| {
+            err.map_inner(|err| match err {
+                Error::Api { status: StatusCode::PAYMENT_REQUIRED, message, .. } => {
+                    Error::PaymentDeclined { message }
+                }
+                err => err,
+            })
+        })?;
+        Ok(receipt.unwrap())
+    }
+
+    /// Query current and scheduled outages affecting the services of the account
+    pub async fn outages(&self) -> Result<Vec<customer::Outage>, Error> {
+        self.execute(&customer::GetOutages).await.map(|data| data.unwrap())
+    }
+
+    /// Query current and scheduled outages affecting a service
+    pub async fn outages_for(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<Vec<customer::Outage>, Error> {
+        let mut outages = self.outages().await?;
+        outages.retain(|outage| outage.affects(service_id));
+        Ok(outages)
+    }
+
+    /// Query the calls made and received by a VoIP service within a range of dates
+    ///
+    /// Every page of calls is fetched; use [`paginate`](#method.paginate) with
+    /// [`GetRecords`](customer/struct.GetRecords.html) to fetch them as they are consumed.
+    pub async fn voip_calls(
+        &self,
+        service_id: customer::ServiceId,
+        range: customer::DateRange,
+    ) -> Result<Vec<customer::CallRecord>, Error> {
+        let query = customer::GetRecords::voip_calls(service_id, range);
+        self.paginate(query).collect_all().await
+    }
+
+    /// Query the calls made and received by a mobile service within a range of dates
+    ///
+    /// Every page of calls is fetched, as for [`voip_calls`](#method.voip_calls).
+    pub async fn mobile_calls(
+        &self,
+        service_id: customer::ServiceId,
+        range: customer::DateRange,
+    ) -> Result<Vec<customer::CallRecord>, Error> {
+        let query = customer::GetRecords::mobile_calls(service_id, range);
+        self.paginate(query).collect_all().await
+    }
+
+    /// Query the messages sent by a mobile service within a range of dates
+    ///
+    /// Every page of messages is fetched, as for [`voip_calls`](#method.voip_calls).
+    pub async fn mobile_sms(
+        &self,
+        service_id: customer::ServiceId,
+        range: customer::DateRange,
+    ) -> Result<Vec<customer::SmsRecord>, Error> {
+        let query = customer::GetRecords::mobile_sms(service_id, range);
+        self.paginate(query).collect_all().await
+    }
+
+    /// Query the calls made and received by a phone service within a range of dates
+    ///
+    /// Every page of calls is fetched, as for [`voip_calls`](#method.voip_calls).
+    pub async fn phone_calls(
+        &self,
+        service_id: customer::ServiceId,
+        range: customer::DateRange,
+    ) -> Result<customer::CallHistory, Error> {
+        let query = customer::GetRecords::phone_calls(service_id, range);
+        self.paginate(query).collect_all().await.map(customer::CallHistory::new)
+    }
+
+    /// Query the alerts sent as a service is used
+    pub async fn usage_alerts(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<customer::UsageAlertSettings, Error> {
+        let query = customer::GetUsageAlerts::new(service_id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Change the alerts sent as a service is used
+    ///
+    /// The thresholds are sorted and any repeated are removed before they are sent. Fails with
+    /// [`Error::InvalidInput`](enum.Error.html#variant.InvalidInput) if any is not between 1 and
+    /// 100 percent.
+    pub async fn set_usage_alerts(
+        &self,
+        service_id: customer::ServiceId,
+        settings: customer::UsageAlertSettings,
+    ) -> Result<(), Error> {
+        let query = customer::SetUsageAlerts::new(service_id, settings)?;
+        self.execute(&query).await?;
+        self.invalidate(&customer::GetUsageAlerts::new(service_id).path());
+        Ok(())
+    }
+
+    /// Query which notifications are sent about the account
+    pub async fn notification_preferences(&self) -> Result<customer::NotificationSettings, Error> {
+        let query = customer::GetNotificationSettings;
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Change which notifications are sent about the account
+    ///
+    /// Preferences not otherwise known are sent as they were read.
+    pub async fn set_notification_preferences(
+        &self,
+        prefs: customer::NotificationSettings,
+    ) -> Result<(), Error> {
+        let query = customer::SetNotificationSettings::new(prefs);
+        self.execute(&query).await?;
+        self.invalidate(&customer::GetNotificationSettings.path());
+        Ok(())
+    }
+
+    /// Request a quote to cancel a service
+    ///
+    /// The service is not cancelled until the quote is given to
+    /// [`confirm_cancellation`](#method.confirm_cancellation).
+    pub async fn request_cancellation(
+        &self,
+        service_id: customer::ServiceId,
+        request: customer::CancellationRequest,
+    ) -> Result<customer::CancellationQuote, Error> {
+        let query = customer::RequestCancellation::new(service_id, request);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Confirm a quote to cancel a service, cancelling it
+    ///
+    /// Fails with [`Error::QuoteExpired`](enum.Error.html#variant.QuoteExpired) if the quote has
+    /// expired, without sending a request if it is known to have.
+    pub async fn confirm_cancellation(
+        &self,
+        quote: customer::CancellationQuote,
+    ) -> Result<customer::CancellationReceipt, Error> {
+        if quote.is_expired_at(chrono::Utc::now()) {
+            return Err(Error::QuoteExpired);
+        }
+        let query = customer::ConfirmCancellation::from(quote);
+        let receipt = self.execute(&query).await.map_err(|err| {
+            err.map_inner(|err| match err {
+                Error::Api { status: StatusCode::GONE, .. } => Error::QuoteExpired,
+                err => err,
+            })
+        })?;
+        self.invalidate(&customer::GetServices.path());
+        Ok(receipt.unwrap())
+    }
+
+    /// Suspend a service from a date, until another date or until it is resumed
+    ///
+    /// Fails with [`Error::AlreadySuspended`](enum.Error.html#variant.AlreadySuspended) if the
+    /// service is already suspended, or with
+    /// [`Error::ServiceIneligible`](enum.Error.html#variant.ServiceIneligible) if it can not be.
+    pub async fn suspend_service(
+        &self,
+        service_id: customer::ServiceId,
+        from: NaiveDate,
+        until: Option<NaiveDate>,
+    ) -> Result<(), Error> {
+        let query = customer::SuspendService::new(service_id, from, until);
+        self.execute(&query).await.map_err(|err| {
+            err.map_inner(|err| match err {
+                Error::Api { status: StatusCode::CONFLICT, .. } => Error::AlreadySuspended,
+                Error::Api { status: StatusCode::UNPROCESSABLE_ENTITY, message, .. } => {
+                    Error::ServiceIneligible { message }
+                }
+                err => err,
+            })
+        })?;
+        self.invalidate(&customer::GetServices.path());
+        Ok(())
+    }
+
+    /// Resume a suspended service
+    pub async fn resume_service(&self, service_id: customer::ServiceId) -> Result<(), Error> {
+        self.execute(&customer::ResumeService::new(service_id)).await?;
+        self.invalidate(&customer::GetServices.path());
+        Ok(())
+    }
+
+    /// Query the orders of hardware made for the account
+    pub async fn orders(&self) -> Result<Vec<customer::Order>, Error> {
+        self.execute(&customer::GetOrders).await.map(|data| data.unwrap())
+    }
+
+    /// Query the referrals made by the account
+    pub async fn referrals(&self) -> Result<customer::ReferralInfo, Error> {
+        self.execute(&customer::GetReferrals).await.map(|data| data.unwrap())
+    }
+
+    /// Query the IP addresses assigned to a service
+    pub async fn ip_details(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<customer::IpDetails, Error> {
+        let query = customer::GetIpDetails::new(service_id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query the current quality of the connection of a service
+    pub async fn line_stats(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<customer::LineStats, Error> {
+        let query = customer::GetLineStats::new(service_id);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query the quality of the connection of a service over a range of dates
+    pub async fn line_history(
+        &self,
+        service_id: customer::ServiceId,
+        range: customer::DateRange,
+    ) -> Result<Vec<customer::LineSample>, Error> {
+        let query = customer::GetLineHistory::new(service_id, range);
+        self.execute(&query).await.map(|data| data.unwrap())
+    }
+
+    /// Query the speed tests of a service recorded by the portal
+    ///
+    /// The results are sorted from the newest, whatever order the API gives them in.
+    pub async fn speed_tests(
+        &self,
+        service_id: customer::ServiceId,
+    ) -> Result<Vec<customer::SpeedTestResult>, Error> {
+        let query = customer::GetSpeedTests::new(service_id);
+        let mut results = self.execute(&query).await?.unwrap();
+        results.sort_by_key(|result| Reverse(result.time));
+        Ok(results)
+    }
+
+    /// Query everything about the account at once
+    ///
+    /// The queries are made concurrently, and each succeeds or fails independently. The usage of
+    /// each mobile service is queried once the services are known.
+    pub async fn snapshot(&self) -> AccountSnapshot {
+        let services_and_usage = async {
+            let services = self.services().await;
+            let mut usage = Vec::new();
+            for service in services.iter().flat_map(customer::Services::mobile) {
+                let id = service.as_ref().id();
+                usage.push((id, self.mobile_usage_for(service).await));
+            }
+            (services, usage)
+        };
+        let ((services, usage), invoices, balance) =
+            tokio::join!(services_and_usage, self.invoices(None), self.balance());
+        AccountSnapshot { services, usage, invoices, balance }
+    }
+}
+
+/// Everything about an account, as queried by [`snapshot`](struct.Client.html#method.snapshot)
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct AccountSnapshot {
+    /// The services on the account
+    pub services: Result<customer::Services, Error>,
+    /// The usage of each mobile service, which is empty if the services could not be queried
+    pub usage: Vec<(customer::ServiceId, Result<customer::MobileUsage, Error>)>,
+    /// The invoices of the account
+    pub invoices: Result<Vec<customer::Invoice>, Error>,
+    /// The balance of the account
+    pub balance: Result<customer::AccountBalance, Error>,
+}
+
+/// Options for a single query
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    /// Fetch a fresh response even if one is cached
+    pub no_cache: bool,
+    /// Give up on the query if it has not completed within this time, including any retries
+    pub deadline: Option<Duration>,
+}
+
+/// The base URL of the API used by offline clients, which is never connected to
+const OFFLINE_URL: &str = "https://offline.invalid";
+
+/// The header identifying a request, sent with each request and echoed by some responses
+const REQUEST_ID: &str = "x-request-id";
+
+/// What was seen of the response to a request, even if it could not be received
+#[derive(Default)]
+struct Seen {
+    status: Option<StatusCode>,
+    headers: Option<HeaderMap>,
+}
+
+/// A successful response to a request
+#[derive(Clone)]
+struct Received {
+    headers: HeaderMap,
+    body: Body,
+}
+
+impl Received {
+    /// Read the headers and body of a response
+    async fn read(response: Response) -> Result<Self, Error> {
+        let headers = response.headers().clone();
+        let body = Body::read(response).await?;
+        Ok(Received { headers, body })
+    }
+}
+
+/// Details of a downloaded response
+#[derive(Debug, Clone)]
+pub struct DownloadInfo {
+    /// The type of the content of the response, if given
+    pub content_type: Option<String>,
+    /// The number of bytes written
+    pub length: u64,
+    /// The name suggested for the file by the `Content-Disposition` header, if given
+    pub filename: Option<String>,
+}
+
+impl DownloadInfo {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| headers.get(name)?.to_str().ok();
+        DownloadInfo {
+            content_type: header(CONTENT_TYPE).map(str::to_owned),
+            length: 0,
+            filename: header(CONTENT_DISPOSITION).and_then(filename),
+        }
+    }
+}
+
+/// The type of the content of a PDF document
+const APPLICATION_PDF: &str = "application/pdf";
+
+/// Get the file name given by a `Content-Disposition` header
+fn filename(disposition: &str) -> Option<String> {
+    disposition.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("filename") {
+            Some(value.trim().trim_matches('"').to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+/// Get the identifier the API gave a response
+fn request_id_of(headers: &HeaderMap) -> Option<String> {
+    let id = headers.get(REQUEST_ID)?.to_str().ok()?;
+    Some(id.to_owned())
+}
+
+/// A response to a query, along with its details
+#[derive(Debug, Clone)]
+pub struct ApiResponse<T> {
+    /// The body of the response
+    pub body: T,
+    /// The status of the response
+    pub status: StatusCode,
+    /// The headers of the response
+    pub headers: HeaderMap,
+    /// The time taken to get the response, including any retries
+    pub elapsed: Duration,
+}
+
+/// A response to a raw query
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    /// The status of the response
+    pub status: StatusCode,
+    /// The headers of the response
+    pub headers: HeaderMap,
+    /// The body of the response, or the text of the body if it is not JSON
+    pub body: Value,
+}
+
+impl From<Received> for RawResponse {
+    fn from(Received { headers, body }: Received) -> Self {
+        let status = body.status;
+        let body = match body.parse() {
+            Ok(body) => body,
+            Err(_) => Value::String(body.text().into_owned()),
+        };
+        RawResponse { status, headers, body }
     }
 }
 
-const URL_PREFIX: &'static str = "https://webservices.api.exetel.com.au/v1";
+/// A query of an arbitrary path of the API
+struct RawQuery<'a> {
+    path: &'a str,
+    body: Option<&'a Value>,
+}
+
+impl Query for RawQuery<'_> {
+    type Body = Value;
+    type Response = Value;
+
+    fn path(&self) -> Cow<'_, str> {
+        self.path.into()
+    }
+
+    fn body(&self) -> Option<&Value> {
+        self.body
+    }
+}
 
 /// An object that can be queried from the Exetel API
-pub(crate) trait Query {
+///
+/// The body of a query is sent as JSON. Unless another method is given, queries with a body are
+/// sent as a `POST` and other queries are sent as a `GET`. A response with no content can be
+/// deserialized as `()`.
+///
+/// Most responses from the API are wrapped in a [`Data`](customer/struct.Data.html) envelope,
+/// which can be used as the response type and unwrapped afterwards.
+///
+/// Queries for endpoints that are not yet modelled by this crate can be defined by implementing
+/// this trait and executing them with [`Client::execute`](struct.Client.html#method.execute).
+///
+/// ```
+/// use exetel_api::{build_request, parse_response, ApiVersion, Authorization, HttpResponse};
+/// use exetel_api::{Method, Query};
+/// use reqwest::StatusCode;
+/// use serde::{Deserialize, Serialize};
+/// use std::borrow::Cow;
+///
+/// #[derive(Serialize)]
+/// struct Rename {
+///     nickname: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Renamed {
+///     nickname: String,
+/// }
+///
+/// struct RenameService(u64, Rename);
+///
+/// impl Query for RenameService {
+///     type Body = Rename;
+///     type Response = Renamed;
+///
+///     fn method(&self) -> Method {
+///         Method::PUT
+///     }
+///
+///     fn path(&self) -> Cow<'_, str> {
+///         format!("/service/{}/nickname", self.0).into()
+///     }
+///
+///     fn body(&self) -> Option<&Rename> {
+///         Some(&self.1)
+///     }
+/// }
+///
+/// let query = RenameService(42, Rename { nickname: "Home".to_owned() });
+/// let authorization = Authorization::from_tokens("access".to_owned(), None, None);
+/// let api = "https://example.com/api";
+/// let request = build_request(api, &ApiVersion::V1, &authorization, &query).unwrap();
+/// assert_eq!(request.method, Method::PUT);
+/// assert_eq!(request.url.as_str(), "https://example.com/api/v1/service/42/nickname");
+/// assert_eq!(request.body.unwrap(), br#"{"nickname":"Home"}"#);
+///
+/// let response = HttpResponse {
+///     status: StatusCode::OK,
+///     headers: Default::default(),
+///     body: br#"{"nickname": "Home"}"#.to_vec(),
+/// };
+/// assert_eq!(parse_response(&query, response).unwrap().nickname, "Home");
+/// ```
+pub trait Query {
     /// Type of object used for query
     type Body: Serialize;
 
     /// Type of response to produce
     type Response: DeserializeOwned;
 
-    /// URL to use for query
-    fn path<'q>(&'q self) -> Cow<str>;
+    /// Whether the query may be safely retried even though its method is not idempotent
+    ///
+    /// `GET`, `PUT` and `DELETE` queries are always considered idempotent.
+    const IDEMPOTENT: bool = false;
+
+    /// Method with which to send the query
+    ///
+    /// By default queries with a body are sent as a `POST` and other queries as a `GET`.
+    fn method(&self) -> Method {
+        if self.body().is_some() {
+            Method::POST
+        } else {
+            Method::GET
+        }
+    }
+
+    /// Path of the query relative to the base URL of the API, such as `/service`
+    fn path(&self) -> Cow<'_, str>;
+
+    /// Parameters to add to the query string of the URL
+    fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        Vec::new()
+    }
 
-    /// Get the URL for the query
-    fn url(&self) -> Result<Url, Error> {
-        Ok(format!("{}{}", URL_PREFIX, self.path()).parse()?)
+    /// Version of the API to which to send the query, instead of that of the client
+    fn version(&self) -> Option<ApiVersion> {
+        None
     }
 
     /// Object to send for query
@@ -103,3 +1517,978 @@ pub(crate) trait Query {
         None
     }
 }
+
+/// Get the URL of a query to an API, using the given version unless the query chooses its own
+pub(crate) fn url<Q: Query>(api: &str, version: &ApiVersion, query: &Q) -> Result<Url, Error> {
+    let url = match query.version() {
+        Some(version) => join(api, &version, &query.path()),
+        None => join(api, version, &query.path()),
+    };
+    let mut url: Url = url
+        .parse()
+        .map_err(|err| Error::InvalidInput(format!("{}: {}", url, err)))?;
+
+    let params = query.query_params();
+    if !params.is_empty() {
+        url.query_pairs_mut().extend_pairs(params);
+    }
+    Ok(url)
+}
+
+/// Join a path relative to a version of an API onto its base URL
+fn join(api: &str, version: &ApiVersion, path: &str) -> String {
+    match version.as_str() {
+        "" => format!("{}{}", api, path),
+        version => format!("{}/{}{}", api, version, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use crate::{AuthError, Secret, StaticCredentials};
+
+    /// The path at which the server receives a query of `/test`
+    const TEST_PATH: &str = "/api/v1/test";
+
+    /// A client sending queries to a mock server with an authorization that never expires
+    fn builder(server: &MockServer) -> ClientBuilder {
+        let authorization = Authorization::from_tokens("access".to_owned(), None, None);
+        let base_url = Url::parse(&format!("{}/api", server.url())).unwrap();
+        Client::builder(authorization).base_url(base_url)
+    }
+
+    /// The path at which the server receives requests to refresh the authorization
+    const REFRESH_PATH: &str = "/api/auth/postRefreshToken";
+
+    /// A response to a request to refresh the authorization
+    const REFRESHED: &str = r#"{
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "access_token": "new-access",
+        "refresh_token": "new-refresh",
+        "persistLogin": false
+    }"#;
+
+    /// A client sending queries to a mock server with an authorization that it can refresh,
+    /// expiring in the given number of seconds
+    fn refreshable(server: &MockServer, expires_in: u64) -> ClientBuilder {
+        let mut authorization = Authorization::from_tokens(
+            "old-access".to_owned(),
+            Some("old-refresh".to_owned()),
+            Some(expires_in),
+        );
+        authorization.set_endpoints(server.endpoints());
+        let base_url = Url::parse(&format!("{}/api", server.url())).unwrap();
+        Client::builder(authorization).base_url(base_url)
+    }
+
+    /// A query of `/test` with a response of the given type
+    struct Test<T>(std::marker::PhantomData<T>);
+
+    impl<T: DeserializeOwned> Test<T> {
+        fn new() -> Self {
+            Test(std::marker::PhantomData)
+        }
+    }
+
+    impl<T: DeserializeOwned> Query for Test<T> {
+        type Body = ();
+        type Response = T;
+
+        fn path(&self) -> Cow<'_, str> {
+            "/test".into()
+        }
+    }
+
+    /// A query of `/test` with parameters
+    struct Params(Vec<(&'static str, &'static str)>);
+
+    impl Query for Params {
+        type Body = ();
+        type Response = Value;
+
+        fn path(&self) -> Cow<'_, str> {
+            "/test".into()
+        }
+
+        fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+            self.0.iter().map(|&(name, value)| (name.into(), value.into())).collect()
+        }
+    }
+
+    #[test]
+    fn query_params_are_encoded() {
+        let api = "https://api.invalid";
+        let version = ApiVersion::default();
+        let query = Params(vec![
+            ("name", "Jane Citizen"),
+            ("filter", "a&b=c"),
+            ("suburb", "Café Żółw"),
+        ]);
+
+        let url = url(api, &version, &query).unwrap();
+        let expected = "https://api.invalid/v1/test\
+            ?name=Jane+Citizen&filter=a%26b%3Dc&suburb=Caf%C3%A9+%C5%BB%C3%B3%C5%82w";
+        assert_eq!(url.as_str(), expected);
+
+        let pairs: Vec<_> = url.query_pairs().collect();
+        assert_eq!(pairs[1], ("filter".into(), "a&b=c".into()));
+        assert_eq!(pairs[2], ("suburb".into(), "Café Żółw".into()));
+    }
+
+    #[test]
+    fn queries_without_params_have_no_query_string() {
+        let url = url("https://api.invalid", &ApiVersion::default(), &Params(vec![])).unwrap();
+        assert_eq!(url.as_str(), "https://api.invalid/v1/test");
+        assert_eq!(url.query(), None);
+    }
+
+    #[tokio::test]
+    async fn query_params_are_received() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let client = builder(&server).build().unwrap();
+
+        let query = Params(vec![("from", "2020-01-01"), ("note", "a & b")]);
+        client.execute(&query).await.unwrap();
+
+        let received = server.received();
+        assert_eq!(received[0].path, TEST_PATH);
+        assert_eq!(received[0].query.as_deref(), Some("from=2020-01-01&note=a+%26+b"));
+    }
+
+    #[tokio::test]
+    async fn successful_response_is_parsed() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, r#"{"ok": true}"#));
+        let client = builder(&server).build().unwrap();
+
+        let response = client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(response, serde_json::json!({"ok": true}));
+    }
+
+    #[tokio::test]
+    async fn no_content_is_parsed_as_unit() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::empty(204));
+        let client = builder(&server).build().unwrap();
+
+        client.execute(&Test::<()>::new()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn queries_are_sent_to_the_base_url() {
+        let server = MockServer::start().await;
+        let path = "/custom/base/v1/test";
+        server.respond(Method::GET, path, MockResponse::json(200, "{}"));
+        let authorization = Authorization::from_tokens("access".to_owned(), None, None);
+        let base_url = Url::parse(&format!("{}/custom/base/", server.url())).unwrap();
+        let client = Client::builder(authorization).base_url(base_url).build().unwrap();
+
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(server.count(path), 1);
+        assert_eq!(server.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn slow_responses_time_out() {
+        let server = MockServer::start().await;
+        let slow = MockResponse::json(200, "{}").delay(Duration::from_secs(5));
+        server.respond(Method::GET, TEST_PATH, slow);
+        let client = builder(&server).timeout(Duration::from_millis(1)).build().unwrap();
+
+        let started = Instant::now();
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::Timeout(_)), "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn raw_responses_pass_through() {
+        let server = MockServer::start().await;
+        let body = r#"{"nested": {"list": [1, 2.5, "three"]}, "empty": null}"#;
+        let response = MockResponse::json(200, body).header("x-custom", "custom value");
+        server.respond(Method::GET, TEST_PATH, response);
+        server.respond(Method::POST, TEST_PATH, MockResponse::json(201, "not json"));
+        let client = builder(&server).build().unwrap();
+
+        let raw = client.get_raw("/test").await.unwrap();
+        assert_eq!(raw.status, StatusCode::OK);
+        assert_eq!(raw.headers["x-custom"], "custom value");
+        assert_eq!(raw.body, serde_json::from_str::<Value>(body).unwrap());
+
+        let sent = serde_json::json!({"key": ["value", 1, true]});
+        let raw = client.post_raw("/test", &sent).await.unwrap();
+        assert_eq!(raw.status, StatusCode::CREATED);
+        assert_eq!(raw.body, Value::String("not json".to_owned()));
+        assert_eq!(server.received()[1].json(), sent);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn requests_are_traced_without_their_authorization() {
+        use crate::mock::Capture;
+
+        let server = MockServer::start().await;
+        server.respond(Method::POST, TEST_PATH, MockResponse::json(200, "{}"));
+        let authorization = Authorization::from_tokens("secret-token".to_owned(), None, None);
+        let base_url = Url::parse(&format!("{}/api", server.url())).unwrap();
+        let client = Client::builder(authorization).base_url(base_url).build().unwrap();
+        let capture = Capture::default();
+        let _subscriber = tracing::subscriber::set_default(capture.clone());
+
+        client.post_raw("/test", &serde_json::json!({"key": "value"})).await.unwrap();
+
+        let span = capture.span("exetel.request").unwrap();
+        assert_eq!(span.field("path"), Some("/test"));
+        assert_eq!(span.field("method"), Some("POST"));
+        assert_eq!(span.field("status"), Some("200"));
+        assert!(span.field("elapsed_ms").is_some());
+        let request_id = &server.received()[0].headers[REQUEST_ID];
+        assert_eq!(span.field("request_id"), Some(request_id.to_str().unwrap()));
+
+        let events = capture.events();
+        let body = events.iter().find_map(|event| event.field("body")).unwrap();
+        assert_eq!(body, r#"{"key":"value"}"#);
+        for value in capture.values() {
+            assert!(!value.contains("secret-token"), "token recorded in {}", value);
+        }
+    }
+
+    /// A query of `/test` sent with the given method and body
+    struct Verb(Method, Option<Value>);
+
+    impl Query for Verb {
+        type Body = Value;
+        type Response = ();
+
+        fn method(&self) -> Method {
+            self.0.clone()
+        }
+
+        fn path(&self) -> Cow<'_, str> {
+            "/test".into()
+        }
+
+        fn body(&self) -> Option<&Value> {
+            self.1.as_ref()
+        }
+    }
+
+    #[tokio::test]
+    async fn queries_are_sent_with_their_method() {
+        let server = MockServer::start().await;
+        let body = serde_json::json!({"name": "Jane"});
+        let queries = vec![
+            (Verb(Method::GET, None), MockResponse::json(200, "null")),
+            (Verb(Method::POST, Some(body.clone())), MockResponse::json(200, "null")),
+            (Verb(Method::PUT, Some(body.clone())), MockResponse::empty(204)),
+            (Verb(Method::PATCH, Some(body.clone())), MockResponse::empty(204)),
+            (Verb(Method::DELETE, None), MockResponse::empty(204)),
+        ];
+        for (query, response) in &queries {
+            server.respond(query.0.clone(), TEST_PATH, response.clone());
+        }
+        let client = builder(&server).build().unwrap();
+
+        for (query, _) in &queries {
+            client.execute(query).await.unwrap();
+        }
+
+        let received = server.received();
+        assert_eq!(received.len(), queries.len());
+        for (received, (query, _)) in received.iter().zip(&queries) {
+            assert_eq!(received.method, query.0);
+            assert_eq!(received.path, TEST_PATH);
+            match &query.1 {
+                Some(body) => {
+                    assert_eq!(&received.json(), body, "{}", query.0);
+                    assert_eq!(received.headers[CONTENT_TYPE], "application/json");
+                }
+                None => assert!(received.body.is_empty(), "{}", query.0),
+            }
+        }
+    }
+
+    #[test]
+    fn rejected_authorization_requires_authentication() {
+        for status in [StatusCode::UNAUTHORIZED, StatusCode::FORBIDDEN].iter() {
+            let err = transport::check_status(*status, &HeaderMap::new()).unwrap_err();
+            assert!(matches!(err, Error::ReauthenticationRequired), "{}: {:?}", status, err);
+        }
+    }
+
+    #[tokio::test]
+    async fn unauthorized_without_refresh_token_fails() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(401, "{}"));
+        let client = builder(&server).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert!(matches!(err, Error::Auth(AuthError::NoRefreshToken)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_reports_retry_after() {
+        let server = MockServer::start().await;
+        let response = MockResponse::json(429, "{}").header("retry-after", "120");
+        server.respond(Method::GET, TEST_PATH, response);
+        let client = builder(&server).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        match err.inner() {
+            Error::RateLimited(info) => {
+                assert_eq!(info.retry_after, Some(Duration::from_secs(120)));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limited_without_retry_after() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(429, "{}"));
+        let client = builder(&server).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        match err.inner() {
+            Error::RateLimited(info) => assert_eq!(info.retry_after, None),
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn server_error_keeps_status_and_body() {
+        let server = MockServer::start().await;
+        let page = MockResponse::json(500, "<html>Internal Server Error</html>")
+            .header("content-type", "text/html");
+        server.respond(Method::GET, TEST_PATH, page);
+        let client = builder(&server).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        match err.inner() {
+            Error::Api { status, code: None, message } => {
+                assert_eq!(*status, StatusCode::INTERNAL_SERVER_ERROR);
+                assert_eq!(message, "<html>Internal Server Error</html>");
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    /// A policy retrying quickly, so that tests don't wait
+    fn quick_retries() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(503, "{}"));
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(502, "{}"));
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let client = builder(&server).retry(quick_retries()).build().unwrap();
+
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(server.received().len(), 3);
+        assert_eq!(client.requests_made(), 3);
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_report_attempts() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(503, "{}"));
+        let client = builder(&server).retry(quick_retries()).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert!(matches!(err, Error::RetriesExhausted { attempts: 3, .. }), "{:?}", err);
+        assert_eq!(server.received().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn long_retry_after_is_clamped() {
+        let server = MockServer::start().await;
+        let limited = MockResponse::json(429, "{}").header("retry-after", "3600");
+        server.respond(Method::GET, TEST_PATH, limited);
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let client = builder(&server).retry(quick_retries()).build().unwrap();
+
+        let query = Test::<Value>::new();
+        time::timeout(Duration::from_secs(5), client.execute(&query)).await.unwrap().unwrap();
+        assert_eq!(server.received().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failures_that_are_not_transient_are_not_retried() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(500, "{}"));
+        let client = builder(&server).retry(quick_retries()).build().unwrap();
+
+        client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert_eq!(server.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn posts_are_not_retried() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, TEST_PATH, MockResponse::json(503, "{}"));
+        let client = builder(&server).retry(quick_retries()).build().unwrap();
+
+        client.post_raw("/test", &serde_json::json!({})).await.unwrap_err();
+        assert_eq!(server.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_wait_up_to_the_ceiling() {
+        let server = MockServer::start().await;
+        let limited = MockResponse::json(429, "{}").header("retry-after", "1");
+        server.respond(Method::GET, TEST_PATH, limited);
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let ceiling = Duration::from_secs(1);
+        let client = builder(&server).wait_on_rate_limit(ceiling).build().unwrap();
+
+        let started = Instant::now();
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert!(started.elapsed() >= ceiling);
+        assert_eq!(server.count(TEST_PATH), 2);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_beyond_the_ceiling_fail() {
+        let server = MockServer::start().await;
+        let limited = MockResponse::json(429, "{}").header("retry-after", "120");
+        server.respond(Method::GET, TEST_PATH, limited);
+        let ceiling = Duration::from_secs(1);
+        let client = builder(&server).wait_on_rate_limit(ceiling).build().unwrap();
+
+        let started = Instant::now();
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::RateLimited(_)), "{:?}", err);
+        assert!(started.elapsed() < ceiling);
+        assert_eq!(server.count(TEST_PATH), 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_delays_requests_beyond_the_limit() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let interval = Duration::from_millis(200);
+        let client = builder(&server).rate_limit(10, interval).build().unwrap();
+
+        let started = Instant::now();
+        let queries: Vec<_> = (0..11)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.execute(&Test::<Value>::new()).await })
+            })
+            .collect();
+        for query in queries {
+            query.await.unwrap().unwrap();
+        }
+
+        assert!(started.elapsed() >= interval, "took {:?}", started.elapsed());
+        assert_eq!(server.received().len(), 11);
+    }
+
+    #[tokio::test]
+    async fn cached_responses_are_replayed() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, r#"{"n": 1}"#));
+        let ttl = CachePolicy::ttl(Duration::from_secs(300));
+        let client = builder(&server).cache(ttl).build().unwrap();
+
+        for _ in 0..3 {
+            let response = client.execute(&Test::<Value>::new()).await.unwrap();
+            assert_eq!(response["n"], 1);
+        }
+        assert_eq!(server.received().len(), 1);
+
+        let fresh = QueryOptions { no_cache: true, ..Default::default() };
+        client.execute_with(&Test::<Value>::new(), fresh).await.unwrap();
+        assert_eq!(server.received().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_misses_share_a_request() {
+        let server = MockServer::start().await;
+        let slow = MockResponse::json(200, "{}").delay(Duration::from_millis(100));
+        server.respond(Method::GET, TEST_PATH, slow);
+        let ttl = CachePolicy::ttl(Duration::from_secs(300));
+        let client = builder(&server).cache(ttl).build().unwrap();
+
+        let queries: Vec<_> = (0..5)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.execute(&Test::<Value>::new()).await })
+            })
+            .collect();
+        for query in queries {
+            query.await.unwrap().unwrap();
+        }
+        assert_eq!(server.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn invalidated_responses_are_fetched_again() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let ttl = CachePolicy::ttl(Duration::from_secs(300));
+        let client = builder(&server).cache(ttl).build().unwrap();
+
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        client.invalidate("/test");
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(server.received().len(), 2);
+
+        client.invalidate_all();
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(server.received().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn posts_are_not_cached() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, TEST_PATH, MockResponse::json(200, "{}"));
+        let ttl = CachePolicy::ttl(Duration::from_secs(300));
+        let client = builder(&server).cache(ttl).build().unwrap();
+
+        let body = serde_json::json!({});
+        let query = RawQuery { path: "/test", body: Some(&body) };
+        client.execute(&query).await.unwrap();
+        client.execute(&query).await.unwrap();
+        assert_eq!(server.received().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn deadline_abandons_slow_queries() {
+        let server = MockServer::start().await;
+        let slow = MockResponse::json(200, "{}").delay(Duration::from_millis(500));
+        server.respond(Method::GET, TEST_PATH, slow);
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        // Only one request may be sent at a time, so a permit kept by the abandoned query would
+        // hold up the next
+        let client = builder(&server).rate_limit(1, Duration::from_millis(10)).build().unwrap();
+
+        let deadline = Some(Duration::from_millis(50));
+        let options = QueryOptions { deadline, ..Default::default() };
+        let err = client.execute_with(&Test::<Value>::new(), options).await.unwrap_err();
+        assert!(matches!(err, Error::Deadline { .. }), "{:?}", err);
+
+        let query = Test::<Value>::new();
+        let next = time::timeout(Duration::from_millis(250), client.execute(&query));
+        next.await.expect("abandoned query released the client").unwrap();
+    }
+
+    #[tokio::test]
+    async fn dropping_a_query_while_refreshing_releases_the_authorization() {
+        let server = MockServer::start().await;
+        let slow = MockResponse::json(200, REFRESHED).delay(Duration::from_millis(500));
+        server.respond(Method::POST, REFRESH_PATH, slow);
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, REFRESHED));
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+
+        // Expiring within the refresh window, so each query refreshes it first
+        let client = refreshable(&server, 60).build().unwrap();
+
+        let query = Test::<Value>::new();
+        let dropped = time::timeout(Duration::from_millis(50), client.execute(&query)).await;
+        assert!(dropped.is_err());
+
+        let next = time::timeout(Duration::from_millis(250), client.execute(&query));
+        next.await.expect("dropped query released the authorization").unwrap();
+        assert_eq!(client.authorization().await.access_token().expose(), "new-access");
+    }
+
+    #[tokio::test]
+    async fn rejected_token_is_refreshed_and_retried_once() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(401, "{}"));
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, r#"{"ok": true}"#));
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, REFRESHED));
+        let client = refreshable(&server, 3600).build().unwrap();
+
+        let response = client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(response["ok"], true);
+        assert_eq!(server.count(REFRESH_PATH), 1);
+        assert_eq!(server.count(TEST_PATH), 2);
+
+        let bearers: Vec<_> = server
+            .received()
+            .into_iter()
+            .filter(|received| received.path == TEST_PATH)
+            .map(|received| received.headers[AUTHORIZATION].to_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(bearers, ["Bearer old-access", "Bearer new-access"]);
+    }
+
+    #[tokio::test]
+    async fn token_rejected_after_refreshing_is_not_retried_again() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(401, "{}"));
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(200, REFRESHED));
+        let client = refreshable(&server, 3600).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::ReauthenticationRequired), "{:?}", err);
+        assert_eq!(server.count(REFRESH_PATH), 1);
+        assert_eq!(server.count(TEST_PATH), 2);
+    }
+
+    #[tokio::test]
+    async fn requests_are_sent_as_built() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, TEST_PATH, MockResponse::json(200, "{}"));
+        let client = builder(&server).build().unwrap();
+
+        let body = serde_json::json!({"key": "value"});
+        client.post_raw("/test", &body).await.unwrap();
+
+        let query = RawQuery { path: "/test", body: Some(&body) };
+        let api = format!("{}/api", server.url());
+        let authorization = client.authorization().await;
+        let built = transport::build_request(&api, &ApiVersion::V1, &authorization, &query);
+        let built = built.unwrap();
+
+        let received = &server.received()[0];
+        assert_eq!(received.path, built.url.path());
+        assert_eq!(Some(received.body.clone()), built.body);
+        for (name, value) in &built.headers {
+            assert_eq!(&received.headers[name], value, "{}", name);
+        }
+        assert!(received.headers.contains_key(REQUEST_ID));
+    }
+
+    /// The path at which the server receives requests to log in
+    const LOGIN_PATH: &str = "/api/auth/postLogin";
+
+    /// A client that can log in again with fixed credentials once its refresh token is rejected
+    fn with_credentials(server: &MockServer) -> Client {
+        server.respond(Method::POST, REFRESH_PATH, MockResponse::json(401, "{}"));
+        let credentials = StaticCredentials::new("user".to_owned(), Secret::new("pass".to_owned()));
+        refreshable(server, 0).credentials(credentials).build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejected_refresh_logs_in_again() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(200, REFRESHED));
+        let client = with_credentials(&server);
+
+        client.execute(&Test::<Value>::new()).await.unwrap();
+
+        assert_eq!(server.count(REFRESH_PATH), 1);
+        let login = server.received().into_iter().find(|r| r.path == LOGIN_PATH).unwrap();
+        assert_eq!(login.json()["username"], "user");
+        assert_eq!(login.json()["password"], "pass");
+        let test = server.received().into_iter().find(|r| r.path == TEST_PATH).unwrap();
+        assert_eq!(test.headers[AUTHORIZATION], "Bearer new-access");
+        assert_eq!(client.authorization().await.bearer().unwrap(), "Bearer new-access");
+    }
+
+    #[tokio::test]
+    async fn failure_to_log_in_again_is_reported() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        server.respond(Method::POST, LOGIN_PATH, MockResponse::json(401, "{}"));
+        let client = with_credentials(&server);
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        let invalid = matches!(err.inner(), Error::Auth(AuthError::InvalidCredentials));
+        assert!(invalid, "{:?}", err);
+        assert_eq!(server.count(REFRESH_PATH), 1);
+        assert_eq!(server.count(LOGIN_PATH), 1);
+        assert_eq!(server.count(TEST_PATH), 0);
+    }
+
+    #[tokio::test]
+    async fn snapshot_queries_concurrently() {
+        let server = MockServer::start().await;
+        let delay = Duration::from_millis(300);
+        let services = include_str!("../fixtures/services.json");
+        let usage = r#"{"data": {
+            "dataUsage": "12.5GB of 20GB",
+            "nationalCallMinutes": 42,
+            "smsCount": 7,
+            "internationalUsage": "$1.50",
+            "resetDate": "2026-10-15"
+        }}"#;
+        let invoices = r#"{"data": [{
+            "invoiceNumber": "INV-1",
+            "issueDate": "2026-09-01",
+            "dueDate": "2026-09-15",
+            "total": "$79.99",
+            "amountOutstanding": "$0.00",
+            "status": "Paid"
+        }]}"#;
+        let responses = [
+            ("/api/v1/service", MockResponse::json(200, services)),
+            ("/api/v1/mobile/2000001/usage", MockResponse::json(200, usage)),
+            ("/api/v1/invoice", MockResponse::json(200, invoices)),
+            ("/api/v1/account/balance", MockResponse::json(500, "{}")),
+        ];
+        for (path, response) in responses.iter() {
+            server.respond(Method::GET, path, response.clone().delay(delay));
+        }
+        let client = builder(&server).build().unwrap();
+
+        let started = Instant::now();
+        let snapshot = client.snapshot().await;
+        let elapsed = started.elapsed();
+
+        // The usage waits for the services, but the invoices and balance are queried alongside
+        assert!(elapsed >= delay * 2, "{:?}", elapsed);
+        assert!(elapsed < delay * 3, "{:?}", elapsed);
+        assert_eq!(snapshot.services.unwrap().mobile().len(), 1);
+        assert_eq!(snapshot.usage.len(), 1);
+        let (id, usage) = &snapshot.usage[0];
+        assert_eq!(id.to_string(), "2000001");
+        assert_eq!(usage.as_ref().unwrap().sms_count, 7);
+        assert_eq!(snapshot.invoices.unwrap()[0].invoice_number.as_ref(), "INV-1");
+        assert!(snapshot.balance.is_err());
+    }
+
+    /// An observer recording each event it receives, labelled with its name
+    struct Recording(&'static str, Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl RequestObserver for Recording {
+        fn on_response(&self, path: &str, status: StatusCode, _: Duration, attempt: u32) {
+            let event = format!("{}: {} {} #{}", self.0, path, status.as_u16(), attempt);
+            self.1.lock().unwrap().push(event);
+        }
+
+        fn on_error(&self, path: &str, kind: &crate::ErrorKind, attempt: u32) {
+            let event = format!("{}: {} {:?} #{}", self.0, path, kind, attempt);
+            self.1.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn observers_see_every_attempt() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(503, "{}"));
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        server.respond(Method::GET, "/api/v1/missing", MockResponse::json(404, "{}"));
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let counting = Arc::new(crate::CountingObserver::new());
+        let client = builder(&server)
+            .retry(quick_retries())
+            .observer(Recording("first", events.clone()))
+            .observer(Recording("second", events.clone()))
+            .observer(counting.clone())
+            .build()
+            .unwrap();
+
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        client.get_raw("/missing").await.unwrap_err();
+
+        let events = events.lock().unwrap();
+        let expected = [
+            "first: /api/v1/test 503 #1",
+            "first: /api/v1/test Api #1",
+            "second: /api/v1/test 503 #1",
+            "second: /api/v1/test Api #1",
+            "first: /api/v1/test 200 #2",
+            "second: /api/v1/test 200 #2",
+            "first: /api/v1/missing 404 #1",
+            "first: /api/v1/missing NotFound #1",
+            "second: /api/v1/missing 404 #1",
+            "second: /api/v1/missing NotFound #1",
+        ];
+        assert_eq!(*events, expected);
+        assert_eq!(counting.responses(), 3);
+        assert_eq!(counting.errors(), 2);
+    }
+
+    #[tokio::test]
+    async fn clones_share_their_authorization_across_tasks() {
+        let server = MockServer::start().await;
+        let services = include_str!("../fixtures/services.json");
+        let services = MockResponse::json(200, services).delay(Duration::from_millis(50));
+        server.respond(Method::GET, "/api/v1/service", services);
+        let refreshed = MockResponse::json(200, REFRESHED).delay(Duration::from_millis(50));
+        server.respond(Method::POST, REFRESH_PATH, refreshed);
+        let client = refreshable(&server, 0).build().unwrap();
+
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.services().await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(server.count(REFRESH_PATH), 1);
+        assert_eq!(server.count("/api/v1/service"), 4);
+        for received in server.received().iter().filter(|r| r.path == "/api/v1/service") {
+            assert_eq!(received.headers[AUTHORIZATION], "Bearer new-access");
+        }
+        assert_eq!(client.authorization().await.bearer().unwrap(), "Bearer new-access");
+    }
+
+    #[tokio::test]
+    async fn errors_carry_the_request_id_sent() {
+        let server = MockServer::start().await;
+        let failure = MockResponse::json(500, "{}").header("x-request-id", "server-123");
+        server.respond(Method::GET, TEST_PATH, failure);
+        let client = builder(&server).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        let sent = server.received()[0].headers[REQUEST_ID].to_str().unwrap().to_owned();
+        assert!(matches!(err, Error::Request { .. }), "{:?}", err);
+        assert_eq!(err.request_id(), Some(sent.parse().unwrap()));
+        assert_eq!(err.server_request_id(), Some("server-123"));
+    }
+
+    #[tokio::test]
+    async fn retries_are_sent_with_the_same_request_id() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(503, "{}"));
+        let policy = RetryPolicy { max_attempts: 2, ..quick_retries() };
+        let client = builder(&server).retry(policy).build().unwrap();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        let received = server.received();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].headers[REQUEST_ID], received[1].headers[REQUEST_ID]);
+        let sent = received[0].headers[REQUEST_ID].to_str().unwrap();
+        assert_eq!(err.request_id(), Some(sent.parse().unwrap()));
+        assert_eq!(err.server_request_id(), None);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn compressed_responses_are_decompressed() {
+        let server = MockServer::start().await;
+        let compressed = include_bytes!("../fixtures/services.json.gz");
+        let response = MockResponse::bytes(200, "application/json", compressed)
+            .header("content-encoding", "gzip");
+        server.respond(Method::GET, "/api/v1/service", response);
+        let client = builder(&server).build().unwrap();
+
+        let services = client.services().await.unwrap();
+        assert_eq!(services.mobile().len(), 1);
+        let encodings = &server.received()[0].headers["accept-encoding"];
+        assert!(encodings.to_str().unwrap().contains("gzip"), "{:?}", encodings);
+
+        let client = builder(&server).no_compression().build().unwrap();
+        let err = client.services().await.unwrap_err();
+        assert!(matches!(err.inner(), Error::Deserialize { .. }), "{:?}", err);
+        assert!(!server.received()[1].headers.contains_key("accept-encoding"));
+    }
+
+    /// A hash of some bytes, for comparing large bodies
+    fn hash_of(bytes: &[u8]) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[tokio::test]
+    async fn downloads_are_streamed_in_chunks() {
+        let server = MockServer::start().await;
+        let body: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let response = MockResponse::bytes(200, "text/csv", &body)
+            .header("content-disposition", "attachment; filename=\"usage.csv\"")
+            .chunked(8 * 1024);
+        server.respond(Method::GET, "/api/v1/export", response);
+        let client = builder(&server).build().unwrap();
+
+        let mut downloaded = Vec::new();
+        let info = client.download("/export", &mut downloaded).await.unwrap();
+        assert_eq!(info.length, body.len() as u64);
+        assert_eq!(info.content_type.as_deref(), Some("text/csv"));
+        assert_eq!(info.filename.as_deref(), Some("usage.csv"));
+        assert_eq!(hash_of(&downloaded), hash_of(&body));
+        assert_eq!(downloaded.len(), body.len());
+    }
+
+    #[tokio::test]
+    async fn full_responses_include_their_headers() {
+        let server = MockServer::start().await;
+        let response = MockResponse::json(201, r#"{"ok": true}"#)
+            .header("x-ratelimit-remaining", "41")
+            .header("x-request-id", "server-123")
+            .delay(Duration::from_millis(20));
+        server.respond(Method::GET, TEST_PATH, response);
+        let client = builder(&server).build().unwrap();
+
+        let response = client.execute_full(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(response.body, serde_json::json!({"ok": true}));
+        assert_eq!(response.status, StatusCode::CREATED);
+        assert_eq!(response.headers["x-ratelimit-remaining"], "41");
+        assert_eq!(response.headers["x-request-id"], "server-123");
+        assert_eq!(response.headers[CONTENT_TYPE], "application/json");
+        assert!(response.elapsed >= Duration::from_millis(20), "{:?}", response.elapsed);
+    }
+
+    #[tokio::test]
+    async fn connections_are_reused_unless_pooling_is_disabled() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+
+        let client = builder(&server)
+            .pool_idle_timeout(Some(Duration::from_secs(30)))
+            .tcp_keepalive(Some(Duration::from_secs(15)))
+            .build()
+            .unwrap();
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(server.connections(), 1);
+
+        let client = builder(&server).pool_max_idle_per_host(0).build().unwrap();
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(server.connections(), 3);
+        assert_eq!(server.count(TEST_PATH), 4);
+    }
+
+    #[tokio::test]
+    async fn exhausted_budget_fails_without_connecting() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(503, "{}"));
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let client = builder(&server)
+            .retry(quick_retries())
+            .max_requests(NonZeroU32::new(2).unwrap())
+            .pool_max_idle_per_host(0)
+            .build()
+            .unwrap();
+
+        // The retry is counted against the budget
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(client.requests_made(), 2);
+        let connections = server.connections();
+
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::BudgetExhausted), "{:?}", err);
+        assert_eq!(client.requests_made(), 2);
+        assert_eq!(server.connections(), connections);
+        assert_eq!(server.count(TEST_PATH), 2);
+    }
+
+    #[tokio::test]
+    async fn budget_is_replenished_each_interval() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, TEST_PATH, MockResponse::json(200, "{}"));
+        let interval = Duration::from_millis(100);
+        let client = builder(&server).max_requests_per(interval, 1).build().unwrap();
+
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        let err = client.execute(&Test::<Value>::new()).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::BudgetExhausted), "{:?}", err);
+
+        time::delay_for(interval).await;
+        client.execute(&Test::<Value>::new()).await.unwrap();
+        assert_eq!(server.count(TEST_PATH), 2);
+    }
+}
@@ -1,17 +1,156 @@
-/// Queries relating to a particular customer
+//! Queries relating to a particular customer
 
 use crate::Query;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::DeserializeOwned;
 use std::borrow::Cow;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::str::FromStr;
-use anyhow::Error;
+use crate::Error;
 use std::fmt;
 use std::convert::TryFrom;
-use chrono::NaiveDate;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Deref, Mul, Neg, Sub, SubAssign};
+use std::time::Duration;
+use chrono::{DateTime, Datelike, FixedOffset, Months, NaiveDate, Utc};
 
-#[derive(Debug, Deserialize)]
+mod addon;
+mod alert;
+mod balance;
+mod cancellation;
+mod contact;
+mod dates;
+mod detail;
+mod invoice;
+mod ip;
+mod line;
+mod notification;
+mod order;
+mod outage;
+mod payment;
+mod plan;
+mod referral;
+mod suspension;
+mod ticket;
+mod usage;
+
+use dates::{flexible_date, flexible_date_opt};
+
+pub use addon::{
+    Addon, AddonId, AddonKind, GetAddons, Purchase, PurchaseAddon, PurchaseConfirmation,
+    PurchaseReceipt,
+};
+pub use alert::{AlertChannels, GetUsageAlerts, SetUsageAlerts, UsageAlertSettings};
+pub use balance::{AccountBalance, GetBalance};
+pub use cancellation::{
+    CancellationQuote, CancellationReceipt, CancellationRequest, Confirmation,
+    ConfirmCancellation, RequestCancellation,
+};
+pub use contact::{
+    ContactDetails, ContactUpdate, GetContact, NotificationPreferences, PostalAddress,
+    UpdateContact,
+};
+pub use dates::parse_date;
+pub use detail::{GetServiceDetail, NbnTechnology, ServiceDetail, StaticIp};
+pub use invoice::{DateRange, GetInvoices, Invoice, InvoiceId, InvoicePdf, InvoiceStatus};
+pub use ip::{GetIpDetails, IpAssignment, IpDetails, Ipv6Prefix};
+pub use line::{
+    BitRate, GetLineHistory, GetLineStats, GetSpeedTests, LineSample, LineStats, SpeedTestResult,
+};
+pub use notification::{GetNotificationSettings, NotificationSettings, SetNotificationSettings};
+pub use order::{Carrier, GetOrders, Order, OrderItem, OrderStatus};
+pub use outage::{GetOutages, Outage, OutageKind};
+pub use payment::{
+    CardExpiry, GetPaymentMethods, GetPayments, Payment, PaymentMethod, PaymentMethodKind,
+};
+pub use plan::{
+    GetAvailablePlans, PlanChange, PlanChangeReceipt, PlanChangeTiming, PlanId, PlanOption,
+    RequestPlanChange,
+};
+pub use referral::{GetReferrals, PendingReferral, ReferralInfo, ReferralStatus};
+pub use suspension::{ResumeService, ServiceStatus, SuspendService, Suspension};
+pub use ticket::{
+    CreateTicket, CreatedTicket, GetTicket, GetTickets, NewTicket, ReplyTicket, Ticket,
+    TicketCategory, TicketFilter, TicketId, TicketMessage, TicketReply, TicketStatus,
+    TicketSummary,
+};
+pub use usage::{
+    mask_number, CallHistory, CallRecord, CallType, DataAmount, DataUsage, Direction,
+    GetMobileUsage, GetRecords, MobileUsage, PhoneCallRecord, RecordPage, SmsRecord, UsageRecord,
+};
+
+/// The number identifying a service
+///
+/// The API gives the number either as a number or as a string of digits.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "NumberOrString")]
+#[serde(into = "u64")]
+pub struct ServiceId(u64);
+
+impl FromStr for ServiceId {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        text.trim()
+            .parse()
+            .map(ServiceId)
+            .map_err(|_| Error::InvalidInput(format!("invalid service identifier: {}", text)))
+    }
+}
+
+impl TryFrom<NumberOrString> for ServiceId {
+    type Error = Error;
+
+    fn try_from(id: NumberOrString) -> Result<Self, Error> {
+        // Only whole numbers within range, such as `1000001.0`, identify a service
+        let whole = |id: f64| id.fract() == 0.0 && (0.0..u64::MAX as f64).contains(&id);
+        match id {
+            NumberOrString::Number(id) => Ok(ServiceId(id)),
+            NumberOrString::Float(id) if whole(id) => Ok(ServiceId(id as u64)),
+            NumberOrString::Float(id) => {
+                Err(Error::InvalidInput(format!("invalid service identifier: {}", id)))
+            }
+            NumberOrString::Text(id) => id.parse(),
+        }
+    }
+}
+
+impl From<u64> for ServiceId {
+    fn from(id: u64) -> Self {
+        ServiceId(id)
+    }
+}
+
+impl From<ServiceId> for u64 {
+    fn from(id: ServiceId) -> u64 {
+        id.0
+    }
+}
+
+impl fmt::Display for ServiceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for ServiceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A value given by the API either as a number or as a string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(u64),
+    Float(f64),
+    Text(String),
+}
+
+/// The envelope in which most responses from the API are wrapped
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Data<T> {
     data: T,
 }
@@ -32,60 +171,516 @@ impl<T> Data<T> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// The services on an account
+///
+/// Services that cannot be parsed are skipped and kept as invalid, unless the `strict` feature is
+/// enabled, in which case they fail the whole listing. Invalid services are serialized as they
+/// were given by the API, in their original place among the services of their kind.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawServices")]
 pub struct Services {
-    #[serde(deserialize_with = "Data::proxy")]
     broadband: Vec<BroadbandService>,
-    #[serde(deserialize_with = "Data::proxy")]
     mobile: Vec<MobileService>,
-    #[serde(deserialize_with = "Data::proxy")]
     phone: Vec<PhoneService>,
-    #[serde(deserialize_with = "Data::proxy")]
     voip: Vec<VoipService>,
+    invalid: Vec<InvalidService>,
+}
+
+impl Serialize for Services {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeStruct};
+        let mut state = serializer.serialize_struct("Services", 4)?;
+        let broadband = self.entries("broadband", &self.broadband).map_err(S::Error::custom)?;
+        state.serialize_field("broadband", &Data { data: broadband })?;
+        let mobile = self.entries("mobile", &self.mobile).map_err(S::Error::custom)?;
+        state.serialize_field("mobile", &Data { data: mobile })?;
+        let phone = self.entries("phone", &self.phone).map_err(S::Error::custom)?;
+        state.serialize_field("phone", &Data { data: phone })?;
+        let voip = self.entries("voip", &self.voip).map_err(S::Error::custom)?;
+        state.serialize_field("voip", &Data { data: voip })?;
+        state.end()
+    }
+}
+
+/// The services on an account, before each has been parsed
+#[derive(Deserialize)]
+struct RawServices {
+    #[serde(deserialize_with = "Data::proxy")]
+    broadband: Vec<Value>,
+    #[serde(deserialize_with = "Data::proxy")]
+    mobile: Vec<Value>,
+    #[serde(deserialize_with = "Data::proxy")]
+    phone: Vec<Value>,
+    #[serde(deserialize_with = "Data::proxy")]
+    voip: Vec<Value>,
+}
+
+impl TryFrom<RawServices> for Services {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawServices) -> Result<Self, Self::Error> {
+        let mut invalid = Vec::new();
+        Ok(Services {
+            broadband: parse_services("broadband", raw.broadband, &mut invalid)?,
+            mobile: parse_services("mobile", raw.mobile, &mut invalid)?,
+            phone: parse_services("phone", raw.phone, &mut invalid)?,
+            voip: parse_services("voip", raw.voip, &mut invalid)?,
+            invalid,
+        })
+    }
+}
+
+/// Parse each of the services of a kind, keeping those that cannot be parsed as invalid
+fn parse_services<T: DeserializeOwned>(
+    kind: &'static str,
+    entries: Vec<Value>,
+    invalid: &mut Vec<InvalidService>,
+) -> Result<Vec<T>, serde_json::Error> {
+    let mut services = Vec::with_capacity(entries.len());
+    let mut invalid_of_kind = 0;
+    for entry in entries {
+        match T::deserialize(&entry) {
+            Ok(service) => services.push(service),
+            Err(err) if cfg!(feature = "strict") => return Err(err),
+            Err(err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(kind, error = %err, "skipping service that could not be parsed");
+                let position = services.len() + invalid_of_kind;
+                invalid_of_kind += 1;
+                invalid.push(InvalidService { kind, entry, error: err.to_string(), position });
+            }
+        }
+    }
+    Ok(services)
+}
+
+/// A service given by the API that could not be parsed
+#[derive(Debug, Clone)]
+pub struct InvalidService {
+    /// The kind of service, such as `broadband` or `mobile`
+    pub kind: &'static str,
+    /// The service as given by the API
+    pub entry: Value,
+    /// The reason the service could not be parsed
+    pub error: String,
+    /// The place of the service among those of its kind, as given by the API
+    position: usize,
+}
+
+impl Services {
+    /// The broadband services on the account
+    ///
+    /// ```
+    /// # use exetel_api::customer::{Data, Services};
+    /// # let services: Data<Services> =
+    /// #     serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+    /// # let services = services.unwrap();
+    /// for service in services.broadband() {
+    ///     println!("{} {}", service.as_ref().description(), service.as_ref().monthly_charge());
+    /// }
+    /// ```
+    pub fn broadband(&self) -> &[BroadbandService] {
+        &self.broadband
+    }
+
+    /// The mobile services on the account
+    pub fn mobile(&self) -> &[MobileService] {
+        &self.mobile
+    }
+
+    /// The phone services on the account
+    pub fn phone(&self) -> &[PhoneService] {
+        &self.phone
+    }
+
+    /// The VoIP services on the account
+    pub fn voip(&self) -> &[VoipService] {
+        &self.voip
+    }
+
+    /// Every service on the account, whatever its kind
+    ///
+    /// Broadband services are given first, then mobile, phone and VoIP services, each in the order
+    /// given by the API.
+    pub fn iter(&self) -> impl Iterator<Item = AnyService<'_>> {
+        let broadband = self.broadband.iter().map(AnyService::Broadband);
+        let mobile = self.mobile.iter().map(AnyService::Mobile);
+        let phone = self.phone.iter().map(AnyService::Phone);
+        let voip = self.voip.iter().map(AnyService::Voip);
+        broadband.chain(mobile).chain(phone).chain(voip)
+    }
+
+    /// The number of services on the account
+    pub fn len(&self) -> usize {
+        self.broadband.len() + self.mobile.len() + self.phone.len() + self.voip.len()
+    }
+
+    /// Check if there are no services on the account
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The services of the given type
+    pub fn of_type(&self, service_type: ServiceType) -> impl Iterator<Item = AnyService<'_>> {
+        self.iter().filter(move |service| *service.service_type() == service_type)
+    }
+
+    /// The services given by the API that could not be parsed
+    pub fn invalid(&self) -> &[InvalidService] {
+        &self.invalid
+    }
+
+    /// The services of a kind as given by the API, with those that could not be parsed in place
+    fn entries<T: Serialize>(
+        &self,
+        kind: &str,
+        services: &[T],
+    ) -> Result<Vec<Value>, serde_json::Error> {
+        let mut entries = services.iter().map(serde_json::to_value).collect::<Result<Vec<_>, _>>()?;
+        for invalid in self.invalid.iter().filter(|invalid| invalid.kind == kind) {
+            entries.insert(invalid.position.min(entries.len()), invalid.entry.clone());
+        }
+        Ok(entries)
+    }
+
+    /// Find the service with the given identifier
+    pub fn find_by_id(&self, id: ServiceId) -> Option<AnyService<'_>> {
+        self.iter().find(|service| service.id() == id)
+    }
+
+    /// Find the service with the given number, such as a phone number
+    pub fn find_by_service_number(&self, number: &str) -> Option<AnyService<'_>> {
+        self.iter().find(|service| service.service_number() == number)
+    }
+}
+
+/// The type of a service
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum ServiceType {
+    NbnBroadband,
+    Adsl,
+    Mobile,
+    Voip,
+    Phone,
+    /// A type not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for ServiceType {
+    fn from(service_type: String) -> Self {
+        match service_type.to_ascii_lowercase().as_str() {
+            "nbn" => ServiceType::NbnBroadband,
+            "adsl" => ServiceType::Adsl,
+            "mobile" => ServiceType::Mobile,
+            "voip" => ServiceType::Voip,
+            "phone" => ServiceType::Phone,
+            _ => ServiceType::Other(service_type),
+        }
+    }
+}
+
+impl From<ServiceType> for String {
+    fn from(service_type: ServiceType) -> String {
+        match service_type {
+            ServiceType::Other(service_type) => service_type,
+            service_type => format!("{}", service_type),
+        }
+    }
+}
+
+impl fmt::Display for ServiceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServiceType::NbnBroadband => write!(f, "NBN"),
+            ServiceType::Adsl => write!(f, "ADSL"),
+            ServiceType::Mobile => write!(f, "Mobile"),
+            ServiceType::Voip => write!(f, "VoIP"),
+            ServiceType::Phone => write!(f, "Phone"),
+            ServiceType::Other(service_type) => write!(f, "{}", service_type),
+        }
+    }
+}
+
+impl fmt::Debug for ServiceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A service of any kind
+#[derive(Debug, Clone, Copy)]
+pub enum AnyService<'a> {
+    Broadband(&'a BroadbandService),
+    Mobile(&'a MobileService),
+    Phone(&'a PhoneService),
+    Voip(&'a VoipService),
+}
+
+impl AsRef<Service> for AnyService<'_> {
+    fn as_ref(&self) -> &Service {
+        match self {
+            AnyService::Broadband(service) => service.as_ref(),
+            AnyService::Mobile(service) => service.as_ref(),
+            AnyService::Phone(service) => service.as_ref(),
+            AnyService::Voip(service) => service.as_ref(),
+        }
+    }
+}
+
+impl Deref for AnyService<'_> {
+    type Target = Service;
+
+    fn deref(&self) -> &Service {
+        self.as_ref()
+    }
 }
 
-pub(crate) struct GetServices;
+/// Query for the services on the account
+pub struct GetServices;
 
 impl Query for GetServices {
     type Body = ();
     type Response = Data<Services>;
 
-    fn path(&self) -> Cow<str> {
+    fn path(&self) -> Cow<'_, str> {
         "/service".into()
     }
 }
 
+/// The details common to every kind of service
+///
+/// The details of each kind of service are reached through `AsRef<Service>`.
+///
+/// ```
+/// # use exetel_api::customer::{Data, Services};
+/// # let services: Data<Services> =
+/// #     serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+/// # let services = services.unwrap();
+/// let service = services.mobile()[0].as_ref();
+/// assert_eq!(service.description(), "Mobile 20GB");
+/// assert_eq!(service.service_number(), "0400000000");
+/// assert_eq!(service.monthly_charge().to_string(), "$20.00");
+/// assert!(!service.in_contract());
+/// ```
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Service {
-    id: u64,
+pub struct Service {
+    id: ServiceId,
     description: String,
     monthly_charge: Price,
-    #[serde(serialize_with = "unparse_date")]
-    #[serde(deserialize_with = "parse_date")]
-    contract_start_date: NaiveDate,
-    #[serde(serialize_with = "unparse_date")]
-    #[serde(deserialize_with = "parse_date")]
-    contract_end_date: NaiveDate,
+    #[serde(default)]
+    #[serde(serialize_with = "unparse_optional_date")]
+    #[serde(deserialize_with = "flexible_date_opt")]
+    contract_start_date: Option<NaiveDate>,
+    #[serde(default)]
+    #[serde(serialize_with = "unparse_optional_date")]
+    #[serde(deserialize_with = "flexible_date_opt")]
+    contract_end_date: Option<NaiveDate>,
     current_contract: u64,
     billing_cycle_progress_percentage: Percentage,
     in_contract: bool,
-    payment_via: String,
-    payment_expiry: Option<String>,
+    payment_via: PaymentMethodKind,
+    payment_expiry: Option<CardExpiry>,
     plan_change: bool,
     service_number: String,
-    service_type: String,
+    service_type: ServiceType,
+    #[serde(default)]
+    status: ServiceStatus,
     #[serde(serialize_with = "unparse_short_date")]
-    #[serde(deserialize_with = "parse_short_date")]
+    #[serde(deserialize_with = "flexible_date")]
     next_billing_cycle_start: NaiveDate,
     #[serde(flatten)]
+    #[serde(deserialize_with = "parse_extra::<Service, _>")]
     rest: HashMap<String, Value>,
 }
 
+impl Service {
+    /// The number identifying the service
+    pub fn id(&self) -> ServiceId {
+        self.id
+    }
+
+    /// A description of the plan of the service
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The amount charged for the service each month
+    pub fn monthly_charge(&self) -> Price {
+        self.monthly_charge
+    }
+
+    /// The number of the service, such as its phone number
+    pub fn service_number(&self) -> &str {
+        &self.service_number
+    }
+
+    /// The fields of the service not otherwise known, as given by the API
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.rest
+    }
+
+    /// The type of the service
+    pub fn service_type(&self) -> &ServiceType {
+        &self.service_type
+    }
+
+    pub fn status(&self) -> &ServiceStatus {
+        &self.status
+    }
+
+    /// The length of the current contract, in months
+    pub fn current_contract(&self) -> u64 {
+        self.current_contract
+    }
+
+    /// The dates on which the contract started and ends, if the service has been on contract
+    ///
+    /// ```
+    /// # use exetel_api::customer::{Data, Services};
+    /// # let services: Data<Services> =
+    /// #     serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+    /// # let services = services.unwrap();
+    /// use chrono::NaiveDate;
+    ///
+    /// let start = NaiveDate::from_ymd_opt(2020, 7, 1).unwrap();
+    /// let end = NaiveDate::from_ymd_opt(2021, 7, 1).unwrap();
+    /// assert_eq!(services.broadband()[0].as_ref().contract_dates(), Some((start, end)));
+    /// assert_eq!(services.mobile()[0].as_ref().contract_dates(), None);
+    /// ```
+    pub fn contract_dates(&self) -> Option<(NaiveDate, NaiveDate)> {
+        Some((self.contract_start_date?, self.contract_end_date?))
+    }
+
+    /// The manner in which the service is paid for
+    pub fn payment_via(&self) -> &PaymentMethodKind {
+        &self.payment_via
+    }
+
+    /// The month in which the card used to pay for the service expires, if it is paid by card
+    pub fn payment_expiry(&self) -> Option<CardExpiry> {
+        self.payment_expiry
+    }
+
+    /// Whether the plan of the service can be changed
+    pub fn plan_change(&self) -> bool {
+        self.plan_change
+    }
+
+    /// The date on which the contract started, if the service has been on contract
+    pub fn contract_start_date(&self) -> Option<NaiveDate> {
+        self.contract_start_date
+    }
+
+    /// The date on which the contract ends, if the service has been on contract
+    pub fn contract_end_date(&self) -> Option<NaiveDate> {
+        self.contract_end_date
+    }
+
+    /// Whether the service is within a contract with a known end
+    pub fn in_contract(&self) -> bool {
+        self.in_contract && self.contract_end_date.is_some()
+    }
+
+    /// How far through the current billing cycle the service is
+    ///
+    /// ```
+    /// # use exetel_api::customer::{Data, Services};
+    /// # let services: Data<Services> =
+    /// #     serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+    /// # let services = services.unwrap();
+    /// let progress = services.broadband()[0].as_ref().billing_cycle_progress();
+    /// assert_eq!(progress.to_string(), "45%");
+    /// assert_eq!(progress.of(60), 27);
+    /// ```
+    pub fn billing_cycle_progress(&self) -> Percentage {
+        self.billing_cycle_progress_percentage
+    }
+
+    /// The date on which the next billing cycle starts
+    pub fn next_billing_cycle_start(&self) -> NaiveDate {
+        self.next_billing_cycle_start
+    }
+
+    /// The number of days from the given date until the next billing cycle starts
+    ///
+    /// ```
+    /// # use exetel_api::customer::{Data, Services};
+    /// # let services: Data<Services> =
+    /// #     serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+    /// # let services = services.unwrap();
+    /// use chrono::NaiveDate;
+    ///
+    /// let service = services.mobile()[0].as_ref();
+    /// let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+    /// assert_eq!(service.next_billing_cycle_start(), date(10, 15));
+    /// assert_eq!(service.days_until_next_cycle(date(10, 1)), 14);
+    /// ```
+    pub fn days_until_next_cycle(&self, today: NaiveDate) -> i64 {
+        (self.next_billing_cycle_start - today).num_days()
+    }
+
+    /// The first and last dates of the billing cycle containing the given date
+    ///
+    /// Billing cycles are taken to be monthly, starting on the same day of each month as the next
+    /// cycle, or the last day of a month that is too short. A cycle that would start or end beyond
+    /// the dates that can be represented is cut short at the first or last such date.
+    ///
+    /// ```
+    /// # use exetel_api::customer::{Data, Services};
+    /// # let services: Data<Services> =
+    /// #     serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+    /// # let services = services.unwrap();
+    /// use chrono::NaiveDate;
+    ///
+    /// let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+    /// let service = services.mobile()[0].as_ref();
+    /// assert_eq!(service.current_cycle_range(date(10, 1)), (date(9, 15), date(10, 14)));
+    /// assert_eq!(service.current_cycle_range(date(10, 15)), (date(10, 15), date(11, 14)));
+    /// ```
+    pub fn current_cycle_range(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let anchor = self.next_billing_cycle_start;
+        let cycle_start = |months: i32| {
+            let shift = Months::new(months.unsigned_abs());
+            match months {
+                months if months < 0 => anchor.checked_sub_months(shift),
+                _ => anchor.checked_add_months(shift),
+            }
+        };
+
+        // Find the first cycle starting after the date, then step back to the one containing it.
+        // Starting from the number of months between the dates, each loop takes at most a step or
+        // two, and stops once the cycles run past the dates that can be represented.
+        let months = (today.year() - anchor.year()) * 12 + today.month() as i32
+            - anchor.month() as i32;
+        let mut next = months;
+        while cycle_start(next).is_some_and(|start| start <= today) {
+            next += 1;
+        }
+        while cycle_start(next - 1).is_some_and(|start| start > today) {
+            next -= 1;
+        }
+        let start = cycle_start(next - 1).unwrap_or(NaiveDate::MIN);
+        let end = cycle_start(next).and_then(|start| start.pred_opt()).unwrap_or(NaiveDate::MAX);
+        (start, end)
+    }
+
+    /// The time remaining in the contract as of the given date, if the service is in contract
+    pub fn contract_remaining(&self, today: NaiveDate) -> Option<chrono::Duration> {
+        let end = self.contract_end_date.filter(|_| self.in_contract)?;
+        if end <= today {
+            return None;
+        }
+        Some(end - today)
+    }
+}
+
+/// A broadband service on the account
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct BroadbandService {
+pub struct BroadbandService {
     #[serde(flatten)]
     service: Service,
 }
@@ -96,9 +691,10 @@ impl AsRef<Service> for BroadbandService {
     }
 }
 
+/// A mobile service on the account
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct MobileService {
+pub struct MobileService {
     #[serde(flatten)]
     service: Service,
 }
@@ -109,9 +705,10 @@ impl AsRef<Service> for MobileService {
     }
 }
 
+/// A phone service on the account
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct PhoneService {
+pub struct PhoneService {
     #[serde(flatten)]
     service: Service,
 }
@@ -122,9 +719,10 @@ impl AsRef<Service> for PhoneService {
     }
 }
 
+/// A VoIP service on the account
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct VoipService {
+pub struct VoipService {
     #[serde(flatten)]
     service: Service,
 }
@@ -135,31 +733,88 @@ impl AsRef<Service> for VoipService {
     }
 }
 
-/// A monetary price
-#[derive(Copy, Clone, Serialize, Deserialize)]
+/// A monetary price, as a number of cents
+///
+/// Adding or multiplying prices panics on overflow, in release builds as well as debug builds,
+/// while subtracting saturates at zero. Use `checked_add`, `checked_mul` or `checked_sub` to
+/// detect a result that cannot be represented.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(try_from = "String")]
 #[serde(into = "String")]
-pub struct Price(u32);
+pub struct Price(u64);
+
+impl Price {
+    /// Construct a price from a number of cents
+    pub fn from_cents(cents: u64) -> Self {
+        Price(cents)
+    }
+
+    /// The number of cents in the price
+    pub fn cents(self) -> u64 {
+        self.0
+    }
+
+    /// The whole dollars in the price and the cents remaining
+    ///
+    /// The dollars saturate at `u32::MAX`; use `cents` for prices of more than that.
+    pub fn dollars_and_cents(self) -> (u32, u8) {
+        let dollars = u32::try_from(self.0 / 100).unwrap_or(u32::MAX);
+        (dollars, (self.0 % 100) as u8)
+    }
+
+    /// Add a price, unless the sum overflows
+    pub fn checked_add(self, other: Price) -> Option<Price> {
+        self.0.checked_add(other.0).map(Price)
+    }
+
+    /// Multiply the price by a quantity, unless the product overflows
+    pub fn checked_mul(self, quantity: u32) -> Option<Price> {
+        self.0.checked_mul(u64::from(quantity)).map(Price)
+    }
+
+    /// Subtract a price, unless it is greater than this price
+    pub fn checked_sub(self, other: Price) -> Option<Price> {
+        self.0.checked_sub(other.0).map(Price)
+    }
+}
 
 impl FromStr for Price {
     type Err = Error;
 
-    fn from_str(mut text: &str) -> Result<Self, Self::Err> {
-        if text.len() >= 1 && &text[0..1] == "$" {
-            text = &text[1..];
-        }
+    /// Parse a price such as `$1,234.56`, where the dollar sign and separators are optional
+    ///
+    /// Any fraction of a cent is rounded to the nearest cent, with half a cent rounded up.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidInput(format!("invalid price: {}", text));
+        let trimmed = text.trim();
+        let amount = trimmed.strip_prefix('$').unwrap_or(trimmed);
+        let (dollars, fraction) = amount.split_once('.').unwrap_or((amount, ""));
 
-        let mut value = 0;
-        let mut amounts = text.split('.').collect::<Vec<_>>();
-        if amounts.len() == 1 {
-            amounts.push("0");
+        let mut groups = dollars.split(',');
+        let first = groups.next().unwrap_or_default();
+        let separated = groups.all(|group| group.len() == 3) && first.len() <= 3;
+        if dollars.contains(',') && (!separated || first.is_empty()) {
+            return Err(invalid());
+        }
+        let dollars = dollars.replace(',', "");
+        let is_digits = |text: &str| text.bytes().all(|c| c.is_ascii_digit());
+        let is_empty = dollars.is_empty() && fraction.is_empty();
+        if is_empty || !is_digits(&dollars) || !is_digits(fraction) {
+            return Err(invalid());
         }
-        for amount in amounts.iter().take(2) {
-            value *= 100;
-            value += amount.parse::<u32>()?;
-        };
 
-        Ok(Price(value))
+        let dollars = match dollars.as_str() {
+            "" => 0,
+            dollars => dollars.parse::<u64>().map_err(|_| invalid())?,
+        };
+        let digits = fraction.as_bytes();
+        let digit = |place: usize| digits.get(place).map_or(0, |digit| u64::from(digit - b'0'));
+        let cents = digit(0) * 10 + digit(1) + u64::from(digit(2) >= 5);
+        dollars
+            .checked_mul(100)
+            .and_then(|dollars| dollars.checked_add(cents))
+            .map(Price)
+            .ok_or_else(invalid)
     }
 }
 
@@ -171,9 +826,9 @@ impl TryFrom<String> for Price {
     }
 }
 
-impl Into<String> for Price {
-    fn into(self) -> String {
-        format!("{}", self)
+impl From<Price> for String {
+    fn from(price: Price) -> String {
+        format!("{}", price)
     }
 }
 
@@ -189,58 +844,445 @@ impl fmt::Debug for Price {
     }
 }
 
-fn parse_short_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+impl Add for Price {
+    type Output = Price;
+
+    /// Add a price, panicking if the sum overflows
+    fn add(self, other: Price) -> Price {
+        self.checked_add(other).expect("price overflowed")
+    }
+}
+
+impl AddAssign for Price {
+    fn add_assign(&mut self, other: Price) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Price {
+    type Output = Price;
+
+    /// Subtract a price, giving zero if it is greater than this price
+    fn sub(self, other: Price) -> Price {
+        Price(self.0.saturating_sub(other.0))
+    }
+}
+
+impl SubAssign for Price {
+    fn sub_assign(&mut self, other: Price) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<u32> for Price {
+    type Output = Price;
+
+    /// Multiply the price by a quantity, panicking if the product overflows
+    fn mul(self, quantity: u32) -> Price {
+        self.checked_mul(quantity).expect("price overflowed")
+    }
+}
+
+impl Sum for Price {
+    fn sum<I: Iterator<Item = Price>>(prices: I) -> Price {
+        prices.fold(Price::default(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Price> for Price {
+    fn sum<I: Iterator<Item = &'a Price>>(prices: I) -> Price {
+        prices.copied().sum()
+    }
+}
+
+/// A monetary amount that may be negative, such as a refund
+///
+/// As with [`Price`], negating, adding and summing panic if the result overflows. Use
+/// `checked_neg` or `checked_add` to detect a result that cannot be represented.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
+pub struct SignedPrice(i64);
+
+impl SignedPrice {
+    /// Construct an amount from a number of cents
+    pub fn from_cents(cents: i64) -> Self {
+        SignedPrice(cents)
+    }
+
+    /// The number of cents in the amount
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    /// Check if the amount is negative
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    /// Negate the amount, unless the result overflows
+    pub fn checked_neg(self) -> Option<SignedPrice> {
+        self.0.checked_neg().map(SignedPrice)
+    }
+
+    /// Add an amount, unless the sum overflows
+    pub fn checked_add(self, other: SignedPrice) -> Option<SignedPrice> {
+        self.0.checked_add(other.0).map(SignedPrice)
+    }
+}
+
+impl FromStr for SignedPrice {
+    type Err = Error;
+
+    /// Parse a price such as `-$1,234.56` or `$-1,234.56`, as for an unsigned price
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let trimmed = text.trim();
+        match trimmed.strip_prefix('-').or_else(|| trimmed.strip_prefix("$-")) {
+            Some(magnitude) => Ok(-SignedPrice::from(magnitude.parse::<Price>()?)),
+            None => Ok(SignedPrice::from(trimmed.parse::<Price>()?)),
+        }
+    }
+}
+
+impl TryFrom<String> for SignedPrice {
+    type Error = Error;
+
+    fn try_from(text: String) -> Result<Self, Error> {
+        text.parse()
+    }
+}
+
+impl From<SignedPrice> for String {
+    fn from(price: SignedPrice) -> String {
+        format!("{}", price)
+    }
+}
+
+impl From<Price> for SignedPrice {
+    fn from(price: Price) -> Self {
+        SignedPrice(i64::try_from(price.0).unwrap_or(i64::MAX))
+    }
+}
+
+impl fmt::Display for SignedPrice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{}${}.{:02}", sign, cents / 100, cents % 100)
+    }
+}
+
+impl fmt::Debug for SignedPrice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Neg for SignedPrice {
+    type Output = SignedPrice;
+
+    /// Negate the amount, panicking if the result overflows
+    fn neg(self) -> SignedPrice {
+        self.checked_neg().expect("price overflowed")
+    }
+}
+
+impl Add for SignedPrice {
+    type Output = SignedPrice;
+
+    /// Add an amount, panicking if the sum overflows
+    fn add(self, other: SignedPrice) -> SignedPrice {
+        self.checked_add(other).expect("price overflowed")
+    }
+}
+
+impl AddAssign for SignedPrice {
+    fn add_assign(&mut self, other: SignedPrice) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for SignedPrice {
+    fn sum<I: Iterator<Item = SignedPrice>>(prices: I) -> SignedPrice {
+        prices.fold(SignedPrice::default(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a SignedPrice> for SignedPrice {
+    fn sum<I: Iterator<Item = &'a SignedPrice>>(prices: I) -> SignedPrice {
+        prices.copied().sum()
+    }
+}
+
+/// Parse the fields of an object not otherwise known
+///
+/// With the `strict` feature any such field is an error. Otherwise a warning is logged the first
+/// time each field is seen for a type.
+fn parse_extra<'de, T, D>(deserializer: D) -> Result<HashMap<String, Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let extra: HashMap<String, Value> = Deserialize::deserialize(deserializer)?;
+    if extra.is_empty() {
+        return Ok(extra);
+    }
+
+    let type_name = std::any::type_name::<T>();
+    let mut fields: Vec<&str> = extra.keys().map(String::as_str).collect();
+    fields.sort_unstable();
+
+    #[cfg(feature = "strict")]
+    {
+        use serde::de::Error;
+        Err(D::Error::custom(format!("unknown fields in {}: {}", type_name, fields.join(", "))))
+    }
+
+    #[cfg(not(feature = "strict"))]
+    {
+        #[cfg(feature = "tracing")]
+        {
+            use std::collections::HashSet;
+            use std::sync::{Mutex, OnceLock};
+
+            static SEEN: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+            let mut seen = SEEN.get_or_init(Default::default).lock().unwrap_or_else(|err| {
+                err.into_inner()
+            });
+            fields.retain(|field| seen.insert(format!("{}.{}", type_name, field)));
+            if !fields.is_empty() {
+                let fields = fields.join(", ");
+                tracing::warn!(type_name, %fields, "unknown fields in response");
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = (type_name, fields);
+        Ok(extra)
+    }
+}
+
+/// Parse a time given in the format of RFC 3339, such as `2024-03-05T14:30:00+11:00`
+fn parse_datetime<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
     D: Deserializer<'de>,
 {
     use serde::de::Error;
-    let text: &str = Deserialize::deserialize(deserializer)?;
-    NaiveDate::parse_from_str(text, "%e %b %y").map_err(|err| D::Error::custom(format!("{}", err)))
+    let text: String = Deserialize::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|err| D::Error::custom(format!("{}", err)))
 }
 
-fn parse_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+/// Parse a time given in the format of RFC 3339, keeping its offset from UTC
+fn parse_offset_datetime<'de, D>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error>
 where
     D: Deserializer<'de>,
 {
     use serde::de::Error;
-    let text: &str = Deserialize::deserialize(deserializer)?;
-    NaiveDate::parse_from_str(text, "%e %b %Y").map_err(|err| D::Error::custom(format!("{}", err)))
+    let text: String = Deserialize::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&text).map_err(|err| D::Error::custom(format!("{}", err)))
+}
+
+fn parse_optional_offset_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let text: Option<String> = Deserialize::deserialize(deserializer)?;
+    text.as_deref().map(DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|err| D::Error::custom(format!("{}", err)))
+}
+
+/// Parse a length of time given as `HH:MM:SS`
+fn parse_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let text: String = Deserialize::deserialize(deserializer)?;
+    let invalid = || D::Error::custom(format!("invalid duration: {}", text));
+    let mut seconds = 0;
+    let mut parts = 0;
+    for part in text.trim().split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().map_err(|_| invalid())?;
+        parts += 1;
+    }
+    if parts != 3 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(seconds))
 }
 
 fn unparse_short_date<S: Serializer>(
     date: &NaiveDate,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    serializer.serialize_str(&format!("{}", date.format("%e %b %y")))
+    serializer.serialize_str(&format!("{}", date.format("%-d %b %y")))
 }
 
 fn unparse_date<S: Serializer>(
     date: &NaiveDate,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    serializer.serialize_str(&format!("{}", date.format("%e %b %Y")))
+    serializer.serialize_str(&format!("{}", date.format("%-d %b %Y")))
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize)]
-#[serde(from = "u32")]
-#[serde(into = "u32")]
+fn unparse_optional_date<S: Serializer>(
+    date: &Option<NaiveDate>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match date {
+        Some(date) => unparse_date(date, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn unparse_iso_date<S: Serializer>(
+    date: &NaiveDate,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{}", date.format("%Y-%m-%d")))
+}
+
+fn unparse_optional_iso_date<S: Serializer>(
+    date: &Option<NaiveDate>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match date {
+        Some(date) => serializer.serialize_some(&format!("{}", date.format("%Y-%m-%d"))),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn unparse_datetime<S: Serializer>(
+    time: &DateTime<Utc>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339())
+}
+
+fn unparse_duration<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let seconds = duration.as_secs();
+    let text = format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    serializer.serialize_str(&text)
+}
+
+fn unparse_offset_datetime<S: Serializer>(
+    time: &DateTime<FixedOffset>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339())
+}
+
+fn unparse_optional_offset_datetime<S: Serializer>(
+    time: &Option<DateTime<FixedOffset>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_some(&time.to_rfc3339()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// A percentage, to a hundredth of a percent
+///
+/// Percentages are given by the API as whole or fractional numbers, or as strings such as `62.5%`,
+/// and must be between 0% and 100%. This is checked when they are read or made with `new`, but
+/// not when they are made from a whole number of percent.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "NumberOrString")]
 pub struct Percentage(u32);
 
+impl Percentage {
+    /// Hundredths of a percent in a percent
+    const SCALE: u32 = 100;
+
+    /// Construct a percentage, failing if it is not between 0% and 100%
+    pub fn new(percent: f64) -> Result<Self, Error> {
+        if !(0.0..=100.0).contains(&percent) {
+            let message = format!("percentage {} is not between 0% and 100%", percent);
+            return Err(Error::InvalidInput(message));
+        }
+        Ok(Percentage((percent * f64::from(Self::SCALE)).round() as u32))
+    }
+
+    /// The percentage as a number of percent
+    pub fn as_f64(self) -> f64 {
+        f64::from(self.0) / f64::from(Self::SCALE)
+    }
+
+    /// The proportion of a total given by the percentage, rounded down
+    ///
+    /// A proportion of more than `u64::MAX`, from a percentage over 100%, saturates.
+    pub fn of(self, total: u64) -> u64 {
+        let scale = u128::from(Self::SCALE) * 100;
+        u64::try_from(u128::from(total) * u128::from(self.0) / scale).unwrap_or(u64::MAX)
+    }
+}
+
 impl From<u32> for Percentage {
+    /// Construct a whole percentage, without checking that it is at most 100%
     fn from(percent: u32) -> Self {
-        Percentage(percent)
+        Percentage(percent.saturating_mul(Self::SCALE))
     }
 }
 
-impl Into<u32> for Percentage {
-    fn into(self) -> u32 {
-        self.0
+impl From<Percentage> for u32 {
+    /// The percentage rounded to the nearest whole percent
+    fn from(percent: Percentage) -> u32 {
+        let scale = u64::from(Percentage::SCALE);
+        ((u64::from(percent.0) + scale / 2) / scale) as u32
+    }
+}
+
+impl FromStr for Percentage {
+    type Err = Error;
+
+    /// Parse a percentage such as `62.5%`, where the percent sign is optional
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let trimmed = text.trim();
+        let number = trimmed.strip_suffix('%').unwrap_or(trimmed).trim_end();
+        let percent = number
+            .parse::<f64>()
+            .map_err(|_| Error::InvalidInput(format!("invalid percentage: {}", text)))?;
+        Percentage::new(percent)
+    }
+}
+
+impl TryFrom<NumberOrString> for Percentage {
+    type Error = Error;
+
+    fn try_from(percent: NumberOrString) -> Result<Self, Error> {
+        match percent {
+            NumberOrString::Number(percent) => Percentage::new(percent as f64),
+            NumberOrString::Float(percent) => Percentage::new(percent),
+            NumberOrString::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl Serialize for Percentage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 % Self::SCALE {
+            0 => serializer.serialize_u32(self.0 / Self::SCALE),
+            _ => serializer.serialize_f64(self.as_f64()),
+        }
     }
 }
 
 impl fmt::Display for Percentage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}%", self.0)
+        let (whole, hundredths) = (self.0 / Self::SCALE, self.0 % Self::SCALE);
+        match hundredths {
+            0 => write!(f, "{}%", whole),
+            _ if hundredths % 10 == 0 => write!(f, "{}.{}%", whole, hundredths / 10),
+            _ => write!(f, "{}.{:02}%", whole, hundredths),
+        }
     }
 }
 
@@ -249,3 +1291,532 @@ impl fmt::Debug for Percentage {
         fmt::Display::fmt(self, f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn services() -> Services {
+        let services: Data<Services> =
+            serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+        services.unwrap()
+    }
+
+    #[test]
+    fn service_types_are_case_insensitive() {
+        let cases = [
+            ("NBN", ServiceType::NbnBroadband),
+            ("nbn", ServiceType::NbnBroadband),
+            ("ADSL", ServiceType::Adsl),
+            ("mobile", ServiceType::Mobile),
+            ("VOIP", ServiceType::Voip),
+            ("Phone", ServiceType::Phone),
+        ];
+        for (text, expected) in cases.iter() {
+            let service_type: ServiceType = serde_json::from_value(Value::from(*text)).unwrap();
+            assert_eq!(service_type, *expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn unknown_service_types_round_trip_exactly() {
+        for text in ["Fibre 1000", "sat-nbn", " Padded ", "ÜNICODE"].iter() {
+            let json = serde_json::to_string(text).unwrap();
+            let service_type: ServiceType = serde_json::from_str(&json).unwrap();
+            assert_eq!(service_type, ServiceType::Other(text.to_string()));
+            assert_eq!(serde_json::to_string(&service_type).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn services_are_filtered_by_type() {
+        let services = services();
+        let ids = |service_type| {
+            services.of_type(service_type).map(|service| service.id()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(ServiceType::NbnBroadband), [ServiceId(1000001)]);
+        assert_eq!(ids(ServiceType::Mobile), [ServiceId(2000001)]);
+        assert_eq!(ids(ServiceType::Voip), [ServiceId(3000001)]);
+        assert!(ids(ServiceType::Phone).is_empty());
+        assert!(ids(ServiceType::Other("Fibre".to_owned())).is_empty());
+    }
+
+    /// The broadband service of the fixture, with its next billing cycle starting on a date
+    fn billed_from(year: i32, month: u32, day: u32) -> Service {
+        let mut services = services();
+        let mut service = services.broadband.remove(0).service;
+        service.next_billing_cycle_start = date(year, month, day);
+        service
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn billing_cycles_contain_the_date() {
+        let service = billed_from(2026, 11, 15);
+        let cycle = (date(2026, 10, 15), date(2026, 11, 14));
+        assert_eq!(service.current_cycle_range(date(2026, 10, 15)), cycle);
+        assert_eq!(service.current_cycle_range(date(2026, 10, 31)), cycle);
+        assert_eq!(service.current_cycle_range(date(2026, 11, 14)), cycle);
+        let next = (date(2026, 11, 15), date(2026, 12, 14));
+        assert_eq!(service.current_cycle_range(date(2026, 11, 15)), next);
+        let later = (date(2028, 2, 15), date(2028, 3, 14));
+        assert_eq!(service.current_cycle_range(date(2028, 3, 1)), later);
+        let earlier = (date(2019, 12, 15), date(2020, 1, 14));
+        assert_eq!(service.current_cycle_range(date(2020, 1, 1)), earlier);
+    }
+
+    #[test]
+    fn billing_cycles_anchored_at_the_end_of_a_month_end_in_short_months() {
+        let service = billed_from(2026, 1, 31);
+        let february = (date(2026, 1, 31), date(2026, 2, 27));
+        assert_eq!(service.current_cycle_range(date(2026, 2, 10)), february);
+        let march = (date(2026, 2, 28), date(2026, 3, 30));
+        assert_eq!(service.current_cycle_range(date(2026, 2, 28)), march);
+        let april = (date(2026, 3, 31), date(2026, 4, 29));
+        assert_eq!(service.current_cycle_range(date(2026, 4, 1)), april);
+        let previous = (date(2025, 12, 31), date(2026, 1, 30));
+        assert_eq!(service.current_cycle_range(date(2026, 1, 1)), previous);
+    }
+
+    #[test]
+    fn billing_cycles_anchored_on_a_leap_day_start_on_the_last_of_february() {
+        let service = billed_from(2024, 2, 29);
+        let leap = (date(2024, 2, 29), date(2024, 3, 28));
+        assert_eq!(service.current_cycle_range(date(2024, 3, 1)), leap);
+        let common = (date(2025, 2, 28), date(2025, 3, 28));
+        assert_eq!(service.current_cycle_range(date(2025, 3, 1)), common);
+        let next_leap = (date(2028, 1, 29), date(2028, 2, 28));
+        assert_eq!(service.current_cycle_range(date(2028, 2, 28)), next_leap);
+        let after = (date(2028, 2, 29), date(2028, 3, 28));
+        assert_eq!(service.current_cycle_range(date(2028, 2, 29)), after);
+    }
+
+    #[test]
+    fn billing_cycles_stop_at_the_limits_of_the_calendar() {
+        let service = billed_from(2026, 11, 15);
+        let last = (date(NaiveDate::MAX.year(), 12, 15), NaiveDate::MAX);
+        assert_eq!(service.current_cycle_range(NaiveDate::MAX), last);
+        let first = (NaiveDate::MIN, date(NaiveDate::MIN.year(), 1, 14));
+        assert_eq!(service.current_cycle_range(NaiveDate::MIN), first);
+    }
+
+    fn mixed_services() -> Services {
+        let services: Data<Services> =
+            serde_json::from_str(include_str!("../fixtures/services-mixed.json")).unwrap();
+        services.unwrap()
+    }
+
+    #[test]
+    fn services_are_iterated_by_kind_in_the_order_given() {
+        let services = mixed_services();
+        let ids: Vec<_> = services.iter().map(|service| u64::from(service.id())).collect();
+        let expected = [1000002, 1000001, 1000003, 2000002, 2000001, 4000001, 3000001];
+        assert_eq!(ids, expected);
+        assert_eq!(services.len(), 7);
+        assert!(!services.is_empty());
+        assert!(matches!(services.iter().nth(5), Some(AnyService::Phone(_))));
+        assert!(matches!(services.iter().last(), Some(AnyService::Voip(_))));
+    }
+
+    #[test]
+    fn services_are_found_by_id_and_number() {
+        let services = mixed_services();
+        let service = services.find_by_id(ServiceId(2000002)).unwrap();
+        assert!(matches!(service, AnyService::Mobile(_)));
+        assert_eq!(service.description(), "Mobile 40GB");
+        assert!(services.find_by_id(ServiceId(9999999)).is_none());
+
+        let service = services.find_by_service_number("0280000000").unwrap();
+        assert!(matches!(service, AnyService::Phone(_)));
+        assert_eq!(service.id(), ServiceId(4000001));
+        assert!(services.find_by_service_number("0400 000 000").is_none());
+        assert!(services.find_by_service_number("").is_none());
+    }
+
+    #[test]
+    fn services_of_a_type_keep_their_order() {
+        let services = mixed_services();
+        let ids = |service_type| {
+            services.of_type(service_type).map(|service| service.id()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(ServiceType::NbnBroadband), [ServiceId(1000002), ServiceId(1000001)]);
+        assert_eq!(ids(ServiceType::Adsl), [ServiceId(1000003)]);
+        assert_eq!(ids(ServiceType::Mobile), [ServiceId(2000002), ServiceId(2000001)]);
+        assert_eq!(ids(ServiceType::Phone), [ServiceId(4000001)]);
+
+        let none = serde_json::json!({"data": []});
+        let empty = serde_json::json!({
+            "broadband": none,
+            "mobile": none,
+            "phone": none,
+            "voip": none,
+        });
+        let empty: Services = serde_json::from_value(empty).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+        assert!(empty.find_by_id(ServiceId(1000001)).is_none());
+    }
+
+    #[test]
+    fn service_ids_are_read_from_numbers_and_strings() {
+        let id = |json: &str| serde_json::from_str::<ServiceId>(json);
+        assert_eq!(id("1000001").unwrap(), ServiceId(1000001));
+        assert_eq!(id(r#""1000001""#).unwrap(), ServiceId(1000001));
+        assert_eq!(id(r#"" 1000001 ""#).unwrap(), ServiceId(1000001));
+        assert_eq!(id("1000001.0").unwrap(), ServiceId(1000001));
+        assert_eq!(id("0").unwrap(), ServiceId(0));
+        assert_eq!(serde_json::to_string(&ServiceId(1000001)).unwrap(), "1000001");
+    }
+
+    #[test]
+    fn invalid_service_ids_are_rejected() {
+        for json in ["1000001.5", "-1", "-1.0", "1e20", r#""1000001.5""#, r#""""#, "null"].iter() {
+            assert!(serde_json::from_str::<ServiceId>(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn services_round_trip() {
+        let services = services();
+        let json = serde_json::to_value(&services).unwrap();
+        let reloaded: Services = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&reloaded).unwrap(), json);
+        assert_eq!(reloaded.len(), services.len());
+        assert_eq!(json["broadband"]["data"][0]["id"], 1000001);
+    }
+
+    #[test]
+    fn service_round_trips_exactly() {
+        let fixture: Value =
+            serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+        let entry = &fixture["data"]["broadband"]["data"][0];
+        let service: Service = serde_json::from_value(entry.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&service).unwrap(), *entry);
+
+        let reloaded: Service = serde_json::from_value(serde_json::to_value(&service).unwrap())
+            .unwrap();
+        assert_eq!(reloaded.contract_dates(), service.contract_dates());
+        assert_eq!(reloaded.next_billing_cycle_start(), service.next_billing_cycle_start());
+        assert_eq!(reloaded.monthly_charge(), service.monthly_charge());
+        assert_eq!(reloaded.billing_cycle_progress(), service.billing_cycle_progress());
+    }
+
+    #[test]
+    fn missing_contract_dates_round_trip() {
+        let services = services();
+        let service = &services.mobile()[0];
+        assert_eq!(service.as_ref().contract_dates(), None);
+        let json = serde_json::to_value(service).unwrap();
+        assert_eq!(json["contractStartDate"], Value::Null);
+        let reloaded: MobileService = serde_json::from_value(json).unwrap();
+        assert_eq!(reloaded.as_ref().contract_start_date(), None);
+        assert_eq!(reloaded.as_ref().contract_end_date(), None);
+    }
+
+    #[test]
+    fn single_digit_days_are_not_padded() {
+        #[derive(Serialize)]
+        struct Dates {
+            #[serde(serialize_with = "unparse_date")]
+            long: NaiveDate,
+            #[serde(serialize_with = "unparse_short_date")]
+            short: NaiveDate,
+        }
+
+        let date = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+        let json = serde_json::to_value(Dates { long: date, short: date }).unwrap();
+        assert_eq!(json["long"], "5 Mar 2026");
+        assert_eq!(json["short"], "5 Mar 26");
+        for text in [&json["long"], &json["short"]].iter() {
+            assert_eq!(dates::parse_date(text.as_str().unwrap()).unwrap(), date);
+        }
+    }
+
+    #[test]
+    fn prices_round_trip() {
+        for cents in [0, 5, 99, 100, 123_456, u64::MAX].iter() {
+            let price = Price::from_cents(*cents);
+            let json = serde_json::to_string(&price).unwrap();
+            assert_eq!(serde_json::from_str::<Price>(&json).unwrap(), price, "{}", json);
+        }
+    }
+
+    #[test]
+    fn percentages_round_trip() {
+        for text in ["0", "45", "62.5", "33.33", "100"].iter() {
+            let percentage: Percentage = serde_json::from_str(text).unwrap();
+            let json = serde_json::to_string(&percentage).unwrap();
+            assert_eq!(json, *text);
+            assert_eq!(serde_json::from_str::<Percentage>(&json).unwrap(), percentage);
+        }
+    }
+
+    #[cfg(not(feature = "strict"))]
+    #[test]
+    fn invalid_services_are_serialized_in_place() {
+        use serde_json::json;
+
+        let mut fixture: Value =
+            serde_json::from_str(include_str!("../fixtures/services.json")).unwrap();
+        let broadband = fixture["data"]["broadband"]["data"].as_array_mut().unwrap();
+        let valid = broadband[0].clone();
+        broadband.insert(0, json!({"id": 1000002, "description": "Missing fields"}));
+        broadband.push(json!({"id": "not a number"}));
+        broadband.push(valid);
+
+        let services = serde_json::from_value::<Data<Services>>(fixture.clone()).unwrap().unwrap();
+        assert_eq!(services.broadband().len(), 2);
+        assert_eq!(services.invalid().len(), 2);
+
+        let json = serde_json::to_value(&services).unwrap();
+        assert_eq!(json["broadband"], fixture["data"]["broadband"]);
+        let reloaded: Services = serde_json::from_value(json).unwrap();
+        assert_eq!(reloaded.invalid().len(), 2);
+    }
+
+    fn with_unknown_fields() -> Result<Data<Services>, serde_json::Error> {
+        serde_json::from_str(include_str!("../fixtures/services-unknown-field.json"))
+    }
+
+    #[cfg(feature = "strict")]
+    #[test]
+    fn unknown_fields_are_rejected_when_strict() {
+        let err = with_unknown_fields().unwrap_err().to_string();
+        assert!(err.contains("connectionNotes, staticIpAddon"), "{}", err);
+    }
+
+    #[cfg(not(feature = "strict"))]
+    #[test]
+    fn unknown_fields_are_kept_when_lenient() {
+        let services = with_unknown_fields().unwrap().unwrap();
+        assert!(services.invalid().is_empty());
+        let extra = services.broadband()[0].as_ref().extra();
+        assert_eq!(extra.len(), 2);
+        assert_eq!(extra["staticIpAddon"], true);
+        assert_eq!(extra["connectionNotes"], "Connected on 3 Jul 2020");
+        assert!(services.mobile()[0].as_ref().extra().is_empty());
+    }
+
+    #[test]
+    fn prices_are_added_and_multiplied() {
+        let price = Price::from_cents(1_999);
+        assert_eq!(price + Price::from_cents(1), Price::from_cents(2_000));
+        assert_eq!(price * 3, Price::from_cents(5_997));
+        assert_eq!(price - Price::from_cents(2_000), Price::default());
+        assert_eq!(price.checked_sub(Price::from_cents(2_000)), None);
+
+        let mut total = price;
+        total += price;
+        assert_eq!(total, Price::from_cents(3_998));
+        assert_eq!([price, price, price].iter().sum::<Price>(), price * 3);
+        assert_eq!(Price::from_cents(123_456).dollars_and_cents(), (1_234, 56));
+    }
+
+    #[test]
+    fn overflow_is_detected() {
+        let max = Price::from_cents(u64::MAX);
+        assert_eq!(max.checked_add(Price::from_cents(0)), Some(max));
+        assert_eq!(max.checked_add(Price::from_cents(1)), None);
+        assert_eq!(max.checked_mul(1), Some(max));
+        assert_eq!(max.checked_mul(2), None);
+        assert_eq!(max.dollars_and_cents(), (u32::MAX, 15));
+        let most = Price::from_cents(u64::from(u32::MAX) * 100 + 99);
+        assert_eq!(most.dollars_and_cents(), (u32::MAX, 99));
+        assert_eq!(Price::from_cents(99).dollars_and_cents(), (0, 99));
+    }
+
+    #[test]
+    #[should_panic(expected = "price overflowed")]
+    fn adding_panics_on_overflow() {
+        let _ = Price::from_cents(u64::MAX) + Price::from_cents(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "price overflowed")]
+    fn adding_in_place_panics_on_overflow() {
+        let mut price = Price::from_cents(u64::MAX);
+        price += Price::from_cents(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "price overflowed")]
+    fn multiplying_panics_on_overflow() {
+        let _ = Price::from_cents(u64::MAX / 2 + 1) * 2;
+    }
+
+    #[test]
+    #[should_panic(expected = "price overflowed")]
+    fn summing_panics_on_overflow() {
+        let _: Price = vec![Price::from_cents(u64::MAX), Price::from_cents(1)].into_iter().sum();
+    }
+
+    #[test]
+    fn signed_prices_are_added_and_negated() {
+        let refund = SignedPrice::from_cents(-1_999);
+        assert_eq!(refund.cents(), -1_999);
+        assert!(refund.is_negative());
+        assert_eq!(-refund, SignedPrice::from(Price::from_cents(1_999)));
+        assert_eq!(refund + SignedPrice::from_cents(2_000), SignedPrice::from_cents(1));
+        assert_eq!([refund, -refund].iter().sum::<SignedPrice>(), SignedPrice::default());
+
+        let min = SignedPrice::from_cents(i64::MIN);
+        assert_eq!(min.checked_neg(), None);
+        assert_eq!(min.checked_add(SignedPrice::from_cents(-1)), None);
+        assert_eq!(SignedPrice::from_cents(i64::MAX).checked_add(SignedPrice::from_cents(1)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "price overflowed")]
+    fn negating_signed_prices_panics_on_overflow() {
+        let _ = -SignedPrice::from_cents(i64::MIN);
+    }
+
+    #[test]
+    #[should_panic(expected = "price overflowed")]
+    fn summing_signed_prices_panics_on_overflow() {
+        let prices = vec![SignedPrice::from_cents(i64::MAX), SignedPrice::from_cents(1)];
+        let _: SignedPrice = prices.into_iter().sum();
+    }
+
+    #[test]
+    fn prices_are_parsed() {
+        let cases = [
+            ("$0.00", Some(0)),
+            ("$79.99", Some(7_999)),
+            ("79.99", Some(7_999)),
+            ("$1,234.56", Some(123_456)),
+            ("$1,234,567", Some(123_456_700)),
+            ("$5.5", Some(550)),
+            ("$5.", Some(500)),
+            ("$.5", Some(50)),
+            ("$5", Some(500)),
+            ("$3.994", Some(399)),
+            ("$3.995", Some(400)),
+            ("$3.999", Some(400)),
+            ("$0.004", Some(0)),
+            ("$0.005", Some(1)),
+            ("  $12.34\t", Some(1_234)),
+            ("$184467440737095516.15", Some(u64::MAX)),
+            ("$184467440737095516.16", None),
+            ("", None),
+            ("   ", None),
+            ("$", None),
+            (".", None),
+            ("-$10.00", None),
+            ("$-10.00", None),
+            ("$ 5", None),
+            ("$1 000", None),
+            ("$1,23", None),
+            ("$,123", None),
+            ("$1234,567", None),
+            ("$1.2.3", None),
+            ("$1e3", None),
+            ("AU$5", None),
+        ];
+        for (text, expected) in cases.iter() {
+            let parsed = text.parse::<Price>();
+            match expected {
+                Some(cents) => assert_eq!(parsed.unwrap(), Price::from_cents(*cents), "{:?}", text),
+                None => {
+                    let err = parsed.unwrap_err();
+                    assert!(matches!(err, Error::InvalidInput(_)), "{:?}: {:?}", text, err);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn signed_prices_are_parsed() {
+        let cases = [
+            ("$10.00", Some(1_000)),
+            ("-$10.00", Some(-1_000)),
+            ("$-10.00", Some(-1_000)),
+            (" -$1,234.56 ", Some(-123_456)),
+            ("-5.5", Some(-550)),
+            ("-$0.00", Some(0)),
+            ("", None),
+            ("-", None),
+            ("--$1", None),
+            ("+$1", None),
+        ];
+        for (text, expected) in cases.iter() {
+            let parsed = text.parse::<SignedPrice>();
+            match expected {
+                Some(cents) => assert_eq!(parsed.unwrap(), SignedPrice(*cents), "{:?}", text),
+                None => {
+                    let err = parsed.unwrap_err();
+                    assert!(matches!(err, Error::InvalidInput(_)), "{:?}: {:?}", text, err);
+                }
+            }
+        }
+        assert!("-$10.00".parse::<SignedPrice>().unwrap().is_negative());
+        assert_eq!("-$10.5".parse::<SignedPrice>().unwrap().to_string(), "-$10.50");
+    }
+
+    #[test]
+    fn percentages_are_checked() {
+        assert_eq!(Percentage::new(0.0).unwrap(), Percentage::from(0));
+        assert_eq!(Percentage::new(100.0).unwrap(), Percentage::from(100));
+        assert_eq!(u32::from(Percentage::new(45.0).unwrap()), 45);
+
+        let invalid = [
+            Percentage::new(100.01),
+            Percentage::new(250.0),
+            Percentage::new(-0.01),
+            Percentage::new(f64::NAN),
+            "100.01%".parse(),
+        ];
+        for err in invalid.iter() {
+            assert!(matches!(err, Err(Error::InvalidInput(_))), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn whole_percentages_are_not_checked() {
+        assert_eq!(Percentage::from(0).as_f64(), 0.0);
+        assert_eq!(Percentage::from(100).as_f64(), 100.0);
+        assert_eq!(Percentage::from(250).as_f64(), 250.0);
+        assert_eq!(Percentage::from(250).to_string(), "250%");
+        assert_eq!(u32::from(Percentage::from(45)), 45);
+        assert_eq!(u32::from(Percentage::from(u32::MAX)), u32::MAX / 100 + 1);
+        assert!(Percentage::from(101) > Percentage::from(100));
+    }
+
+    #[test]
+    fn percentages_are_parsed() {
+        let cases = [
+            ("0", Some(0.0)),
+            ("100", Some(100.0)),
+            ("62.5", Some(62.5)),
+            ("\"62.5%\"", Some(62.5)),
+            ("\" 45 % \"", Some(45.0)),
+            ("\"100\"", Some(100.0)),
+            ("100.01", None),
+            ("250", None),
+            ("-1", None),
+            ("\"+101%\"", None),
+            ("\"half\"", None),
+        ];
+        for (json, expected) in cases.iter() {
+            let parsed = serde_json::from_str::<Percentage>(json);
+            match expected {
+                Some(percent) => assert_eq!(parsed.unwrap().as_f64(), *percent, "{}", json),
+                None => assert!(parsed.is_err(), "{}: {:?}", json, parsed),
+            }
+        }
+    }
+
+    #[test]
+    fn proportions_of_totals_are_rounded_down_and_saturate() {
+        let total = 60_000_000_000;
+        assert_eq!(Percentage::from(0).of(total), 0);
+        assert_eq!(Percentage::new(62.5).unwrap().of(total), 37_500_000_000);
+        assert_eq!(Percentage::from(100).of(total), total);
+        assert_eq!(Percentage::from(100).of(u64::MAX), u64::MAX);
+        assert_eq!(Percentage::from(250).of(total), 150_000_000_000);
+        assert_eq!(Percentage::from(250).of(u64::MAX), u64::MAX);
+    }
+}
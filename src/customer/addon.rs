@@ -0,0 +1,256 @@
+//! Queries for the add-ons that can be bought for a service
+
+use super::{Data, DataAmount, Price, ServiceId};
+use crate::{Error, Query};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The identifier of an add-on
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AddonId(String);
+
+impl From<String> for AddonId {
+    fn from(id: String) -> Self {
+        AddonId(id)
+    }
+}
+
+impl AsRef<str> for AddonId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for AddonId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for AddonId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Query for the add-ons that can be bought for a service
+pub struct GetAddons {
+    service_id: ServiceId,
+}
+
+impl GetAddons {
+    pub fn new(service_id: ServiceId) -> Self {
+        GetAddons { service_id }
+    }
+}
+
+impl Query for GetAddons {
+    type Body = ();
+    type Response = Data<Vec<Addon>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/addons", self.service_id).into()
+    }
+}
+
+/// An add-on that can be bought for a service
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Addon {
+    pub id: AddonId,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: AddonKind,
+    pub price: Price,
+    /// The data added by a data pack
+    #[serde(default)]
+    pub size: Option<DataAmount>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// The kind of an add-on
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum AddonKind {
+    DataPack,
+    SpeedBoost,
+    /// A kind not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for AddonKind {
+    fn from(kind: String) -> Self {
+        let normalized = kind.to_ascii_lowercase().replace(|c: char| !c.is_alphanumeric(), "");
+        match normalized.as_str() {
+            "datapack" | "datablock" => AddonKind::DataPack,
+            "speedboost" => AddonKind::SpeedBoost,
+            _ => AddonKind::Other(kind),
+        }
+    }
+}
+
+impl From<AddonKind> for String {
+    fn from(kind: AddonKind) -> String {
+        match kind {
+            AddonKind::Other(kind) => kind,
+            kind => format!("{}", kind),
+        }
+    }
+}
+
+impl fmt::Display for AddonKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddonKind::DataPack => write!(f, "Data Pack"),
+            AddonKind::SpeedBoost => write!(f, "Speed Boost"),
+            AddonKind::Other(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+impl fmt::Debug for AddonKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Confirmation that an add-on is to be bought at its listed price
+///
+/// This can only be made from an add-on as listed by the API, so that a purchase is never made
+/// for an add-on or a price that was not seen.
+#[derive(Debug, Clone)]
+pub struct PurchaseConfirmation {
+    addon_id: AddonId,
+    price: Price,
+}
+
+impl PurchaseConfirmation {
+    /// Confirm the purchase of an add-on at its listed price
+    pub fn confirm(addon: &Addon) -> Self {
+        PurchaseConfirmation { addon_id: addon.id.clone(), price: addon.price }
+    }
+
+    /// The price confirmed for the add-on
+    pub fn price(&self) -> Price {
+        self.price
+    }
+}
+
+/// Query to buy an add-on for a service
+pub struct PurchaseAddon {
+    service_id: ServiceId,
+    purchase: Purchase,
+}
+
+/// The body of a request to buy an add-on
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Purchase {
+    addon_id: AddonId,
+    confirmed_price: Price,
+}
+
+impl PurchaseAddon {
+    /// Create the query, failing if the confirmation is for another add-on
+    pub fn new(
+        service_id: ServiceId,
+        addon_id: AddonId,
+        confirmation: PurchaseConfirmation,
+    ) -> Result<Self, Error> {
+        if confirmation.addon_id != addon_id {
+            let message = format!(
+                "purchase of {} was confirmed for {}",
+                addon_id, confirmation.addon_id,
+            );
+            return Err(Error::InvalidInput(message));
+        }
+        let purchase = Purchase { addon_id, confirmed_price: confirmation.price };
+        Ok(PurchaseAddon { service_id, purchase })
+    }
+}
+
+impl Query for PurchaseAddon {
+    type Body = Purchase;
+    type Response = Data<PurchaseReceipt>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/addons/purchase", self.service_id).into()
+    }
+
+    fn body(&self) -> Option<&Purchase> {
+        Some(&self.purchase)
+    }
+}
+
+/// Confirmation that an add-on has been bought
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurchaseReceipt {
+    /// The reference for the purchase
+    pub reference: String,
+    /// The amount charged
+    pub amount: Price,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addons() -> Vec<Addon> {
+        let addons = include_str!("../../fixtures/addons/available.json");
+        serde_json::from_str::<Data<Vec<Addon>>>(addons).unwrap().unwrap()
+    }
+
+    #[test]
+    fn addons_are_read() {
+        let addons = addons();
+        assert_eq!(addons.len(), 3);
+
+        assert_eq!(addons[0].id.as_ref(), "DP-5GB");
+        assert_eq!(addons[0].kind, AddonKind::DataPack);
+        assert_eq!(addons[0].price, Price::from_cents(1_000));
+        assert_eq!(addons[0].size, Some(DataAmount::Bytes(5_000_000_000)));
+
+        assert_eq!(addons[1].kind, AddonKind::SpeedBoost);
+        assert_eq!(addons[1].size, None);
+        assert_eq!(addons[1].rest["durationHours"], 24);
+
+        assert_eq!(addons[2].kind, AddonKind::Other("roaming".to_owned()));
+        assert_eq!(addons[2].size, None);
+    }
+
+    #[test]
+    fn confirmed_addons_are_bought_at_their_listed_price() {
+        let addons = addons();
+        let confirmation = PurchaseConfirmation::confirm(&addons[0]);
+        assert_eq!(confirmation.price(), Price::from_cents(1_000));
+
+        let service_id = ServiceId::from(2_000_001);
+        let query = PurchaseAddon::new(service_id, addons[0].id.clone(), confirmation).unwrap();
+        assert_eq!(query.path(), "/service/2000001/addons/purchase");
+        let expected = serde_json::json!({"addonId": "DP-5GB", "confirmedPrice": "$10.00"});
+        assert_eq!(serde_json::to_value(query.body()).unwrap(), expected);
+
+        let receipt = include_str!("../../fixtures/addons/receipt.json");
+        let receipt = serde_json::from_str::<Data<PurchaseReceipt>>(receipt).unwrap().unwrap();
+        assert_eq!(receipt.reference, "AP-99013");
+        assert_eq!(receipt.amount, Price::from_cents(1_000));
+    }
+
+    #[test]
+    fn confirmations_are_not_used_for_other_addons() {
+        let addons = addons();
+        let confirmation = PurchaseConfirmation::confirm(&addons[1]);
+        let service_id = ServiceId::from(2_000_001);
+        let err = PurchaseAddon::new(service_id, addons[0].id.clone(), confirmation).err().unwrap();
+        assert!(matches!(&err, Error::InvalidInput(message) if message.contains("SB-24H")));
+    }
+}
@@ -0,0 +1,151 @@
+//! Queries for the alerts sent as a service is used
+
+use super::{Data, Percentage, ServiceId};
+use crate::{Error, Method, Query};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Query for the alerts sent as a service is used
+pub struct GetUsageAlerts {
+    service_id: ServiceId,
+}
+
+impl GetUsageAlerts {
+    pub fn new(service_id: ServiceId) -> Self {
+        GetUsageAlerts { service_id }
+    }
+}
+
+impl Query for GetUsageAlerts {
+    type Body = ();
+    type Response = Data<UsageAlertSettings>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/usage-alerts", self.service_id).into()
+    }
+}
+
+/// Query to change the alerts sent as a service is used
+pub struct SetUsageAlerts {
+    service_id: ServiceId,
+    settings: UsageAlertSettings,
+}
+
+impl SetUsageAlerts {
+    /// Create the query, sorting the thresholds and removing any repeated
+    ///
+    /// Fails if any threshold is not between 1 and 100 percent.
+    pub fn new(service_id: ServiceId, mut settings: UsageAlertSettings) -> Result<Self, Error> {
+        let range = Percentage::from(1)..=Percentage::from(100);
+        if let Some(invalid) = settings.thresholds.iter().find(|&t| !range.contains(t)) {
+            let message = format!("usage alert threshold {} is not between 1% and 100%", invalid);
+            return Err(Error::InvalidInput(message));
+        }
+        settings.thresholds.sort();
+        settings.thresholds.dedup();
+        Ok(SetUsageAlerts { service_id, settings })
+    }
+}
+
+impl Query for SetUsageAlerts {
+    type Body = UsageAlertSettings;
+    type Response = IgnoredAny;
+
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/usage-alerts", self.service_id).into()
+    }
+
+    fn body(&self) -> Option<&UsageAlertSettings> {
+        Some(&self.settings)
+    }
+}
+
+/// The alerts sent as a service is used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageAlertSettings {
+    /// The proportions of the included usage at which alerts are sent, from the least
+    pub thresholds: Vec<Percentage>,
+    /// The manners in which alerts are sent
+    pub channels: AlertChannels,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl UsageAlertSettings {
+    pub fn new(thresholds: Vec<Percentage>, channels: AlertChannels) -> Self {
+        UsageAlertSettings { thresholds, channels, rest: HashMap::new() }
+    }
+}
+
+/// The manners in which alerts are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertChannels {
+    /// Whether alerts are sent by email
+    pub email: bool,
+    /// Whether alerts are sent by SMS
+    pub sms: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn percent(percent: f64) -> Percentage {
+        Percentage::new(percent).unwrap()
+    }
+
+    #[test]
+    fn usage_alerts_are_read() {
+        let settings = include_str!("../../fixtures/usage-alerts.json");
+        let settings = serde_json::from_str::<Data<UsageAlertSettings>>(settings).unwrap().unwrap();
+        assert_eq!(settings.thresholds, [percent(50.0), percent(80.0), percent(100.0)]);
+        assert_eq!(settings.channels, AlertChannels { email: true, sms: false });
+        assert_eq!(settings.rest["quietHours"], "22:00-07:00");
+    }
+
+    #[test]
+    fn thresholds_are_sorted_and_deduplicated() {
+        let thresholds = vec![percent(90.0), percent(50.0), percent(90.0), percent(75.5)];
+        let settings = UsageAlertSettings::new(thresholds, AlertChannels::default());
+        let query = SetUsageAlerts::new(ServiceId::from(2_000_001), settings).unwrap();
+        assert_eq!(query.path(), "/service/2000001/usage-alerts");
+        let expected = json!({
+            "thresholds": [50, 75.5, 90],
+            "channels": { "email": false, "sms": false },
+        });
+        assert_eq!(serde_json::to_value(query.body()).unwrap(), expected);
+    }
+
+    #[test]
+    fn thresholds_outside_one_to_a_hundred_percent_are_rejected() {
+        let outside = [percent(0.0), percent(0.99), Percentage::from(101), Percentage::from(250)];
+        for threshold in outside.iter() {
+            let thresholds = vec![percent(50.0), *threshold];
+            let settings = UsageAlertSettings::new(thresholds, AlertChannels::default());
+            let err = SetUsageAlerts::new(ServiceId::from(2_000_001), settings).err().unwrap();
+            assert!(matches!(err, Error::InvalidInput(_)), "{:?}: {:?}", threshold, err);
+        }
+        let settings = UsageAlertSettings::new(vec![percent(1.0)], AlertChannels::default());
+        assert!(SetUsageAlerts::new(ServiceId::from(2_000_001), settings).is_ok());
+    }
+
+    #[test]
+    fn unknown_settings_are_sent_back() {
+        let settings = include_str!("../../fixtures/usage-alerts.json");
+        let settings = serde_json::from_str::<Data<UsageAlertSettings>>(settings).unwrap().unwrap();
+        let query = SetUsageAlerts::new(ServiceId::from(2_000_001), settings).unwrap();
+        let body = serde_json::to_value(query.body()).unwrap();
+        assert_eq!(body["quietHours"], "22:00-07:00");
+        assert_eq!(body["thresholds"], json!([50, 80, 100]));
+    }
+}
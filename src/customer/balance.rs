@@ -0,0 +1,85 @@
+//! Queries for the balance of an account
+
+use super::{flexible_date, unparse_iso_date, Data, Price, SignedPrice};
+use crate::Query;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Query for the balance of the account and a summary of its next bill
+pub struct GetBalance;
+
+impl Query for GetBalance {
+    type Body = ();
+    type Response = Data<AccountBalance>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/account/balance".into()
+    }
+}
+
+/// The balance of an account and a summary of its next bill
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBalance {
+    /// The amount owed on the account, which is negative if the account is in credit
+    pub balance: SignedPrice,
+    /// The amount of the balance that is overdue
+    #[serde(default)]
+    pub overdue_amount: Price,
+    /// The date on which the next invoice will be issued
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub next_invoice_date: NaiveDate,
+    /// The estimated total of the next invoice
+    pub estimated_next_bill: Price,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl AccountBalance {
+    /// Check if any of the balance is overdue
+    pub fn is_overdue(&self) -> bool {
+        self.overdue_amount > Price::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance(fixture: &str) -> AccountBalance {
+        serde_json::from_str::<Data<AccountBalance>>(fixture).unwrap().unwrap()
+    }
+
+    #[test]
+    fn settled_balance_is_not_overdue() {
+        let balance = balance(include_str!("../../fixtures/balance/zero.json"));
+        assert_eq!(balance.balance, SignedPrice::default());
+        assert!(!balance.balance.is_negative());
+        assert!(!balance.is_overdue());
+        assert_eq!(balance.next_invoice_date, NaiveDate::from_ymd_opt(2026, 11, 1).unwrap());
+        assert_eq!(balance.estimated_next_bill, Price::from_cents(9_999));
+    }
+
+    #[test]
+    fn credit_balance_is_negative() {
+        let balance = balance(include_str!("../../fixtures/balance/credit.json"));
+        assert!(balance.balance.is_negative());
+        assert_eq!(balance.balance.cents(), -3_550);
+        assert_eq!(balance.overdue_amount, Price::default());
+        assert!(!balance.is_overdue());
+        assert_eq!(balance.next_invoice_date, NaiveDate::from_ymd_opt(2026, 11, 1).unwrap());
+    }
+
+    #[test]
+    fn overdue_balance_is_reported() {
+        let balance = balance(include_str!("../../fixtures/balance/overdue.json"));
+        assert_eq!(balance.balance.cents(), 21_998);
+        assert_eq!(balance.overdue_amount, Price::from_cents(12_000));
+        assert!(balance.is_overdue());
+        assert_eq!(balance.rest["collectionsDate"], "15/11/2026");
+    }
+}
@@ -0,0 +1,226 @@
+//! Queries to cancel a service
+//!
+//! A service is cancelled in two steps: a quote is requested, giving any fee for ending the
+//! contract early, and the quote is then confirmed. Confirming consumes the quote, and a quote can
+//! only be had from the API.
+
+use super::{
+    flexible_date, parse_optional_offset_datetime, unparse_iso_date, unparse_optional_iso_date,
+    unparse_optional_offset_datetime, Data, Price, ServiceId,
+};
+use crate::Query;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A request to cancel a service
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancellationRequest {
+    /// Why the service is being cancelled
+    pub reason: String,
+    /// The date on which the service should end, or as soon as possible if none is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "unparse_optional_iso_date")]
+    pub requested_date: Option<NaiveDate>,
+}
+
+/// Query for a quote to cancel a service
+pub struct RequestCancellation {
+    service_id: ServiceId,
+    request: CancellationRequest,
+}
+
+impl RequestCancellation {
+    pub fn new(service_id: ServiceId, request: CancellationRequest) -> Self {
+        RequestCancellation { service_id, request }
+    }
+}
+
+impl Query for RequestCancellation {
+    type Body = CancellationRequest;
+    type Response = Data<CancellationQuote>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/cancellation", self.service_id).into()
+    }
+
+    fn body(&self) -> Option<&CancellationRequest> {
+        Some(&self.request)
+    }
+}
+
+/// A quote to cancel a service, which must be confirmed for the service to be cancelled
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancellationQuote {
+    /// The service to be cancelled
+    pub service_id: ServiceId,
+    /// The fee charged for ending the contract of the service early
+    #[serde(default)]
+    pub early_termination_fee: Price,
+    /// The date on which the service would end
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub cancellation_date: NaiveDate,
+    /// The time after which the quote can no longer be confirmed, if any
+    #[serde(default)]
+    #[serde(serialize_with = "unparse_optional_offset_datetime")]
+    #[serde(deserialize_with = "parse_optional_offset_datetime")]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    token: String,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl CancellationQuote {
+    /// Check if the quote can no longer be confirmed as of the given time
+    pub fn is_expired_at(&self, time: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= time)
+    }
+}
+
+/// Query to confirm a quote to cancel a service, cancelling it
+pub struct ConfirmCancellation {
+    service_id: ServiceId,
+    confirmation: Confirmation,
+}
+
+/// The body of a confirmation of a quote to cancel a service
+#[derive(Debug, Serialize)]
+pub struct Confirmation {
+    token: String,
+}
+
+impl From<CancellationQuote> for ConfirmCancellation {
+    fn from(quote: CancellationQuote) -> Self {
+        let confirmation = Confirmation { token: quote.token };
+        ConfirmCancellation { service_id: quote.service_id, confirmation }
+    }
+}
+
+impl Query for ConfirmCancellation {
+    type Body = Confirmation;
+    type Response = Data<CancellationReceipt>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/cancellation/confirm", self.service_id).into()
+    }
+
+    fn body(&self) -> Option<&Confirmation> {
+        Some(&self.confirmation)
+    }
+}
+
+/// Confirmation that a service is to be cancelled
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancellationReceipt {
+    /// The reference for the cancellation
+    pub reference: String,
+    /// The date on which the service ends
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub cancellation_date: NaiveDate,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use crate::Error;
+    use reqwest::Method;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn cancellation_is_requested_and_confirmed() {
+        let server = MockServer::start().await;
+        let quote = r#"{"data": {
+            "serviceId": 1000001,
+            "earlyTerminationFee": "$120.50",
+            "cancellationDate": "2021-03-31",
+            "expiresAt": null,
+            "token": "quote-token"
+        }}"#;
+        let receipt = r#"{"data": {
+            "reference": "CAN-42",
+            "cancellationDate": "31 Mar 2021"
+        }}"#;
+        let path = "/api/v1/service/1000001/cancellation";
+        server.respond(Method::POST, path, MockResponse::json(200, quote));
+        let confirm = "/api/v1/service/1000001/cancellation/confirm";
+        server.respond(Method::POST, confirm, MockResponse::json(200, receipt));
+        let client = server.client();
+
+        let request = CancellationRequest {
+            reason: "Moving house".to_owned(),
+            requested_date: NaiveDate::from_ymd_opt(2021, 3, 31),
+        };
+        let service_id = ServiceId::from(1_000_001);
+        let quote = client.request_cancellation(service_id, request).await.unwrap();
+        assert_eq!(quote.early_termination_fee, Price::from_cents(12050));
+
+        let receipt = client.confirm_cancellation(quote).await.unwrap();
+        assert_eq!(receipt.reference, "CAN-42");
+        assert_eq!(Some(receipt.cancellation_date), NaiveDate::from_ymd_opt(2021, 3, 31));
+
+        let received = server.received();
+        assert_eq!(received[0].path, path);
+        let expected = json!({"reason": "Moving house", "requestedDate": "2021-03-31"});
+        assert_eq!(received[0].json(), expected);
+        assert_eq!(received[1].path, confirm);
+        assert_eq!(received[1].json(), json!({"token": "quote-token"}));
+    }
+
+    #[tokio::test]
+    async fn expired_quote_is_reported() {
+        let server = MockServer::start().await;
+        let quote = r#"{"data": {
+            "serviceId": 1000001,
+            "cancellationDate": "2021-03-31",
+            "token": "quote-token"
+        }}"#;
+        let path = "/api/v1/service/1000001/cancellation";
+        server.respond(Method::POST, path, MockResponse::json(200, quote));
+        let confirm = "/api/v1/service/1000001/cancellation/confirm";
+        let gone = r#"{"message": "Quote has expired"}"#;
+        server.respond(Method::POST, confirm, MockResponse::json(410, gone));
+        let client = server.client();
+
+        let request = CancellationRequest {
+            reason: "Moving house".to_owned(),
+            requested_date: None,
+        };
+        let service_id = ServiceId::from(1_000_001);
+        let quote = client.request_cancellation(service_id, request).await.unwrap();
+        let err = client.confirm_cancellation(quote).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::QuoteExpired), "{:?}", err);
+        assert_eq!(server.received()[0].json(), json!({"reason": "Moving house"}));
+    }
+
+    #[test]
+    fn quotes_expire_at_their_expiry() {
+        let quote = r#"{
+            "serviceId": 1000001,
+            "cancellationDate": "2021-03-31",
+            "expiresAt": "2021-03-01T12:00:00+11:00",
+            "token": "quote-token"
+        }"#;
+        let quote: CancellationQuote = serde_json::from_str(quote).unwrap();
+        let expires_at = quote.expires_at.unwrap().with_timezone(&Utc);
+        assert!(!quote.is_expired_at(expires_at - chrono::Duration::seconds(1)));
+        assert!(quote.is_expired_at(expires_at));
+
+        let quote: CancellationQuote = serde_json::from_value(json!({
+            "serviceId": 1000001,
+            "cancellationDate": "2021-03-31",
+            "token": "quote-token",
+        }))
+        .unwrap();
+        assert!(!quote.is_expired_at(expires_at));
+    }
+}
@@ -0,0 +1,183 @@
+//! Queries for the contact details of an account
+
+use super::Data;
+use crate::{Method, Query};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Query for the contact details of the account
+pub struct GetContact;
+
+impl Query for GetContact {
+    type Body = ();
+    type Response = Data<ContactDetails>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/account/contact".into()
+    }
+}
+
+/// Query to change some of the contact details of the account
+pub struct UpdateContact {
+    update: ContactUpdate,
+}
+
+impl UpdateContact {
+    pub fn new(update: ContactUpdate) -> Self {
+        UpdateContact { update }
+    }
+}
+
+impl Query for UpdateContact {
+    type Body = ContactUpdate;
+    type Response = Data<ContactDetails>;
+
+    fn method(&self) -> Method {
+        Method::PATCH
+    }
+
+    fn path(&self) -> Cow<'_, str> {
+        "/account/contact".into()
+    }
+
+    fn body(&self) -> Option<&ContactUpdate> {
+        Some(&self.update)
+    }
+}
+
+/// The contact details of an account
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactDetails {
+    /// The name of the account holder
+    pub name: String,
+    /// The address to which email is sent
+    pub email: String,
+    /// The number to which messages are sent
+    pub mobile: Option<String>,
+    /// The address to which post is sent
+    pub postal_address: PostalAddress,
+    /// The manners in which the account holder is notified
+    pub notifications: NotificationPreferences,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// A postal address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostalAddress {
+    /// The street address, or a post office box
+    pub line1: String,
+    /// Any further line of the address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line2: Option<String>,
+    pub suburb: String,
+    pub state: String,
+    pub postcode: String,
+}
+
+/// The manners in which an account holder is notified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPreferences {
+    /// Whether notifications are sent by email
+    pub email: bool,
+    /// Whether notifications are sent by SMS
+    pub sms: bool,
+}
+
+/// Changes to the contact details of an account
+///
+/// Only the details that are set are changed; the others are left out of the request.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mobile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    postal_address: Option<PostalAddress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notifications: Option<NotificationPreferences>,
+}
+
+impl ContactUpdate {
+    pub fn new() -> Self {
+        ContactUpdate::default()
+    }
+
+    /// Change the name of the account holder
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Change the address to which email is sent
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Change the number to which messages are sent
+    pub fn mobile(mut self, mobile: impl Into<String>) -> Self {
+        self.mobile = Some(mobile.into());
+        self
+    }
+
+    /// Change the address to which post is sent
+    pub fn postal_address(mut self, address: PostalAddress) -> Self {
+        self.postal_address = Some(address);
+        self
+    }
+
+    /// Change the manners in which the account holder is notified
+    pub fn notifications(mut self, notifications: NotificationPreferences) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unset_details_are_left_out_of_updates() {
+        let update = ContactUpdate::new().email("someone@example.com");
+        assert_eq!(serde_json::to_string(&update).unwrap(), r#"{"email":"someone@example.com"}"#);
+        assert_eq!(serde_json::to_string(&ContactUpdate::new()).unwrap(), "{}");
+    }
+
+    #[test]
+    fn set_details_are_sent_in_updates() {
+        let address = PostalAddress {
+            line1: "PO Box 1".to_owned(),
+            line2: None,
+            suburb: "Sydney".to_owned(),
+            state: "NSW".to_owned(),
+            postcode: "2000".to_owned(),
+        };
+        let update = ContactUpdate::new()
+            .mobile("0400000000")
+            .postal_address(address)
+            .notifications(NotificationPreferences { email: true, sms: false });
+        let expected = json!({
+            "mobile": "0400000000",
+            "postalAddress": {
+                "line1": "PO Box 1",
+                "suburb": "Sydney",
+                "state": "NSW",
+                "postcode": "2000",
+            },
+            "notifications": { "email": true, "sms": false },
+        });
+        assert_eq!(serde_json::to_value(&update).unwrap(), expected);
+    }
+}
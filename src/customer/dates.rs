@@ -0,0 +1,130 @@
+//! Parsing of dates in the formats given by the API
+
+use crate::Error;
+use chrono::NaiveDate;
+use serde::{Deserialize, Deserializer};
+
+/// The formats in which dates are given by the API, in the order they are tried
+///
+/// Two-digit years are tried before four-digit years, as a four-digit year would otherwise accept
+/// a two-digit year as being in the first century.
+const FORMATS: &[&str] = &["%Y-%m-%d", "%e %b %y", "%e %b %Y", "%d/%m/%Y"];
+
+/// Parse a date given in any of the formats used by the API
+///
+/// Dates may be given as `2020-07-01`, `1 Jul 20`, `1 Jul 2020` or `01/07/2020`.
+pub fn parse_date(text: &str) -> Result<NaiveDate, Error> {
+    let trimmed = text.trim();
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(trimmed, format).ok())
+        .ok_or_else(|| Error::InvalidInput(format!("invalid date: {:?}", text)))
+}
+
+/// Parse a date given in any of the formats used by the API
+pub(crate) fn flexible_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let text: String = Deserialize::deserialize(deserializer)?;
+    parse_date(&text).map_err(D::Error::custom)
+}
+
+/// Parse a date that may be missing, given as null, an empty string or `N/A`
+pub(crate) fn flexible_date_opt<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let text: Option<String> = Deserialize::deserialize(deserializer)?;
+    match text.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(text) if text.eq_ignore_ascii_case("n/a") => Ok(None),
+        Some(text) => parse_date(text).map(Some).map_err(D::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn each_format_is_parsed() {
+        let cases = [
+            ("2020-07-21", date(2020, 7, 21)),
+            ("21 Jul 20", date(2020, 7, 21)),
+            ("21 Jul 2020", date(2020, 7, 21)),
+            ("21/07/2020", date(2020, 7, 21)),
+            ("  21 Jul 2020\n", date(2020, 7, 21)),
+        ];
+        for (text, expected) in cases.iter() {
+            assert_eq!(parse_date(text).unwrap(), *expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn single_digit_days_are_parsed() {
+        let cases = [
+            ("2020-07-01", date(2020, 7, 1)),
+            ("1 Jul 20", date(2020, 7, 1)),
+            (" 1 Jul 20", date(2020, 7, 1)),
+            ("1 Jul 2020", date(2020, 7, 1)),
+            ("01 Jul 2020", date(2020, 7, 1)),
+            ("01/07/2020", date(2020, 7, 1)),
+            ("1/7/2020", date(2020, 7, 1)),
+        ];
+        for (text, expected) in cases.iter() {
+            assert_eq!(parse_date(text).unwrap(), *expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn every_day_of_a_year_is_parsed_in_each_format() {
+        let mut day = date(2024, 1, 1);
+        while day.year() == 2024 {
+            for format in FORMATS.iter() {
+                let text = day.format(format).to_string();
+                assert_eq!(parse_date(&text).unwrap(), day, "{:?}", text);
+            }
+            day = day.succ_opt().unwrap();
+        }
+    }
+
+    #[test]
+    fn garbage_is_rejected_with_the_text() {
+        for text in ["", "N/A", "tomorrow", "32 Jul 2020", "2020-13-01", "07/21/2020"].iter() {
+            let err = parse_date(text).unwrap_err();
+            match &err {
+                Error::InvalidInput(message) => {
+                    assert!(message.contains(&format!("{:?}", text)), "{}", message)
+                }
+                err => panic!("unexpected error for {:?}: {:?}", text, err),
+            }
+        }
+    }
+
+    #[test]
+    fn missing_dates_are_none() {
+        #[derive(Deserialize)]
+        struct Dated {
+            #[serde(default)]
+            #[serde(deserialize_with = "flexible_date_opt")]
+            date: Option<NaiveDate>,
+        }
+
+        let parse = |json| serde_json::from_str::<Dated>(json).map(|dated| dated.date);
+        assert_eq!(parse(r#"{}"#).unwrap(), None);
+        assert_eq!(parse(r#"{"date": null}"#).unwrap(), None);
+        assert_eq!(parse(r#"{"date": " "}"#).unwrap(), None);
+        assert_eq!(parse(r#"{"date": "n/a"}"#).unwrap(), None);
+        assert_eq!(parse(r#"{"date": "1 Jul 2020"}"#).unwrap(), Some(date(2020, 7, 1)));
+        let err = parse(r#"{"date": "soon"}"#).unwrap_err();
+        assert!(err.to_string().contains("\"soon\""), "{}", err);
+    }
+}
@@ -0,0 +1,181 @@
+//! Queries for the details of the connection of a service
+
+use super::{Data, Ipv6Prefix, ServiceId};
+use crate::Query;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+
+/// Query for the details of a service
+pub struct GetServiceDetail {
+    id: ServiceId,
+}
+
+impl GetServiceDetail {
+    pub fn new(id: ServiceId) -> Self {
+        GetServiceDetail { id }
+    }
+}
+
+impl Query for GetServiceDetail {
+    type Body = ();
+    type Response = Data<ServiceDetail>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}", self.id).into()
+    }
+}
+
+/// The details of a service, including those of its connection
+///
+/// Most details are only given for broadband services.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceDetail {
+    pub id: ServiceId,
+    pub service_type: String,
+    /// The address to which the service is delivered
+    #[serde(default)]
+    pub address: Option<String>,
+    /// The technology by which the NBN is connected
+    #[serde(default)]
+    pub technology: Option<NbnTechnology>,
+    /// The tier of speed of the plan, such as `100/20`
+    #[serde(default)]
+    pub speed_tier: Option<String>,
+    /// The identifier of the access virtual circuit of the connection
+    #[serde(default)]
+    pub avc_id: Option<String>,
+    /// The identifier of the connectivity virtual circuit the connection is served by
+    #[serde(default)]
+    pub cvc_id: Option<String>,
+    /// The static IP address of the service, if one is assigned
+    #[serde(default)]
+    pub static_ip: Option<StaticIp>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// A static IP address assigned to a service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticIp {
+    pub address: IpAddr,
+    /// The IPv6 prefix delegated to the service, if any
+    #[serde(default)]
+    pub ipv6_prefix: Option<Ipv6Prefix>,
+}
+
+/// The technology by which a service is connected to the NBN
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum NbnTechnology {
+    /// Fibre to the premises
+    Fttp,
+    /// Fibre to the curb
+    Fttc,
+    /// Hybrid fibre-coaxial
+    Hfc,
+    /// Fibre to the node
+    Fttn,
+    FixedWireless,
+    /// A technology not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for NbnTechnology {
+    fn from(technology: String) -> Self {
+        let normalized = technology
+            .to_ascii_lowercase()
+            .replace(|c: char| !c.is_alphanumeric(), "");
+        match normalized.as_str() {
+            "fttp" | "fttpremises" => NbnTechnology::Fttp,
+            "fttc" | "fttcurb" => NbnTechnology::Fttc,
+            "hfc" => NbnTechnology::Hfc,
+            "fttn" | "fttnode" => NbnTechnology::Fttn,
+            "fixedwireless" | "fw" => NbnTechnology::FixedWireless,
+            _ => NbnTechnology::Other(technology),
+        }
+    }
+}
+
+impl From<NbnTechnology> for String {
+    fn from(technology: NbnTechnology) -> String {
+        match technology {
+            NbnTechnology::Other(technology) => technology,
+            technology => format!("{}", technology),
+        }
+    }
+}
+
+impl fmt::Display for NbnTechnology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NbnTechnology::Fttp => write!(f, "FTTP"),
+            NbnTechnology::Fttc => write!(f, "FTTC"),
+            NbnTechnology::Hfc => write!(f, "HFC"),
+            NbnTechnology::Fttn => write!(f, "FTTN"),
+            NbnTechnology::FixedWireless => write!(f, "Fixed Wireless"),
+            NbnTechnology::Other(technology) => write!(f, "{}", technology),
+        }
+    }
+}
+
+impl fmt::Debug for NbnTechnology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(fixture: &str) -> ServiceDetail {
+        serde_json::from_str::<Data<ServiceDetail>>(fixture).unwrap().unwrap()
+    }
+
+    #[test]
+    fn broadband_details_are_read() {
+        let detail = read(include_str!("../../fixtures/detail/broadband.json"));
+        assert_eq!(detail.id, ServiceId::from(1_000_001));
+        assert_eq!(detail.technology, Some(NbnTechnology::Fttp));
+        assert_eq!(detail.speed_tier.as_deref(), Some("100/20"));
+        assert_eq!(detail.avc_id.as_deref(), Some("AVC000000000001"));
+        assert_eq!(detail.cvc_id.as_deref(), Some("CVC000000000001"));
+        let static_ip = detail.static_ip.unwrap();
+        assert_eq!(static_ip.address, "203.0.113.10".parse::<IpAddr>().unwrap());
+        let prefix = static_ip.ipv6_prefix.unwrap();
+        assert_eq!(prefix.to_string(), "2001:db8:1234::/48");
+        assert_eq!(detail.rest["nbnLocationId"], "LOC000000000001");
+    }
+
+    #[test]
+    fn missing_details_are_absent() {
+        let detail = read(include_str!("../../fixtures/detail/wireless.json"));
+        assert_eq!(detail.technology, Some(NbnTechnology::FixedWireless));
+        assert_eq!(detail.cvc_id, None);
+        assert_eq!(detail.static_ip, None);
+
+        let detail = read(include_str!("../../fixtures/detail/mobile.json"));
+        assert_eq!(detail.service_type, "Mobile");
+        assert_eq!(detail.address, None);
+        assert_eq!(detail.technology, None);
+        assert_eq!(detail.speed_tier, None);
+        assert_eq!(detail.static_ip, None);
+    }
+
+    #[test]
+    fn technologies_are_normalized() {
+        let technology = |name: &str| NbnTechnology::from(name.to_owned());
+        assert_eq!(technology("fttp"), NbnTechnology::Fttp);
+        assert_eq!(technology("FTT-Curb"), NbnTechnology::Fttc);
+        assert_eq!(technology("FTTN"), NbnTechnology::Fttn);
+        assert_eq!(technology("Satellite"), NbnTechnology::Other("Satellite".to_owned()));
+        assert_eq!(String::from(NbnTechnology::FixedWireless), "Fixed Wireless");
+    }
+}
@@ -0,0 +1,313 @@
+//! Queries for the invoices of an account
+
+use super::{flexible_date, unparse_iso_date, Data, Price};
+use crate::{DownloadInfo, Query};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A range of dates, including both its first and last date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    /// The first date in the range
+    pub from: NaiveDate,
+    /// The last date in the range
+    pub to: NaiveDate,
+}
+
+impl DateRange {
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Self {
+        DateRange { from, to }
+    }
+
+    /// Check if a date is within the range
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from <= date && date <= self.to
+    }
+
+    /// Parameters of a query restricting it to the range
+    pub(crate) fn query_params(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
+        vec![
+            ("from".into(), self.from.format("%Y-%m-%d").to_string().into()),
+            ("to".into(), self.to.format("%Y-%m-%d").to_string().into()),
+        ]
+    }
+}
+
+/// Query for the invoices of the account, optionally only those issued within a range of dates
+#[derive(Default)]
+pub struct GetInvoices {
+    range: Option<DateRange>,
+}
+
+impl GetInvoices {
+    pub fn new(range: Option<DateRange>) -> Self {
+        GetInvoices { range }
+    }
+}
+
+impl Query for GetInvoices {
+    type Body = ();
+    type Response = Data<Vec<Invoice>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/invoice".into()
+    }
+
+    fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        self.range.as_ref().map(DateRange::query_params).unwrap_or_default()
+    }
+}
+
+/// The number identifying an invoice
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InvoiceId(String);
+
+impl From<String> for InvoiceId {
+    fn from(id: String) -> Self {
+        InvoiceId(id)
+    }
+}
+
+impl AsRef<str> for InvoiceId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for InvoiceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for InvoiceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Details of the downloaded PDF of an invoice
+#[derive(Debug, Clone)]
+pub struct InvoicePdf {
+    /// The number of bytes written
+    pub size: u64,
+    /// The name suggested for the file by the API, if given
+    pub filename: Option<String>,
+}
+
+impl From<DownloadInfo> for InvoicePdf {
+    fn from(info: DownloadInfo) -> Self {
+        InvoicePdf { size: info.length, filename: info.filename }
+    }
+}
+
+/// An invoice issued for the account
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invoice {
+    /// The number identifying the invoice
+    pub invoice_number: InvoiceId,
+    /// The date on which the invoice was issued
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub issue_date: NaiveDate,
+    /// The date by which the invoice must be paid
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub due_date: NaiveDate,
+    /// The total amount of the invoice
+    pub total: Price,
+    /// The amount of the invoice that is yet to be paid
+    pub amount_outstanding: Price,
+    /// Whether the invoice has been paid
+    pub status: InvoiceStatus,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// Whether an invoice has been paid
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum InvoiceStatus {
+    Paid,
+    Unpaid,
+    Overdue,
+    /// A status not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for InvoiceStatus {
+    fn from(status: String) -> Self {
+        match status.to_ascii_lowercase().as_str() {
+            "paid" => InvoiceStatus::Paid,
+            "unpaid" => InvoiceStatus::Unpaid,
+            "overdue" => InvoiceStatus::Overdue,
+            _ => InvoiceStatus::Other(status),
+        }
+    }
+}
+
+impl From<InvoiceStatus> for String {
+    fn from(status: InvoiceStatus) -> String {
+        match status {
+            InvoiceStatus::Other(status) => status,
+            status => format!("{}", status),
+        }
+    }
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvoiceStatus::Paid => write!(f, "Paid"),
+            InvoiceStatus::Unpaid => write!(f, "Unpaid"),
+            InvoiceStatus::Overdue => write!(f, "Overdue"),
+            InvoiceStatus::Other(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+impl fmt::Debug for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use crate::Error;
+    use chrono::Datelike;
+    use reqwest::Method;
+
+    const INVOICES: &str = include_str!("../../fixtures/invoices.json");
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn invoices() -> Vec<Invoice> {
+        serde_json::from_str::<Data<Vec<Invoice>>>(INVOICES).unwrap().unwrap()
+    }
+
+    #[test]
+    fn invoices_are_deserialized() {
+        let invoices = invoices();
+        assert_eq!(invoices.len(), 4);
+
+        let invoice = &invoices[1];
+        assert_eq!(invoice.invoice_number.as_ref(), "E1000002");
+        assert_eq!(invoice.issue_date, date(2026, 9, 1));
+        assert_eq!(invoice.due_date, date(2026, 9, 15));
+        assert_eq!(invoice.total, Price::from_cents(9_999));
+        assert_eq!(invoice.amount_outstanding, Price::from_cents(2_000));
+        assert_eq!(invoice.status, InvoiceStatus::Overdue);
+        assert_eq!(invoice.rest["pdfAvailable"], true);
+
+        let statuses: Vec<_> = invoices.iter().map(|invoice| invoice.status.clone()).collect();
+        let expected = [
+            InvoiceStatus::Unpaid,
+            InvoiceStatus::Overdue,
+            InvoiceStatus::Paid,
+            InvoiceStatus::Other("Disputed".to_owned()),
+        ];
+        assert_eq!(statuses, expected);
+        assert_eq!(String::from(invoices[3].status.clone()), "Disputed");
+    }
+
+    #[test]
+    fn invoice_totals_are_summed() {
+        let spent: Price = invoices()
+            .iter()
+            .filter(|invoice| invoice.issue_date.year() == 2026)
+            .map(|invoice| invoice.total)
+            .sum();
+        assert_eq!(spent, Price::from_cents(32_048));
+    }
+
+    #[tokio::test]
+    async fn invoices_are_queried_for_a_range() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, "/api/v1/invoice", MockResponse::json(200, INVOICES));
+        let client = server.client();
+
+        let range = DateRange::new(date(2026, 1, 1), date(2026, 12, 31));
+        assert_eq!(client.invoices(Some(range)).await.unwrap().len(), 4);
+        client.invoices(None).await.unwrap();
+
+        let received = server.received();
+        assert_eq!(received[0].query.as_deref(), Some("from=2026-01-01&to=2026-12-31"));
+        assert_eq!(received[1].query, None);
+    }
+
+    #[test]
+    fn ranges_include_both_ends() {
+        let range = DateRange::new(date(2026, 1, 1), date(2026, 1, 31));
+        assert!(range.contains(date(2026, 1, 1)));
+        assert!(range.contains(date(2026, 1, 31)));
+        assert!(!range.contains(date(2025, 12, 31)));
+        assert!(!range.contains(date(2026, 2, 1)));
+    }
+
+    /// The path at which the server receives requests for the PDF of an invoice
+    const PDF_PATH: &str = "/api/v1/invoice/E1000001/pdf";
+
+    #[tokio::test]
+    async fn invoice_pdfs_are_downloaded_exactly() {
+        let server = MockServer::start().await;
+        let pdf = b"%PDF-1.4\n\x00\xff\xfe binary \r\n%%EOF";
+        let response = MockResponse::bytes(200, "application/pdf", pdf)
+            .header("content-disposition", "attachment; filename=\"E1000001.pdf\"");
+        server.respond(Method::GET, PDF_PATH, response);
+        let client = server.client();
+
+        let mut downloaded = Vec::new();
+        let invoice = InvoiceId::from("E1000001".to_owned());
+        let info = client.invoice_pdf(&invoice, &mut downloaded).await.unwrap();
+        assert_eq!(downloaded, pdf.to_vec());
+        assert_eq!(info.size, pdf.len() as u64);
+        assert_eq!(info.filename.as_deref(), Some("E1000001.pdf"));
+        assert_eq!(server.received()[0].headers["accept"], "application/pdf");
+    }
+
+    #[tokio::test]
+    async fn missing_invoice_pdfs_are_not_found() {
+        let server = MockServer::start().await;
+        let client = server.client();
+
+        let mut downloaded = Vec::new();
+        let invoice = InvoiceId::from("E1000001".to_owned());
+        let err = client.invoice_pdf(&invoice, &mut downloaded).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::NotFound), "{:?}", err);
+        assert!(downloaded.is_empty());
+        assert_eq!(server.count(PDF_PATH), 1);
+    }
+
+    #[tokio::test]
+    async fn login_pages_are_not_taken_for_pdfs() {
+        let server = MockServer::start().await;
+        let page = b"<html><body>Please log in</body></html>";
+        let response = MockResponse::bytes(200, "text/html; charset=utf-8", page);
+        server.respond(Method::GET, PDF_PATH, response);
+        let client = server.client();
+
+        let mut downloaded = Vec::new();
+        let invoice = InvoiceId::from("E1000001".to_owned());
+        let err = client.invoice_pdf(&invoice, &mut downloaded).await.unwrap_err();
+        match err.inner() {
+            Error::UnexpectedContentType { expected, received } => {
+                assert_eq!(*expected, "application/pdf");
+                assert_eq!(received.as_deref(), Some("text/html; charset=utf-8"));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        assert!(downloaded.is_empty());
+    }
+}
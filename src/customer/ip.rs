@@ -0,0 +1,206 @@
+//! Queries for the IP addresses of broadband services
+
+use super::{Data, ServiceId};
+use crate::{Error, Query};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Query for the IP addresses assigned to a service
+pub struct GetIpDetails {
+    service_id: ServiceId,
+}
+
+impl GetIpDetails {
+    pub fn new(service_id: ServiceId) -> Self {
+        GetIpDetails { service_id }
+    }
+}
+
+impl Query for GetIpDetails {
+    type Body = ();
+    type Response = Data<IpDetails>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/ip", self.service_id).into()
+    }
+}
+
+/// The IP addresses assigned to a service
+///
+/// A service with no IP allocation, such as a mobile service, has no addresses.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IpDetails {
+    /// The IPv4 address currently assigned to the service
+    #[serde(default, deserialize_with = "parse_optional")]
+    pub ipv4_address: Option<Ipv4Addr>,
+    /// Whether the IPv4 address is kept by the service or may change
+    #[serde(default)]
+    pub assignment: Option<IpAssignment>,
+    /// The IPv6 prefix delegated to the service
+    #[serde(default, deserialize_with = "parse_optional")]
+    pub ipv6_prefix: Option<Ipv6Prefix>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// Whether an IP address is kept by a service or may change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IpAssignment {
+    Static,
+    Dynamic,
+}
+
+/// A prefix of IPv6 addresses, such as `2001:db8:1234::/48`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
+pub struct Ipv6Prefix {
+    address: Ipv6Addr,
+    length: u8,
+}
+
+impl Ipv6Prefix {
+    /// Fails if the length is longer than an address
+    pub fn new(address: Ipv6Addr, length: u8) -> Option<Self> {
+        if length <= 128 {
+            Some(Ipv6Prefix { address, length })
+        } else {
+            None
+        }
+    }
+
+    /// The first address of the prefix
+    pub fn address(&self) -> Ipv6Addr {
+        self.address
+    }
+
+    /// The number of leading bits of the address that make up the prefix
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    /// Check if an address is within the prefix
+    pub fn contains(&self, address: Ipv6Addr) -> bool {
+        let mask = u128::MAX.checked_shl(128 - u32::from(self.length)).unwrap_or(0);
+        u128::from(self.address) & mask == u128::from(address) & mask
+    }
+}
+
+impl FromStr for Ipv6Prefix {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidInput(format!("invalid IPv6 prefix: {}", text));
+        let (address, length) = text.trim().split_once('/').ok_or_else(invalid)?;
+        let address = address.parse().map_err(|_| invalid())?;
+        let length = length.parse().map_err(|_| invalid())?;
+        Ipv6Prefix::new(address, length).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for Ipv6Prefix {
+    type Error = Error;
+
+    fn try_from(text: String) -> Result<Self, Error> {
+        text.parse()
+    }
+}
+
+impl From<Ipv6Prefix> for String {
+    fn from(prefix: Ipv6Prefix) -> String {
+        format!("{}", prefix)
+    }
+}
+
+impl fmt::Display for Ipv6Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.length)
+    }
+}
+
+impl fmt::Debug for Ipv6Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Parse a value that may be absent, given as either `null` or an empty string
+fn parse_optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    use serde::de::Error;
+    let text: Option<Cow<'_, str>> = Deserialize::deserialize(deserializer)?;
+    match text.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(text) => text.parse().map(Some).map_err(D::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(fixture: &str) -> IpDetails {
+        serde_json::from_str::<Data<IpDetails>>(fixture).unwrap().unwrap()
+    }
+
+    #[test]
+    fn addresses_are_read() {
+        let details = read(include_str!("../../fixtures/ip/static.json"));
+        assert_eq!(details.ipv4_address, Some(Ipv4Addr::new(203, 0, 113, 10)));
+        assert_eq!(details.assignment, Some(IpAssignment::Static));
+        let prefix = details.ipv6_prefix.unwrap();
+        assert_eq!(prefix.address(), "2001:db8:1234::".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(prefix.length(), 48);
+        assert_eq!(details.rest["reverseDns"], "host.example.net");
+    }
+
+    #[test]
+    fn missing_addresses_are_absent() {
+        let details = read(include_str!("../../fixtures/ip/dynamic.json"));
+        assert_eq!(details.ipv4_address, Some(Ipv4Addr::new(198, 51, 100, 7)));
+        assert_eq!(details.assignment, Some(IpAssignment::Dynamic));
+        assert_eq!(details.ipv6_prefix, None);
+
+        let details = read(include_str!("../../fixtures/ip/none.json"));
+        assert_eq!(details.ipv4_address, None);
+        assert_eq!(details.assignment, None);
+        assert_eq!(details.ipv6_prefix, None);
+    }
+
+    #[test]
+    fn invalid_addresses_are_rejected() {
+        let invalid = r#"{"ipv4Address": "203.0.113.256"}"#;
+        assert!(serde_json::from_str::<IpDetails>(invalid).is_err());
+        for text in ["2001:db8::", "2001:db8::/129", "2001:db8::/x", "203.0.113.0/24"].iter() {
+            let err = text.parse::<Ipv6Prefix>().unwrap_err();
+            assert!(matches!(err, Error::InvalidInput(_)), "{:?}: {:?}", text, err);
+        }
+    }
+
+    #[test]
+    fn prefixes_contain_their_addresses() {
+        let prefix: Ipv6Prefix = "2001:db8:1234::/48".parse().unwrap();
+        assert!(prefix.contains("2001:db8:1234:ffff::1".parse().unwrap()));
+        assert!(!prefix.contains("2001:db8:1235::1".parse().unwrap()));
+        assert_eq!(prefix.to_string(), "2001:db8:1234::/48");
+
+        let everything = Ipv6Prefix::new(Ipv6Addr::UNSPECIFIED, 0).unwrap();
+        assert!(everything.contains("2001:db8::1".parse().unwrap()));
+        let single: Ipv6Prefix = "2001:db8::1/128".parse().unwrap();
+        assert!(single.contains("2001:db8::1".parse().unwrap()));
+        assert!(!single.contains("2001:db8::2".parse().unwrap()));
+        assert_eq!(Ipv6Prefix::new(Ipv6Addr::UNSPECIFIED, 129), None);
+    }
+}
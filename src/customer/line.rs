@@ -0,0 +1,309 @@
+//! Queries for the quality of the connections of broadband services
+
+use super::{parse_offset_datetime, unparse_offset_datetime, Data, DateRange, ServiceId};
+use crate::Query;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// A rate of transfer, kept in kilobits per second
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BitRate(u64);
+
+impl BitRate {
+    pub fn from_kbps(kbps: u64) -> Self {
+        BitRate(kbps)
+    }
+
+    pub fn kbps(&self) -> u64 {
+        self.0
+    }
+
+    pub fn mbps(&self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+}
+
+impl fmt::Display for BitRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.1} Mbps", self.mbps())
+    }
+}
+
+impl fmt::Debug for BitRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Query for the current quality of the connection of a service
+pub struct GetLineStats {
+    service_id: ServiceId,
+}
+
+impl GetLineStats {
+    pub fn new(service_id: ServiceId) -> Self {
+        GetLineStats { service_id }
+    }
+}
+
+impl Query for GetLineStats {
+    type Body = ();
+    type Response = Data<LineStats>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/line", self.service_id).into()
+    }
+}
+
+/// The current quality of the connection of a service
+///
+/// Details are absent for technologies that do not report them, such as the rates attainable by
+/// fibre to the premises.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineStats {
+    /// The rate at which the connection is synchronised downstream
+    #[serde(default)]
+    pub sync_down: Option<BitRate>,
+    /// The rate at which the connection is synchronised upstream
+    #[serde(default)]
+    pub sync_up: Option<BitRate>,
+    /// The highest rate the line could be synchronised at downstream
+    #[serde(default)]
+    pub attainable_down: Option<BitRate>,
+    /// The highest rate the line could be synchronised at upstream
+    #[serde(default)]
+    pub attainable_up: Option<BitRate>,
+    /// The time since the connection last dropped
+    #[serde(default, rename = "uptimeSeconds")]
+    #[serde(serialize_with = "unparse_optional_seconds")]
+    #[serde(deserialize_with = "parse_optional_seconds")]
+    pub uptime: Option<Duration>,
+    /// The number of times the connection dropped in the last `drop_window_days`
+    #[serde(default)]
+    pub drop_count: Option<u32>,
+    /// The number of days over which drops are counted
+    #[serde(default)]
+    pub drop_window_days: Option<u32>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// Query for the quality of the connection of a service over a range of dates
+pub struct GetLineHistory {
+    service_id: ServiceId,
+    range: DateRange,
+}
+
+impl GetLineHistory {
+    pub fn new(service_id: ServiceId, range: DateRange) -> Self {
+        GetLineHistory { service_id, range }
+    }
+}
+
+impl Query for GetLineHistory {
+    type Body = ();
+    type Response = Data<Vec<LineSample>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/line/history", self.service_id).into()
+    }
+
+    fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        self.range.query_params()
+    }
+}
+
+/// The quality of the connection of a service at a point in time
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineSample {
+    #[serde(serialize_with = "unparse_offset_datetime")]
+    #[serde(deserialize_with = "parse_offset_datetime")]
+    pub time: DateTime<FixedOffset>,
+    #[serde(default)]
+    pub sync_down: Option<BitRate>,
+    #[serde(default)]
+    pub sync_up: Option<BitRate>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// Query for the speed tests of a service recorded by the portal
+pub struct GetSpeedTests {
+    service_id: ServiceId,
+}
+
+impl GetSpeedTests {
+    pub fn new(service_id: ServiceId) -> Self {
+        GetSpeedTests { service_id }
+    }
+}
+
+impl Query for GetSpeedTests {
+    type Body = ();
+    type Response = Data<Vec<SpeedTestResult>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/speed-tests", self.service_id).into()
+    }
+}
+
+/// The result of a speed test of a service
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeedTestResult {
+    /// The time at which the test was run
+    #[serde(serialize_with = "unparse_offset_datetime")]
+    #[serde(deserialize_with = "parse_offset_datetime")]
+    pub time: DateTime<FixedOffset>,
+    pub download: BitRate,
+    pub upload: BitRate,
+    /// The time taken for a round trip to the server, if it was measured
+    #[serde(default, rename = "latencyMs")]
+    #[serde(serialize_with = "unparse_optional_millis")]
+    #[serde(deserialize_with = "parse_optional_millis")]
+    pub latency: Option<Duration>,
+    /// The server against which the test was run
+    pub server: String,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+fn parse_optional_millis<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let millis: Option<f64> = Deserialize::deserialize(deserializer)?;
+    millis
+        .map(|millis| Duration::try_from_secs_f64(millis / 1000.0).map_err(D::Error::custom))
+        .transpose()
+}
+
+fn unparse_optional_millis<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&(duration.as_secs_f64() * 1000.0)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn parse_optional_seconds<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds: Option<u64> = Deserialize::deserialize(deserializer)?;
+    Ok(seconds.map(Duration::from_secs))
+}
+
+fn unparse_optional_seconds<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&duration.as_secs()),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn stats(fixture: &str) -> LineStats {
+        serde_json::from_str::<Data<LineStats>>(fixture).unwrap().unwrap()
+    }
+
+    #[test]
+    fn line_stats_are_read() {
+        let stats = stats(include_str!("../../fixtures/line/fttn.json"));
+        assert_eq!(stats.sync_down, Some(BitRate::from_kbps(52_340)));
+        assert_eq!(stats.sync_up, Some(BitRate::from_kbps(18_120)));
+        assert_eq!(stats.attainable_down, Some(BitRate::from_kbps(71_500)));
+        assert_eq!(stats.attainable_up, Some(BitRate::from_kbps(24_330)));
+        assert_eq!(stats.uptime, Some(Duration::from_secs(4 * 24 * 60 * 60 + 3 * 60 * 60)));
+        assert_eq!(stats.drop_count, Some(2));
+        assert_eq!(stats.drop_window_days, Some(7));
+        assert_eq!(stats.rest["snrMarginDown"], 6.2);
+    }
+
+    #[test]
+    fn unreported_line_stats_are_absent() {
+        let stats = stats(include_str!("../../fixtures/line/fttp.json"));
+        assert_eq!(stats.sync_down.map(|rate| rate.mbps()), Some(100.0));
+        assert_eq!(stats.attainable_down, None);
+        assert_eq!(stats.attainable_up, None);
+        assert_eq!(stats.uptime, Some(Duration::from_secs(24 * 60 * 60)));
+        assert_eq!(stats.drop_count, None);
+        assert_eq!(stats.drop_window_days, None);
+    }
+
+    #[test]
+    fn line_history_is_read_for_a_range() {
+        let history = include_str!("../../fixtures/line/history.json");
+        let history = serde_json::from_str::<Data<Vec<LineSample>>>(history).unwrap().unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].sync_down, Some(BitRate::from_kbps(52_340)));
+        assert_eq!(history[1].sync_down, None);
+        assert_eq!(history[1].rest["reason"], "Connection down");
+        assert!(history[1].time < history[2].time);
+
+        let from = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let range = DateRange::new(from, to);
+        let query = GetLineHistory::new(ServiceId::from(1_000_001), range);
+        assert_eq!(query.path(), "/service/1000001/line/history");
+        assert_eq!(query.query_params(), range.query_params());
+    }
+
+    #[test]
+    fn bit_rates_are_displayed_in_megabits() {
+        assert_eq!(BitRate::from_kbps(52_340).to_string(), "52.3 Mbps");
+        assert_eq!(BitRate::default().to_string(), "0.0 Mbps");
+    }
+
+    fn speed_tests() -> Vec<SpeedTestResult> {
+        let tests = include_str!("../../fixtures/line/speed-tests.json");
+        serde_json::from_str::<Data<Vec<SpeedTestResult>>>(tests).unwrap().unwrap()
+    }
+
+    #[test]
+    fn speed_tests_are_read() {
+        let tests = speed_tests();
+        assert_eq!(tests.len(), 3);
+        assert_eq!(tests[0].time.to_rfc3339(), "2026-10-13T20:15:00+11:00");
+        assert_eq!(tests[0].download, BitRate::from_kbps(87_450));
+        assert_eq!(tests[0].upload, BitRate::from_kbps(17_320));
+        assert_eq!(tests[0].latency, Some(Duration::from_micros(12_500)));
+        assert_eq!(tests[0].server, "Sydney");
+        assert_eq!(tests[0].rest["jitterMs"], 1.2);
+
+        assert_eq!(tests[1].latency, None);
+        assert_eq!(tests[2].download, BitRate::default());
+        assert_eq!(tests[2].latency, None);
+    }
+
+    #[test]
+    fn speed_test_latency_round_trips_in_milliseconds() {
+        let tests = speed_tests();
+        let json = serde_json::to_value(&tests[0]).unwrap();
+        assert_eq!(json["latencyMs"], 12.5);
+        assert_eq!(json["download"], 87_450);
+        let test: SpeedTestResult = serde_json::from_value(json).unwrap();
+        assert_eq!(test.latency, tests[0].latency);
+
+        let negative = r#"{"time": "2026-10-13T20:15:00+11:00", "download": 1, "upload": 1,
+            "latencyMs": -1, "server": "Sydney"}"#;
+        assert!(serde_json::from_str::<SpeedTestResult>(negative).is_err());
+    }
+}
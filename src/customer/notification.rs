@@ -0,0 +1,114 @@
+//! Queries for the notifications sent about an account
+
+use super::Data;
+use crate::{Method, Query};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Query for the notifications sent about the account
+pub struct GetNotificationSettings;
+
+impl Query for GetNotificationSettings {
+    type Body = ();
+    type Response = Data<NotificationSettings>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/account/notifications".into()
+    }
+}
+
+/// Query to change the notifications sent about the account
+pub struct SetNotificationSettings {
+    settings: NotificationSettings,
+}
+
+impl SetNotificationSettings {
+    pub fn new(settings: NotificationSettings) -> Self {
+        SetNotificationSettings { settings }
+    }
+}
+
+impl Query for SetNotificationSettings {
+    type Body = NotificationSettings;
+    type Response = IgnoredAny;
+
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn path(&self) -> Cow<'_, str> {
+        "/account/notifications".into()
+    }
+
+    fn body(&self) -> Option<&NotificationSettings> {
+        Some(&self.settings)
+    }
+}
+
+/// Which notifications are sent about an account
+///
+/// Preferences not otherwise known are kept in `other`, so that they are sent back unchanged
+/// when the settings are changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    /// Whether offers and news are sent by email
+    #[serde(default)]
+    pub marketing_emails: bool,
+    /// Whether outages are notified by SMS
+    #[serde(default)]
+    pub outage_sms: bool,
+    /// Whether invoices are sent by email
+    #[serde(default)]
+    pub invoice_emails: bool,
+    /// Preferences not otherwise known, by the name given by the API
+    #[serde(flatten)]
+    pub other: HashMap<String, bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use serde_json::json;
+
+    /// The path at which the server receives requests for the notification settings
+    const PATH: &str = "/api/v1/account/notifications";
+
+    #[test]
+    fn unknown_preferences_are_kept() {
+        let settings = include_str!("../../fixtures/notifications.json");
+        let settings: Data<NotificationSettings> = serde_json::from_str(settings).unwrap();
+        let settings = settings.unwrap();
+        assert!(!settings.marketing_emails);
+        assert!(settings.outage_sms);
+        assert!(settings.invoice_emails);
+        assert_eq!(settings.other.len(), 1);
+        assert!(settings.other["paymentReminderSms"]);
+    }
+
+    #[tokio::test]
+    async fn unknown_preferences_are_sent_back_unchanged() {
+        let server = MockServer::start().await;
+        let settings = include_str!("../../fixtures/notifications.json");
+        server.respond(Method::GET, PATH, MockResponse::json(200, settings));
+        server.respond(Method::PUT, PATH, MockResponse::empty(204));
+        let client = server.client();
+
+        let mut settings = client.notification_preferences().await.unwrap();
+        settings.marketing_emails = true;
+        client.set_notification_preferences(settings).await.unwrap();
+
+        let received = server.received();
+        assert_eq!(received[1].method, Method::PUT);
+        let expected = json!({
+            "marketingEmails": true,
+            "outageSms": true,
+            "invoiceEmails": true,
+            "paymentReminderSms": true,
+        });
+        assert_eq!(received[1].json(), expected);
+    }
+}
@@ -0,0 +1,225 @@
+//! Queries for orders of hardware, such as modems and SIMs
+
+use super::{flexible_date, unparse_iso_date, Data};
+use crate::Query;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Query for the orders of hardware made for the account
+pub struct GetOrders;
+
+impl Query for GetOrders {
+    type Body = ();
+    type Response = Data<Vec<Order>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/order".into()
+    }
+}
+
+/// An order of hardware
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub id: String,
+    #[serde(default)]
+    pub items: Vec<OrderItem>,
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub order_date: NaiveDate,
+    pub status: OrderStatus,
+    /// The number by which the shipment can be tracked, once it has shipped
+    #[serde(default)]
+    pub tracking_number: Option<String>,
+    /// The carrier of the shipment, once it has shipped
+    #[serde(default)]
+    pub carrier: Option<Carrier>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl Order {
+    /// The page on which the shipment can be tracked, if it has shipped with a known carrier
+    pub fn tracking_url(&self) -> Option<String> {
+        let number = self.tracking_number.as_deref()?.trim();
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        match self.carrier.as_ref()? {
+            Carrier::AustraliaPost => {
+                Some(format!("https://auspost.com.au/mypost/track/details/{}", number))
+            }
+            Carrier::StarTrack => {
+                Some(format!("https://startrack.com.au/track/details/{}", number))
+            }
+            Carrier::Other(_) => None,
+        }
+    }
+}
+
+/// An item in an order of hardware
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderItem {
+    pub description: String,
+    #[serde(default = "OrderItem::one")]
+    pub quantity: u32,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl OrderItem {
+    fn one() -> u32 {
+        1
+    }
+}
+
+/// The progress of an order of hardware
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum OrderStatus {
+    Processing,
+    Shipped,
+    Delivered,
+    /// A status not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for OrderStatus {
+    fn from(status: String) -> Self {
+        match status.to_ascii_lowercase().as_str() {
+            "processing" => OrderStatus::Processing,
+            "shipped" => OrderStatus::Shipped,
+            "delivered" => OrderStatus::Delivered,
+            _ => OrderStatus::Other(status),
+        }
+    }
+}
+
+impl From<OrderStatus> for String {
+    fn from(status: OrderStatus) -> String {
+        match status {
+            OrderStatus::Other(status) => status,
+            status => format!("{}", status),
+        }
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrderStatus::Processing => write!(f, "Processing"),
+            OrderStatus::Shipped => write!(f, "Shipped"),
+            OrderStatus::Delivered => write!(f, "Delivered"),
+            OrderStatus::Other(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+impl fmt::Debug for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A carrier by which hardware is shipped
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum Carrier {
+    AustraliaPost,
+    StarTrack,
+    /// A carrier not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for Carrier {
+    fn from(carrier: String) -> Self {
+        let normalized = carrier.to_ascii_lowercase().replace(|c: char| !c.is_alphanumeric(), "");
+        match normalized.as_str() {
+            "australiapost" | "auspost" => Carrier::AustraliaPost,
+            "startrack" => Carrier::StarTrack,
+            _ => Carrier::Other(carrier),
+        }
+    }
+}
+
+impl From<Carrier> for String {
+    fn from(carrier: Carrier) -> String {
+        match carrier {
+            Carrier::Other(carrier) => carrier,
+            carrier => format!("{}", carrier),
+        }
+    }
+}
+
+impl fmt::Display for Carrier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Carrier::AustraliaPost => write!(f, "Australia Post"),
+            Carrier::StarTrack => write!(f, "StarTrack"),
+            Carrier::Other(carrier) => write!(f, "{}", carrier),
+        }
+    }
+}
+
+impl fmt::Debug for Carrier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orders() -> Vec<Order> {
+        let orders = include_str!("../../fixtures/orders.json");
+        serde_json::from_str::<Data<Vec<Order>>>(orders).unwrap().unwrap()
+    }
+
+    #[test]
+    fn orders_are_read() {
+        let orders = orders();
+        assert_eq!(orders.len(), 5);
+        assert_eq!(orders[0].order_date, NaiveDate::from_ymd_opt(2026, 10, 2).unwrap());
+        assert_eq!(orders[0].status, OrderStatus::Shipped);
+        assert_eq!(orders[0].carrier, Some(Carrier::AustraliaPost));
+        let quantities: Vec<_> = orders[0].items.iter().map(|item| item.quantity).collect();
+        assert_eq!(quantities, [1, 1]);
+        assert_eq!(orders[1].status, OrderStatus::Delivered);
+        assert_eq!(orders[1].carrier, Some(Carrier::StarTrack));
+        assert_eq!(orders[2].status, OrderStatus::Processing);
+        assert!(orders[2].items.is_empty());
+        assert_eq!(orders[3].carrier, Some(Carrier::Other("CouriersPlease".to_owned())));
+    }
+
+    #[test]
+    fn shipments_are_tracked_with_known_carriers() {
+        let orders = orders();
+        let expected = "https://auspost.com.au/mypost/track/details/33ABC0123456";
+        assert_eq!(orders[0].tracking_url().as_deref(), Some(expected));
+        let expected = "https://startrack.com.au/track/details/STK998877";
+        assert_eq!(orders[1].tracking_url().as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn shipments_are_not_tracked_without_a_known_carrier_or_a_plain_number() {
+        let orders = orders();
+        assert_eq!(orders[2].tracking_url(), None);
+        assert_eq!(orders[3].tracking_url(), None);
+        assert_eq!(orders[4].tracking_url(), None);
+
+        let mut order = orders.into_iter().next().unwrap();
+        order.tracking_number = Some("  ".to_owned());
+        assert_eq!(order.tracking_url(), None);
+        order.tracking_number = Some("33ABC0123456".to_owned());
+        order.carrier = None;
+        assert_eq!(order.tracking_url(), None);
+    }
+}
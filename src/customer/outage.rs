@@ -0,0 +1,155 @@
+//! Queries for outages and maintenance of the network
+
+use super::{
+    parse_offset_datetime, parse_optional_offset_datetime, unparse_offset_datetime,
+    unparse_optional_offset_datetime, Data, ServiceId,
+};
+use crate::Query;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Query for current and scheduled outages affecting the services of the account
+pub struct GetOutages;
+
+impl Query for GetOutages {
+    type Body = ();
+    type Response = Data<Vec<Outage>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/outage".into()
+    }
+}
+
+/// An outage or scheduled maintenance of the network
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Outage {
+    #[serde(rename = "type")]
+    pub kind: OutageKind,
+    /// The time at which the outage started or is scheduled to start
+    #[serde(serialize_with = "unparse_offset_datetime")]
+    #[serde(deserialize_with = "parse_offset_datetime")]
+    pub start: DateTime<FixedOffset>,
+    /// The time by which service is expected to be restored, if known
+    #[serde(default)]
+    #[serde(serialize_with = "unparse_optional_offset_datetime")]
+    #[serde(deserialize_with = "parse_optional_offset_datetime")]
+    pub estimated_restoration: Option<DateTime<FixedOffset>>,
+    /// The services affected by the outage
+    #[serde(default)]
+    pub affected_services: Vec<ServiceId>,
+    /// A description of the state of the outage
+    pub message: String,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl Outage {
+    /// Check if the outage is in effect at the given time
+    ///
+    /// An outage with no estimated restoration is in effect from its start onward.
+    pub fn is_active_at(&self, time: DateTime<Utc>) -> bool {
+        self.start <= time && self.estimated_restoration.is_none_or(|end| time < end)
+    }
+
+    /// Check if the outage affects the given service
+    pub fn affects(&self, service_id: ServiceId) -> bool {
+        self.affected_services.contains(&service_id)
+    }
+}
+
+/// The kind of an outage
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum OutageKind {
+    Unplanned,
+    /// Scheduled maintenance
+    Maintenance,
+    /// A kind not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for OutageKind {
+    fn from(kind: String) -> Self {
+        match kind.to_ascii_lowercase().as_str() {
+            "unplanned" => OutageKind::Unplanned,
+            "maintenance" | "planned" => OutageKind::Maintenance,
+            _ => OutageKind::Other(kind),
+        }
+    }
+}
+
+impl From<OutageKind> for String {
+    fn from(kind: OutageKind) -> String {
+        match kind {
+            OutageKind::Other(kind) => kind,
+            kind => format!("{}", kind),
+        }
+    }
+}
+
+impl fmt::Display for OutageKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutageKind::Unplanned => write!(f, "Unplanned"),
+            OutageKind::Maintenance => write!(f, "Maintenance"),
+            OutageKind::Other(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+impl fmt::Debug for OutageKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn outages() -> Vec<Outage> {
+        let outages = include_str!("../../fixtures/outages.json");
+        serde_json::from_str::<Data<Vec<Outage>>>(outages).unwrap().unwrap()
+    }
+
+    #[test]
+    fn outages_are_read() {
+        let outages = outages();
+        assert_eq!(outages.len(), 2);
+        assert_eq!(outages[0].kind, OutageKind::Unplanned);
+        assert_eq!(outages[0].start.offset().local_minus_utc(), 11 * 60 * 60);
+        assert!(outages[0].affects(ServiceId::from(1_000_001)));
+        assert!(!outages[0].affects(ServiceId::from(3_000_001)));
+        assert_eq!(outages[1].kind, OutageKind::Maintenance);
+        assert_eq!(outages[1].estimated_restoration, None);
+        assert!(outages[1].affects(ServiceId::from(3_000_001)));
+        assert_eq!(outages[1].rest["region"], "NSW");
+    }
+
+    #[test]
+    fn outages_are_active_from_their_start_until_their_restoration() {
+        let outage = &outages()[0];
+        let start = outage.start.with_timezone(&Utc);
+        let end = outage.estimated_restoration.unwrap().with_timezone(&Utc);
+        assert!(!outage.is_active_at(start - Duration::seconds(1)));
+        assert!(outage.is_active_at(start));
+        assert!(outage.is_active_at(end - Duration::seconds(1)));
+        assert!(!outage.is_active_at(end));
+    }
+
+    #[test]
+    fn outages_without_restoration_stay_active() {
+        let outage = &outages()[1];
+        let start = outage.start.with_timezone(&Utc);
+        assert!(!outage.is_active_at(start - Duration::seconds(1)));
+        assert!(outage.is_active_at(start));
+        assert!(outage.is_active_at(start + Duration::days(365)));
+    }
+}
@@ -0,0 +1,319 @@
+//! Queries for the payments made to an account and the methods by which they are made
+
+use super::{flexible_date, unparse_iso_date, Data, DateRange, InvoiceId, SignedPrice};
+use crate::{Error, Query};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// Query for the payments made to the account, optionally only those made within a range of dates
+#[derive(Default)]
+pub struct GetPayments {
+    range: Option<DateRange>,
+}
+
+impl GetPayments {
+    pub fn new(range: Option<DateRange>) -> Self {
+        GetPayments { range }
+    }
+}
+
+impl Query for GetPayments {
+    type Body = ();
+    type Response = Data<Vec<Payment>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/payment".into()
+    }
+
+    fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        self.range.as_ref().map(DateRange::query_params).unwrap_or_default()
+    }
+}
+
+/// A payment made to the account, or a refund made from it
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Payment {
+    /// The date on which the payment was made
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub date: NaiveDate,
+    /// The amount paid, which is negative for a refund
+    pub amount: SignedPrice,
+    /// The manner in which the payment was made
+    pub method: PaymentMethodKind,
+    /// The number of the receipt for the payment, if one was issued
+    #[serde(default)]
+    pub receipt_number: Option<String>,
+    /// The invoices to which the payment was applied
+    #[serde(default)]
+    pub invoices: Vec<InvoiceId>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// Query for the methods of payment stored for the account
+pub struct GetPaymentMethods;
+
+impl Query for GetPaymentMethods {
+    type Body = ();
+    type Response = Data<Vec<PaymentMethod>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/payment/method".into()
+    }
+}
+
+/// A method of payment stored for the account
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentMethod {
+    /// The kind of the method
+    #[serde(rename = "type")]
+    pub kind: PaymentMethodKind,
+    /// The identifier of the card or account, with all but its last digits masked
+    pub masked_identifier: String,
+    /// The month in which a card expires
+    #[serde(default)]
+    pub expiry: Option<CardExpiry>,
+    /// Whether the method is used for payments unless another is chosen
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+impl PaymentMethod {
+    /// Check if the method has expired as of the given date
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expiry.is_some_and(|expiry| expiry.is_expired(today))
+    }
+}
+
+/// The month in which a card expires, such as `08/27`
+///
+/// A card can be used until the end of the month in which it expires.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
+pub struct CardExpiry {
+    year: i32,
+    month: u32,
+}
+
+impl CardExpiry {
+    pub fn new(year: i32, month: u32) -> Option<Self> {
+        if (1..=12).contains(&month) {
+            Some(CardExpiry { year, month })
+        } else {
+            None
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    /// Check if the card has expired as of the given date
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        (today.year(), today.month()) > (self.year, self.month)
+    }
+}
+
+impl FromStr for CardExpiry {
+    type Err = Error;
+
+    /// Parse an expiry given as `MM/YY`, `MM/YYYY` or `YYYY-MM`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidInput(format!("invalid card expiry: {}", text));
+        let (year, month) = match (text.split_once('/'), text.split_once('-')) {
+            (Some((month, year)), _) => (year, month),
+            (None, Some((year, month))) => (year, month),
+            (None, None) => return Err(invalid()),
+        };
+        let month = month.trim().parse().map_err(|_| invalid())?;
+        let year = match year.trim() {
+            year if year.len() == 2 => 2000 + year.parse::<i32>().map_err(|_| invalid())?,
+            year => year.parse().map_err(|_| invalid())?,
+        };
+        CardExpiry::new(year, month).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for CardExpiry {
+    type Error = Error;
+
+    fn try_from(text: String) -> Result<Self, Error> {
+        text.parse()
+    }
+}
+
+impl From<CardExpiry> for String {
+    fn from(expiry: CardExpiry) -> String {
+        format!("{}", expiry)
+    }
+}
+
+impl fmt::Display for CardExpiry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}/{:02}", self.month, self.year.rem_euclid(100))
+    }
+}
+
+impl fmt::Debug for CardExpiry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// The kind of a method of payment
+///
+/// Each kind holds the text given by the API, such as `Credit Card`, so that it is serialized
+/// exactly as it was received.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum PaymentMethodKind {
+    Card(String),
+    DirectDebit(String),
+    Bpay(String),
+    /// A manner not otherwise known, as given by the API
+    Other(String),
+}
+
+impl PaymentMethodKind {
+    /// The text of the kind as given by the API
+    pub fn as_str(&self) -> &str {
+        match self {
+            PaymentMethodKind::Card(method)
+            | PaymentMethodKind::DirectDebit(method)
+            | PaymentMethodKind::Bpay(method)
+            | PaymentMethodKind::Other(method) => method,
+        }
+    }
+}
+
+impl From<String> for PaymentMethodKind {
+    fn from(method: String) -> Self {
+        let normalized = method.to_ascii_lowercase().replace(|c: char| !c.is_alphanumeric(), "");
+        match normalized.as_str() {
+            "card" | "creditcard" | "debitcard" => PaymentMethodKind::Card(method),
+            "directdebit" => PaymentMethodKind::DirectDebit(method),
+            "bpay" => PaymentMethodKind::Bpay(method),
+            _ => PaymentMethodKind::Other(method),
+        }
+    }
+}
+
+impl From<PaymentMethodKind> for String {
+    fn from(method: PaymentMethodKind) -> String {
+        match method {
+            PaymentMethodKind::Card(method)
+            | PaymentMethodKind::DirectDebit(method)
+            | PaymentMethodKind::Bpay(method)
+            | PaymentMethodKind::Other(method) => method,
+        }
+    }
+}
+
+impl fmt::Display for PaymentMethodKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaymentMethodKind::Card(_) => write!(f, "Card"),
+            PaymentMethodKind::DirectDebit(_) => write!(f, "Direct Debit"),
+            PaymentMethodKind::Bpay(_) => write!(f, "BPAY"),
+            PaymentMethodKind::Other(method) => write!(f, "{}", method),
+        }
+    }
+}
+
+impl fmt::Debug for PaymentMethodKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_keep_the_text_given_by_the_api() {
+        let cases = [
+            ("Credit Card", "Card"),
+            ("debit card", "Card"),
+            ("DIRECT_DEBIT", "Direct Debit"),
+            ("Bpay", "BPAY"),
+            ("Cheque", "Cheque"),
+        ];
+        for (text, display) in cases.iter() {
+            let json = serde_json::to_string(text).unwrap();
+            let kind: PaymentMethodKind = serde_json::from_str(&json).unwrap();
+            assert_eq!(kind.as_str(), *text);
+            assert_eq!(kind.to_string(), *display);
+            assert_eq!(serde_json::to_string(&kind).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn kinds_are_recognized() {
+        let kind = PaymentMethodKind::from("Credit Card".to_owned());
+        assert!(matches!(kind, PaymentMethodKind::Card(_)), "{:?}", kind);
+        let kind = PaymentMethodKind::from("Direct Debit".to_owned());
+        assert!(matches!(kind, PaymentMethodKind::DirectDebit(_)), "{:?}", kind);
+        let kind = PaymentMethodKind::from("BPAY".to_owned());
+        assert!(matches!(kind, PaymentMethodKind::Bpay(_)), "{:?}", kind);
+        let kind = PaymentMethodKind::from("Cheque".to_owned());
+        assert!(matches!(kind, PaymentMethodKind::Other(_)), "{:?}", kind);
+    }
+
+    #[test]
+    fn expired_cards_are_recognized() {
+        let method: PaymentMethod = serde_json::from_str(
+            r#"{"type": "Credit Card", "maskedIdentifier": "XXXX-1234", "expiry": "08/24"}"#,
+        )
+        .unwrap();
+        let today = |month| NaiveDate::from_ymd_opt(2024, month, 1).unwrap();
+        assert!(!method.is_expired(today(8)));
+        assert!(method.is_expired(today(9)));
+        assert!(!method.is_default);
+    }
+
+    #[test]
+    fn payments_are_deserialized_including_refunds() {
+        let payments = include_str!("../../fixtures/payments.json");
+        let payments = serde_json::from_str::<Data<Vec<Payment>>>(payments).unwrap().unwrap();
+        assert_eq!(payments.len(), 4);
+
+        let payment = &payments[0];
+        assert_eq!(payment.date, NaiveDate::from_ymd_opt(2026, 10, 15).unwrap());
+        assert_eq!(payment.amount.cents(), 9_999);
+        assert!(matches!(payment.method, PaymentMethodKind::Card(_)), "{:?}", payment.method);
+        assert_eq!(payment.receipt_number.as_deref(), Some("R-100203"));
+        assert_eq!(payment.invoices, [InvoiceId::from("E1000003".to_owned())]);
+
+        let refund = &payments[1];
+        assert!(refund.amount.is_negative());
+        assert_eq!(refund.amount.cents(), -2_000);
+        assert_eq!(refund.receipt_number, None);
+        assert!(refund.invoices.is_empty());
+        assert_eq!(refund.rest["reason"], "Outage credit");
+
+        assert_eq!(payments[2].invoices.len(), 2);
+        assert!(matches!(payments[2].method, PaymentMethodKind::Bpay(_)));
+        assert_eq!(payments[3].receipt_number, None);
+
+        let paid: SignedPrice = payments.iter().map(|payment| payment.amount).sum();
+        assert_eq!(paid.cents(), 40_047);
+    }
+}
@@ -0,0 +1,183 @@
+//! Queries for the plans to which a service can be changed
+
+use super::{flexible_date, unparse_iso_date, Data, DataAmount, Price, ServiceId};
+use crate::Query;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The identifier of a plan
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlanId(String);
+
+impl From<String> for PlanId {
+    fn from(id: String) -> Self {
+        PlanId(id)
+    }
+}
+
+impl AsRef<str> for PlanId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PlanId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for PlanId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Query for the plans to which a service can be changed
+pub struct GetAvailablePlans {
+    service_id: ServiceId,
+}
+
+impl GetAvailablePlans {
+    pub fn new(service_id: ServiceId) -> Self {
+        GetAvailablePlans { service_id }
+    }
+}
+
+impl Query for GetAvailablePlans {
+    type Body = ();
+    type Response = Data<Vec<PlanOption>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/plans", self.service_id).into()
+    }
+}
+
+/// A plan to which a service can be changed
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanOption {
+    pub id: PlanId,
+    pub name: String,
+    pub monthly_price: Price,
+    /// The tier of speed of the plan, such as `100/20`
+    #[serde(default)]
+    pub speed_tier: Option<String>,
+    /// The data included in each billing cycle
+    pub quota: DataAmount,
+    /// The length of the contract the plan requires, in months, if any
+    #[serde(default)]
+    pub contract_months: Option<u32>,
+    /// Whether changing to the plan starts a new contract
+    #[serde(default)]
+    pub restarts_contract: bool,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// When a change of plan takes effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlanChangeTiming {
+    Immediate,
+    /// At the start of the next billing cycle
+    NextBillingCycle,
+}
+
+/// Query to change the plan of a service
+pub struct RequestPlanChange {
+    service_id: ServiceId,
+    change: PlanChange,
+}
+
+/// The body of a request to change the plan of a service
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanChange {
+    plan_id: PlanId,
+    effective: PlanChangeTiming,
+}
+
+impl RequestPlanChange {
+    pub fn new(service_id: ServiceId, plan_id: PlanId, effective: PlanChangeTiming) -> Self {
+        let change = PlanChange { plan_id, effective };
+        RequestPlanChange { service_id, change }
+    }
+}
+
+impl Query for RequestPlanChange {
+    type Body = PlanChange;
+    type Response = Data<PlanChangeReceipt>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/plan-change", self.service_id).into()
+    }
+
+    fn body(&self) -> Option<&PlanChange> {
+        Some(&self.change)
+    }
+}
+
+/// Confirmation that a change of plan has been scheduled
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanChangeReceipt {
+    /// The reference for the request
+    pub reference: String,
+    /// The date on which the change takes effect
+    #[serde(serialize_with = "unparse_iso_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub scheduled_date: NaiveDate,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn available_plans_are_read() {
+        let plans = include_str!("../../fixtures/plans/available.json");
+        let plans = serde_json::from_str::<Data<Vec<PlanOption>>>(plans).unwrap().unwrap();
+        assert_eq!(plans.len(), 3);
+
+        assert_eq!(plans[0].id.as_ref(), "NBN100-20");
+        assert_eq!(plans[0].monthly_price, Price::from_cents(8_499));
+        assert_eq!(plans[0].quota, DataAmount::Unlimited);
+        assert_eq!(plans[0].contract_months, None);
+        assert!(!plans[0].restarts_contract);
+
+        assert_eq!(plans[1].quota, DataAmount::Bytes(500_000_000_000));
+        assert_eq!(plans[1].contract_months, Some(12));
+        assert!(plans[1].restarts_contract);
+        assert_eq!(plans[1].rest["promotion"], "First month free");
+
+        assert_eq!(plans[2].speed_tier, None);
+        assert_eq!(plans[2].quota, DataAmount::Bytes(20_000_000_000));
+        assert!(!plans[2].restarts_contract);
+    }
+
+    #[test]
+    fn plan_changes_are_requested_and_scheduled() {
+        let plan = PlanId::from("NBN50-500GB".to_owned());
+        let timing = PlanChangeTiming::NextBillingCycle;
+        let query = RequestPlanChange::new(ServiceId::from(1_000_001), plan, timing);
+        assert_eq!(query.path(), "/service/1000001/plan-change");
+        let expected = serde_json::json!({
+            "planId": "NBN50-500GB",
+            "effective": "nextBillingCycle",
+        });
+        assert_eq!(serde_json::to_value(query.body()).unwrap(), expected);
+
+        let receipt = include_str!("../../fixtures/plans/change.json");
+        let receipt = serde_json::from_str::<Data<PlanChangeReceipt>>(receipt).unwrap().unwrap();
+        assert_eq!(receipt.reference, "PC-44120");
+        assert_eq!(receipt.scheduled_date, NaiveDate::from_ymd_opt(2026, 11, 1).unwrap());
+    }
+}
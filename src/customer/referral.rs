@@ -0,0 +1,140 @@
+//! Queries for the referrals made by an account
+
+use super::{Data, Price};
+use crate::Query;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Query for the referrals made by the account
+pub struct GetReferrals;
+
+impl Query for GetReferrals {
+    type Body = ();
+    type Response = Data<ReferralInfo>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/referral".into()
+    }
+}
+
+/// The referrals made by an account and the credit earned by them
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferralInfo {
+    /// The code with which others can be referred
+    pub code: String,
+    /// The link with which others can be referred, if given
+    #[serde(default)]
+    pub link: Option<String>,
+    /// The number of referrals that have joined
+    #[serde(default)]
+    pub successful_referrals: u32,
+    /// The credit earned by referrals that have joined
+    #[serde(default)]
+    pub credits_earned: Price,
+    /// The referrals that are yet to join
+    #[serde(default)]
+    pub pending: Vec<PendingReferral>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// A referral that is yet to join
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingReferral {
+    /// The name of the person referred
+    pub name: String,
+    pub status: ReferralStatus,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// The progress of a referral
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum ReferralStatus {
+    /// The person referred has been sent the referral
+    Invited,
+    /// The person referred has signed up, but their service is yet to be connected
+    SignedUp,
+    /// A status not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for ReferralStatus {
+    fn from(status: String) -> Self {
+        let normalized = status.to_ascii_lowercase().replace(|c: char| !c.is_alphanumeric(), "");
+        match normalized.as_str() {
+            "invited" => ReferralStatus::Invited,
+            "signedup" => ReferralStatus::SignedUp,
+            _ => ReferralStatus::Other(status),
+        }
+    }
+}
+
+impl From<ReferralStatus> for String {
+    fn from(status: ReferralStatus) -> String {
+        match status {
+            ReferralStatus::Other(status) => status,
+            status => format!("{}", status),
+        }
+    }
+}
+
+impl fmt::Display for ReferralStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReferralStatus::Invited => write!(f, "Invited"),
+            ReferralStatus::SignedUp => write!(f, "Signed Up"),
+            ReferralStatus::Other(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+impl fmt::Debug for ReferralStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn referrals(fixture: &str) -> ReferralInfo {
+        serde_json::from_str::<Data<ReferralInfo>>(fixture).unwrap().unwrap()
+    }
+
+    #[test]
+    fn referrals_are_read() {
+        let info = referrals(include_str!("../../fixtures/referrals/active.json"));
+        assert_eq!(info.code, "FRIEND-7Q2X");
+        let link = "https://www.exetel.com.au/refer/FRIEND-7Q2X";
+        assert_eq!(info.link.as_deref(), Some(link));
+        assert_eq!(info.successful_referrals, 3);
+        assert_eq!(info.credits_earned, Price::from_cents(15_000));
+        assert_eq!(info.rest["creditPerReferral"], "$50.00");
+
+        let statuses: Vec<_> = info.pending.iter().map(|referral| &referral.status).collect();
+        let expired = ReferralStatus::Other("Expired".to_owned());
+        let expected = [&ReferralStatus::Invited, &ReferralStatus::SignedUp, &expired];
+        assert_eq!(statuses, expected);
+        assert_eq!(info.pending[1].name, "Sam");
+        assert_eq!(info.pending[1].rest["expectedConnection"], "2026-10-30");
+    }
+
+    #[test]
+    fn accounts_without_referrals_have_none() {
+        let info = referrals(include_str!("../../fixtures/referrals/new.json"));
+        assert_eq!(info.code, "FRIEND-9K4M");
+        assert_eq!(info.link, None);
+        assert_eq!(info.successful_referrals, 0);
+        assert_eq!(info.credits_earned, Price::default());
+        assert!(info.pending.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+//! Queries to suspend a service for a time and to resume it
+
+use super::{unparse_iso_date, unparse_optional_iso_date, ServiceId};
+use crate::{Method, Query};
+use chrono::NaiveDate;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Query to suspend a service for a time
+pub struct SuspendService {
+    service_id: ServiceId,
+    suspension: Suspension,
+}
+
+/// The body of a request to suspend a service
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suspension {
+    #[serde(serialize_with = "unparse_iso_date")]
+    from: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "unparse_optional_iso_date")]
+    until: Option<NaiveDate>,
+}
+
+impl SuspendService {
+    /// Suspend a service from a date, until another date or until it is resumed
+    pub fn new(service_id: ServiceId, from: NaiveDate, until: Option<NaiveDate>) -> Self {
+        SuspendService { service_id, suspension: Suspension { from, until } }
+    }
+}
+
+impl Query for SuspendService {
+    type Body = Suspension;
+    type Response = IgnoredAny;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/suspend", self.service_id).into()
+    }
+
+    fn body(&self) -> Option<&Suspension> {
+        Some(&self.suspension)
+    }
+}
+
+/// Query to resume a suspended service
+pub struct ResumeService {
+    service_id: ServiceId,
+}
+
+impl ResumeService {
+    pub fn new(service_id: ServiceId) -> Self {
+        ResumeService { service_id }
+    }
+}
+
+impl Query for ResumeService {
+    type Body = ();
+    type Response = IgnoredAny;
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/service/{}/resume", self.service_id).into()
+    }
+}
+
+/// The status of a service
+#[derive(Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum ServiceStatus {
+    #[default]
+    Active,
+    Suspended,
+    /// Yet to be connected
+    Pending,
+    Cancelled,
+    /// A status not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for ServiceStatus {
+    fn from(status: String) -> Self {
+        match status.to_ascii_lowercase().as_str() {
+            "active" => ServiceStatus::Active,
+            "suspended" => ServiceStatus::Suspended,
+            "pending" => ServiceStatus::Pending,
+            "cancelled" | "canceled" => ServiceStatus::Cancelled,
+            _ => ServiceStatus::Other(status),
+        }
+    }
+}
+
+impl From<ServiceStatus> for String {
+    fn from(status: ServiceStatus) -> String {
+        match status {
+            ServiceStatus::Other(status) => status,
+            status => format!("{}", status),
+        }
+    }
+}
+
+impl fmt::Display for ServiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServiceStatus::Active => write!(f, "Active"),
+            ServiceStatus::Suspended => write!(f, "Suspended"),
+            ServiceStatus::Pending => write!(f, "Pending"),
+            ServiceStatus::Cancelled => write!(f, "Cancelled"),
+            ServiceStatus::Other(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+impl fmt::Debug for ServiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use crate::Error;
+    use serde_json::json;
+
+    /// The path at which the server receives requests to suspend the broadband service
+    const SUSPEND_PATH: &str = "/api/v1/service/1000001/suspend";
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn services_are_suspended_and_resumed() {
+        let server = MockServer::start().await;
+        server.respond(Method::POST, SUSPEND_PATH, MockResponse::empty(204));
+        server.respond(Method::POST, SUSPEND_PATH, MockResponse::empty(204));
+        let resume = "/api/v1/service/1000001/resume";
+        server.respond(Method::POST, resume, MockResponse::empty(204));
+        let client = server.client();
+
+        let service_id = ServiceId::from(1_000_001);
+        let (from, until) = (date(2026, 12, 20), date(2027, 1, 10));
+        client.suspend_service(service_id, from, Some(until)).await.unwrap();
+        client.suspend_service(service_id, from, None).await.unwrap();
+        client.resume_service(service_id).await.unwrap();
+
+        let received = server.received();
+        assert_eq!(received[0].json(), json!({"from": "2026-12-20", "until": "2027-01-10"}));
+        assert_eq!(received[1].json(), json!({"from": "2026-12-20"}));
+        assert_eq!(received[2].method, Method::POST);
+        assert_eq!(received[2].path, resume);
+    }
+
+    #[tokio::test]
+    async fn suspended_services_are_not_suspended_again() {
+        let server = MockServer::start().await;
+        let conflict = r#"{"message": "Service is already suspended"}"#;
+        server.respond(Method::POST, SUSPEND_PATH, MockResponse::json(409, conflict));
+        let client = server.client();
+
+        let service_id = ServiceId::from(1_000_001);
+        let err = client.suspend_service(service_id, date(2026, 12, 20), None).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::AlreadySuspended), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn ineligible_services_are_reported() {
+        let server = MockServer::start().await;
+        let ineligible = r#"{"message": "Services in contract can not be suspended"}"#;
+        server.respond(Method::POST, SUSPEND_PATH, MockResponse::json(422, ineligible));
+        let client = server.client();
+
+        let service_id = ServiceId::from(1_000_001);
+        let err = client.suspend_service(service_id, date(2026, 12, 20), None).await.unwrap_err();
+        match err.inner() {
+            Error::ServiceIneligible { message } => {
+                assert_eq!(message, "Services in contract can not be suspended");
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn statuses_are_read_in_any_case() {
+        let status = |status: &str| ServiceStatus::from(status.to_owned());
+        assert_eq!(status("SUSPENDED"), ServiceStatus::Suspended);
+        assert_eq!(status("canceled"), ServiceStatus::Cancelled);
+        assert_eq!(status("Porting"), ServiceStatus::Other("Porting".to_owned()));
+        assert_eq!(String::from(ServiceStatus::Pending), "Pending");
+        assert_eq!(ServiceStatus::default(), ServiceStatus::Active);
+    }
+}
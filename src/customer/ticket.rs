@@ -0,0 +1,444 @@
+//! Queries for the support tickets of an account
+
+use super::{parse_datetime, unparse_datetime, Data, ServiceId};
+use crate::{Error, Query};
+use chrono::{DateTime, Utc};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The number identifying a support ticket
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TicketId(u64);
+
+impl From<u64> for TicketId {
+    fn from(id: u64) -> Self {
+        TicketId(id)
+    }
+}
+
+impl From<TicketId> for u64 {
+    fn from(id: TicketId) -> u64 {
+        id.0
+    }
+}
+
+impl fmt::Display for TicketId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for TicketId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// The tickets to list
+#[derive(Debug, Clone, Default)]
+pub struct TicketFilter {
+    /// Only list tickets with this status
+    pub status: Option<TicketStatus>,
+}
+
+/// Query for the support tickets of the account
+#[derive(Default)]
+pub struct GetTickets {
+    filter: TicketFilter,
+}
+
+impl GetTickets {
+    pub fn new(filter: TicketFilter) -> Self {
+        GetTickets { filter }
+    }
+}
+
+impl Query for GetTickets {
+    type Body = ();
+    type Response = Data<Vec<TicketSummary>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/ticket".into()
+    }
+
+    fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        match &self.filter.status {
+            Some(status) => vec![("status".into(), status.to_string().to_lowercase().into())],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Query for a support ticket, along with its messages
+pub struct GetTicket {
+    id: TicketId,
+}
+
+impl GetTicket {
+    pub fn new(id: TicketId) -> Self {
+        GetTicket { id }
+    }
+}
+
+impl Query for GetTicket {
+    type Body = ();
+    type Response = Data<Ticket>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/ticket/{}", self.id).into()
+    }
+}
+
+/// Longest subject accepted for a support ticket, in characters
+const MAX_SUBJECT: usize = 200;
+
+/// Longest body accepted for a message of a support ticket, in characters
+const MAX_BODY: usize = 10_000;
+
+/// A support ticket to be created
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTicket {
+    pub subject: String,
+    /// The first message of the ticket
+    pub body: String,
+    /// The service to which the ticket relates, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<ServiceId>,
+    pub category: TicketCategory,
+}
+
+/// Query to create a support ticket
+pub struct CreateTicket {
+    ticket: NewTicket,
+}
+
+impl CreateTicket {
+    /// Create the query, failing if the ticket would be rejected by the API
+    pub fn new(ticket: NewTicket) -> Result<Self, Error> {
+        validate("subject", &ticket.subject, MAX_SUBJECT)?;
+        validate("body", &ticket.body, MAX_BODY)?;
+        Ok(CreateTicket { ticket })
+    }
+}
+
+impl Query for CreateTicket {
+    type Body = NewTicket;
+    type Response = Data<CreatedTicket>;
+
+    fn path(&self) -> Cow<'_, str> {
+        "/ticket".into()
+    }
+
+    fn body(&self) -> Option<&NewTicket> {
+        Some(&self.ticket)
+    }
+}
+
+/// The response to the creation of a support ticket
+#[derive(Debug, Deserialize)]
+pub struct CreatedTicket {
+    pub id: TicketId,
+}
+
+/// Query to reply to a support ticket
+pub struct ReplyTicket {
+    id: TicketId,
+    reply: TicketReply,
+}
+
+/// The body of a reply to a support ticket
+#[derive(Debug, Serialize)]
+pub struct TicketReply {
+    body: String,
+}
+
+impl ReplyTicket {
+    /// Create the query, failing if the reply would be rejected by the API
+    pub fn new(id: TicketId, body: &str) -> Result<Self, Error> {
+        validate("body", body, MAX_BODY)?;
+        let reply = TicketReply { body: body.to_owned() };
+        Ok(ReplyTicket { id, reply })
+    }
+}
+
+impl Query for ReplyTicket {
+    type Body = TicketReply;
+    type Response = IgnoredAny;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/ticket/{}/reply", self.id).into()
+    }
+
+    fn body(&self) -> Option<&TicketReply> {
+        Some(&self.reply)
+    }
+}
+
+/// Check that a field of a ticket is neither blank nor longer than a number of characters
+fn validate(field: &str, text: &str, max: usize) -> Result<(), Error> {
+    if text.trim().is_empty() {
+        return Err(Error::InvalidInput(format!("ticket {} is empty", field)));
+    }
+    let length = text.chars().count();
+    if length > max {
+        let message = format!("ticket {} is {} characters, at most {} allowed", field, length, max);
+        return Err(Error::InvalidInput(message));
+    }
+    Ok(())
+}
+
+/// A summary of a support ticket
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketSummary {
+    pub id: TicketId,
+    pub subject: String,
+    pub status: TicketStatus,
+    /// The time at which the ticket was last changed
+    #[serde(serialize_with = "unparse_datetime")]
+    #[serde(deserialize_with = "parse_datetime")]
+    pub last_updated: DateTime<Utc>,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// A support ticket, along with its messages
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticket {
+    #[serde(flatten)]
+    pub summary: TicketSummary,
+    /// The messages of the ticket, from the first
+    #[serde(default)]
+    pub messages: Vec<TicketMessage>,
+}
+
+/// A message in the thread of a support ticket
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketMessage {
+    /// The name of the person who wrote the message
+    pub author: String,
+    /// The time at which the message was written
+    #[serde(serialize_with = "unparse_datetime")]
+    #[serde(deserialize_with = "parse_datetime")]
+    pub timestamp: DateTime<Utc>,
+    pub body: String,
+}
+
+/// The category of a support ticket
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum TicketCategory {
+    Billing,
+    Technical,
+    General,
+    /// A category not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for TicketCategory {
+    fn from(category: String) -> Self {
+        match category.to_ascii_lowercase().as_str() {
+            "billing" => TicketCategory::Billing,
+            "technical" => TicketCategory::Technical,
+            "general" => TicketCategory::General,
+            _ => TicketCategory::Other(category),
+        }
+    }
+}
+
+impl From<TicketCategory> for String {
+    fn from(category: TicketCategory) -> String {
+        match category {
+            TicketCategory::Other(category) => category,
+            category => format!("{}", category),
+        }
+    }
+}
+
+impl fmt::Display for TicketCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TicketCategory::Billing => write!(f, "Billing"),
+            TicketCategory::Technical => write!(f, "Technical"),
+            TicketCategory::General => write!(f, "General"),
+            TicketCategory::Other(category) => write!(f, "{}", category),
+        }
+    }
+}
+
+impl fmt::Debug for TicketCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// The status of a support ticket
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum TicketStatus {
+    Open,
+    /// Waiting on a response from the account holder
+    Pending,
+    Closed,
+    /// A status not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for TicketStatus {
+    fn from(status: String) -> Self {
+        match status.to_ascii_lowercase().as_str() {
+            "open" => TicketStatus::Open,
+            "pending" => TicketStatus::Pending,
+            "closed" => TicketStatus::Closed,
+            _ => TicketStatus::Other(status),
+        }
+    }
+}
+
+impl From<TicketStatus> for String {
+    fn from(status: TicketStatus) -> String {
+        match status {
+            TicketStatus::Other(status) => status,
+            status => format!("{}", status),
+        }
+    }
+}
+
+impl fmt::Display for TicketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TicketStatus::Open => write!(f, "Open"),
+            TicketStatus::Pending => write!(f, "Pending"),
+            TicketStatus::Closed => write!(f, "Closed"),
+            TicketStatus::Other(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+impl fmt::Debug for TicketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use chrono::TimeZone;
+    use reqwest::Method;
+
+    #[test]
+    fn tickets_are_listed() {
+        let tickets = include_str!("../../fixtures/tickets/list.json");
+        let tickets = serde_json::from_str::<Data<Vec<TicketSummary>>>(tickets).unwrap().unwrap();
+        assert_eq!(tickets.len(), 3);
+        assert_eq!(tickets[0].id, TicketId::from(70_001));
+        assert_eq!(tickets[0].subject, "Dropouts every evening");
+        assert_eq!(tickets[0].status, TicketStatus::Open);
+        assert_eq!(tickets[0].last_updated, Utc.with_ymd_and_hms(2026, 10, 11, 22, 30, 0).unwrap());
+        assert_eq!(tickets[0].rest["priority"], "high");
+        assert_eq!(tickets[1].status, TicketStatus::Pending);
+        assert_eq!(tickets[2].status, TicketStatus::Other("ESCALATED".to_owned()));
+    }
+
+    #[test]
+    fn tickets_are_filtered_by_status() {
+        assert!(GetTickets::default().query_params().is_empty());
+        let filter = TicketFilter { status: Some(TicketStatus::Pending) };
+        let query = GetTickets::new(filter);
+        assert_eq!(query.query_params(), [("status".into(), "pending".into())]);
+    }
+
+    #[test]
+    fn ticket_is_read_with_its_messages() {
+        let ticket = include_str!("../../fixtures/tickets/detail.json");
+        let ticket = serde_json::from_str::<Data<Ticket>>(ticket).unwrap().unwrap();
+        assert_eq!(ticket.summary.id, TicketId::from(70_001));
+        assert_eq!(ticket.messages.len(), 2);
+        assert_eq!(ticket.messages[0].author, "Account Holder");
+        assert!(ticket.messages[0].timestamp < ticket.messages[1].timestamp);
+        assert_eq!(ticket.messages[1].timestamp, ticket.summary.last_updated);
+        assert_eq!(GetTicket::new(ticket.summary.id).path(), "/ticket/70001");
+    }
+
+    fn ticket(subject: &str, body: &str) -> NewTicket {
+        NewTicket {
+            subject: subject.to_owned(),
+            body: body.to_owned(),
+            service_id: None,
+            category: TicketCategory::Technical,
+        }
+    }
+
+    #[test]
+    fn blank_tickets_are_rejected() {
+        let err = CreateTicket::new(ticket("", "body")).err().unwrap();
+        assert!(matches!(&err, Error::InvalidInput(message) if message.contains("subject")));
+        let err = CreateTicket::new(ticket("subject", " \n\t")).err().unwrap();
+        assert!(matches!(&err, Error::InvalidInput(message) if message.contains("body")));
+        let err = ReplyTicket::new(TicketId::from(1), "").err().unwrap();
+        assert!(matches!(&err, Error::InvalidInput(message) if message.contains("body")));
+    }
+
+    #[test]
+    fn long_tickets_are_rejected_by_characters() {
+        let subject = "é".repeat(MAX_SUBJECT);
+        assert!(CreateTicket::new(ticket(&subject, "body")).is_ok());
+        let subject = "é".repeat(MAX_SUBJECT + 1);
+        let err = CreateTicket::new(ticket(&subject, "body")).err().unwrap();
+        assert!(matches!(&err, Error::InvalidInput(message) if message.contains("201")));
+
+        let body = "x".repeat(MAX_BODY);
+        assert!(ReplyTicket::new(TicketId::from(1), &body).is_ok());
+        let body = "x".repeat(MAX_BODY + 1);
+        assert!(ReplyTicket::new(TicketId::from(1), &body).is_err());
+    }
+
+    #[tokio::test]
+    async fn tickets_are_created_and_replied_to() {
+        let server = MockServer::start().await;
+        let created = MockResponse::json(201, r#"{"data": {"id": 70003}}"#);
+        server.respond(Method::POST, "/api/v1/ticket", created);
+        server.respond(Method::POST, "/api/v1/ticket/70003/reply", MockResponse::empty(204));
+        let client = server.client();
+
+        let mut new = ticket("No dial tone", "The phone has no dial tone.");
+        new.service_id = Some(ServiceId::from(3_000_001));
+        let id = client.create_ticket(new).await.unwrap();
+        assert_eq!(id, TicketId::from(70_003));
+        client.reply_ticket(id, "It is back now.").await.unwrap();
+
+        let received = server.received();
+        let expected = serde_json::json!({
+            "subject": "No dial tone",
+            "body": "The phone has no dial tone.",
+            "serviceId": 3_000_001,
+            "category": "Technical",
+        });
+        assert_eq!(received[0].json(), expected);
+        assert_eq!(received[1].json(), serde_json::json!({"body": "It is back now."}));
+    }
+
+    #[tokio::test]
+    async fn invalid_tickets_are_not_sent() {
+        let server = MockServer::start().await;
+        let client = server.client();
+
+        let err = client.create_ticket(ticket("  ", "body")).await.unwrap_err();
+        assert!(matches!(err.inner(), Error::InvalidInput(_)), "{:?}", err);
+        let err = client.reply_ticket(TicketId::from(70_003), "").await.unwrap_err();
+        assert!(matches!(err.inner(), Error::InvalidInput(_)), "{:?}", err);
+        assert!(server.received().is_empty());
+    }
+}
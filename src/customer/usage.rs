@@ -0,0 +1,171 @@
+//! Queries for the usage of services
+//!
+//! Records of calls and messages share the details in `UsageRecord`, and are listed a page at a
+//! time by `GetRecords`.
+
+use super::{parse_offset_datetime, unparse_offset_datetime, Data, DateRange, Price, ServiceId};
+use crate::paginate::{Page, PagedQuery};
+use crate::Query;
+use chrono::{DateTime, FixedOffset};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+mod calls;
+mod mobile;
+
+pub use calls::{CallHistory, CallRecord, CallType, PhoneCallRecord};
+pub use mobile::{DataAmount, DataUsage, GetMobileUsage, MobileUsage, SmsRecord};
+
+/// Query for one page of the records of a service within a range of dates
+pub struct GetRecords<T> {
+    /// The kind of service, as the first segment of the path
+    service: &'static str,
+    service_id: ServiceId,
+    /// The kind of records, as the last segment of the path
+    records: &'static str,
+    range: DateRange,
+    page: u32,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T> GetRecords<T> {
+    fn new(
+        service: &'static str,
+        records: &'static str,
+        service_id: ServiceId,
+        range: DateRange,
+    ) -> Self {
+        GetRecords { service, service_id, records, range, page: 1, item: PhantomData }
+    }
+}
+
+impl GetRecords<CallRecord> {
+    /// Query for the calls of a VoIP service
+    pub fn voip_calls(service_id: ServiceId, range: DateRange) -> Self {
+        GetRecords::new("voip", "calls", service_id, range)
+    }
+
+    /// Query for the calls of a mobile service
+    pub fn mobile_calls(service_id: ServiceId, range: DateRange) -> Self {
+        GetRecords::new("mobile", "calls", service_id, range)
+    }
+}
+
+impl GetRecords<PhoneCallRecord> {
+    /// Query for the calls of a phone service
+    pub fn phone_calls(service_id: ServiceId, range: DateRange) -> Self {
+        GetRecords::new("phone", "calls", service_id, range)
+    }
+}
+
+impl GetRecords<SmsRecord> {
+    /// Query for the messages sent by a mobile service
+    pub fn mobile_sms(service_id: ServiceId, range: DateRange) -> Self {
+        GetRecords::new("mobile", "sms", service_id, range)
+    }
+}
+
+impl<T> Clone for GetRecords<T> {
+    fn clone(&self) -> Self {
+        GetRecords { item: PhantomData, ..*self }
+    }
+}
+
+impl<T: DeserializeOwned> Query for GetRecords<T> {
+    type Body = ();
+    type Response = Data<RecordPage<T>>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/{}/{}/{}", self.service, self.service_id, self.records).into()
+    }
+
+    fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        let mut params = self.range.query_params();
+        params.push(("page".into(), self.page.to_string().into()));
+        params
+    }
+}
+
+impl<T: DeserializeOwned> PagedQuery for GetRecords<T> {
+    type Item = T;
+
+    fn page_param(&self, page: u32) -> Self {
+        GetRecords { page, ..self.clone() }
+    }
+
+    fn into_page(response: Data<RecordPage<T>>) -> Page<T> {
+        let RecordPage { records, page, per_page, total } = response.unwrap();
+        Page::from_totals(records, page, per_page, total)
+    }
+}
+
+/// One page of the records of a service
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordPage<T> {
+    #[serde(default = "Vec::new")]
+    records: Vec<T>,
+    page: u32,
+    per_page: u32,
+    total: u64,
+}
+
+/// The details shared by the records of calls and messages
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+    /// The number at the other end of the call, or to which the message was sent
+    #[serde(alias = "destination")]
+    pub number: String,
+    /// Whether the call or message was sent or received
+    #[serde(default)]
+    pub direction: Direction,
+    /// The time at which the call started or the message was sent
+    #[serde(alias = "start", alias = "timestamp")]
+    #[serde(serialize_with = "unparse_offset_datetime")]
+    #[serde(deserialize_with = "parse_offset_datetime")]
+    pub time: DateTime<FixedOffset>,
+    pub cost: Price,
+}
+
+impl UsageRecord {
+    /// The number at the other end, with all but its last three digits masked
+    pub fn masked_number(&self) -> String {
+        mask_number(&self.number)
+    }
+}
+
+/// Whether a call or message was sent or received
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    Inbound,
+    #[default]
+    Outbound,
+}
+
+/// Mask all but the last three digits of a number
+///
+/// ```
+/// use exetel_api::customer::mask_number;
+///
+/// assert_eq!(mask_number("0400 000 678"), "**** *** 678");
+/// assert_eq!(mask_number("+61 400 000 678"), "+** *** *** 678");
+/// ```
+pub fn mask_number(number: &str) -> String {
+    const KEPT: usize = 3;
+    let digits = number.chars().filter(char::is_ascii_digit).count();
+    let mut seen = 0;
+    number
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            seen += 1;
+            if seen + KEPT > digits { c } else { '*' }
+        })
+        .collect()
+}
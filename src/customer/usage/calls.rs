@@ -0,0 +1,226 @@
+//! Records of the calls made and received by services
+
+use super::UsageRecord;
+use crate::customer::{parse_duration, unparse_duration, Price};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Duration;
+
+/// A call made or received by a service
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRecord {
+    /// The number at the other end of the call, when the call started and what it cost
+    #[serde(flatten)]
+    pub record: UsageRecord,
+    /// The length of the call, which is zero if it was not answered
+    #[serde(serialize_with = "unparse_duration")]
+    #[serde(deserialize_with = "parse_duration")]
+    pub duration: Duration,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// A call made or received by a phone service
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhoneCallRecord {
+    #[serde(flatten)]
+    pub call: CallRecord,
+    pub call_type: CallType,
+}
+
+/// The calls of a phone service, grouped by their type
+#[derive(Debug)]
+pub struct CallHistory {
+    records: Vec<PhoneCallRecord>,
+}
+
+impl CallHistory {
+    pub fn new(records: Vec<PhoneCallRecord>) -> Self {
+        CallHistory { records }
+    }
+
+    /// Every call, in the order given by the API
+    pub fn records(&self) -> &[PhoneCallRecord] {
+        &self.records
+    }
+
+    /// The calls of each type
+    pub fn by_category(&self) -> BTreeMap<&CallType, Vec<&PhoneCallRecord>> {
+        let mut categories = BTreeMap::<_, Vec<_>>::new();
+        for record in &self.records {
+            categories.entry(&record.call_type).or_default().push(record);
+        }
+        categories
+    }
+
+    /// The total cost of every call
+    pub fn total_cost(&self) -> Price {
+        self.records.iter().map(|record| record.call.record.cost).sum()
+    }
+
+    /// The total cost of the calls of each type
+    pub fn cost_by_category(&self) -> BTreeMap<&CallType, Price> {
+        let mut costs = BTreeMap::<_, Price>::new();
+        for record in &self.records {
+            *costs.entry(&record.call_type).or_default() += record.call.record.cost;
+        }
+        costs
+    }
+}
+
+impl From<Vec<PhoneCallRecord>> for CallHistory {
+    fn from(records: Vec<PhoneCallRecord>) -> Self {
+        CallHistory::new(records)
+    }
+}
+
+/// The type of a call made by a phone service, by the kind of number called
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "String")]
+#[serde(into = "String")]
+pub enum CallType {
+    Local,
+    National,
+    Mobile,
+    International,
+    /// A call to a `13` or `1300` number
+    InformationService,
+    /// A type not otherwise known, as given by the API
+    Other(String),
+}
+
+impl From<String> for CallType {
+    fn from(call_type: String) -> Self {
+        match call_type.to_ascii_lowercase().as_str() {
+            "local" => CallType::Local,
+            "national" => CallType::National,
+            "mobile" => CallType::Mobile,
+            "international" => CallType::International,
+            "13" | "1300" | "13/1300" => CallType::InformationService,
+            _ => CallType::Other(call_type),
+        }
+    }
+}
+
+impl From<CallType> for String {
+    fn from(call_type: CallType) -> String {
+        match call_type {
+            CallType::Other(call_type) => call_type,
+            call_type => format!("{}", call_type),
+        }
+    }
+}
+
+impl fmt::Display for CallType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallType::Local => write!(f, "Local"),
+            CallType::National => write!(f, "National"),
+            CallType::Mobile => write!(f, "Mobile"),
+            CallType::International => write!(f, "International"),
+            CallType::InformationService => write!(f, "13/1300"),
+            CallType::Other(call_type) => write!(f, "{}", call_type),
+        }
+    }
+}
+
+impl fmt::Debug for CallType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer::usage::GetRecords;
+    use crate::customer::{Data, Direction};
+    use crate::paginate::{Page, PagedQuery};
+    use serde::de::DeserializeOwned;
+
+    fn page<T: DeserializeOwned>(fixture: &str) -> Page<T> {
+        let response = serde_json::from_str::<Data<_>>(fixture).unwrap();
+        GetRecords::<T>::into_page(response)
+    }
+
+    #[test]
+    fn voip_calls_are_read() {
+        let page = page::<CallRecord>(include_str!("../../../fixtures/calls/voip.json"));
+        assert!(!page.has_more);
+        let calls = page.items;
+        assert_eq!(calls.len(), 3);
+
+        assert_eq!(calls[0].record.number, "0298765678");
+        assert_eq!(calls[0].record.masked_number(), "*******678");
+        assert_eq!(calls[0].duration, Duration::from_secs(4 * 60 + 5));
+        assert_eq!(calls[0].record.cost, Price::from_cents(10));
+        assert_eq!(calls[2].duration, Duration::from_secs(60 * 60 + 2 * 60 + 3));
+        assert_eq!(calls[2].record.masked_number(), "+** ** **** *018");
+    }
+
+    #[test]
+    fn unanswered_calls_take_no_time() {
+        let page = page::<CallRecord>(include_str!("../../../fixtures/calls/voip.json"));
+        let missed = &page.items[1];
+        assert_eq!(missed.duration, Duration::from_secs(0));
+        assert_eq!(missed.record.cost, Price::default());
+        assert_eq!(missed.record.direction, Direction::Inbound);
+        assert_eq!(missed.record.masked_number(), "**** *** 678");
+        assert_eq!(missed.rest["answered"], false);
+    }
+
+    fn history() -> CallHistory {
+        let page = page::<PhoneCallRecord>(include_str!("../../../fixtures/calls/phone.json"));
+        assert!(page.has_more);
+        CallHistory::from(page.items)
+    }
+
+    #[test]
+    fn phone_calls_are_grouped_by_type() {
+        let history = history();
+        assert_eq!(history.records().len(), 6);
+        assert_eq!(history.records()[2].call_type, CallType::InformationService);
+        assert_eq!(history.records()[3].call_type, CallType::InformationService);
+
+        let categories = history.by_category();
+        let types: Vec<_> = categories.keys().cloned().cloned().collect();
+        let premium = CallType::Other("Premium".to_owned());
+        let expected = [CallType::Local, CallType::Mobile, CallType::InformationService, premium];
+        assert_eq!(types, expected);
+        let mobile = &categories[&CallType::Mobile];
+        assert_eq!(mobile.len(), 2);
+        assert_eq!(mobile[1].call.duration, Duration::from_secs(0));
+        let information: Vec<_> = categories[&CallType::InformationService]
+            .iter()
+            .map(|record| record.call.record.number.as_str())
+            .collect();
+        assert_eq!(information, ["131234", "1300123456"]);
+    }
+
+    #[test]
+    fn phone_call_costs_are_totalled_by_type() {
+        let history = history();
+        assert_eq!(history.total_cost(), Price::from_cents(790));
+
+        let costs = history.cost_by_category();
+        assert_eq!(costs.len(), 4);
+        assert_eq!(costs[&CallType::Local], Price::from_cents(10));
+        assert_eq!(costs[&CallType::Mobile], Price::from_cents(120));
+        assert_eq!(costs[&CallType::InformationService], Price::from_cents(65));
+        assert_eq!(costs[&CallType::Other("Premium".to_owned())], Price::from_cents(595));
+        assert!(!costs.contains_key(&CallType::International));
+        assert_eq!(costs.values().copied().sum::<Price>(), history.total_cost());
+    }
+
+    #[test]
+    fn call_types_round_trip() {
+        assert_eq!(CallType::from("13/1300".to_owned()), CallType::InformationService);
+        assert_eq!(String::from(CallType::InformationService), "13/1300");
+        assert_eq!(String::from(CallType::Other("Premium".to_owned())), "Premium");
+        assert!(CallType::from("Premium".to_owned()) > CallType::InformationService);
+    }
+}
@@ -0,0 +1,272 @@
+//! The usage of mobile services
+
+use super::UsageRecord;
+use crate::customer::{flexible_date, unparse_date, Data, MobileService, Price, ServiceId};
+use crate::{Error, Query};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// Query for the usage of a mobile service in the current billing cycle
+pub struct GetMobileUsage {
+    service_id: ServiceId,
+}
+
+impl GetMobileUsage {
+    pub fn new(service_id: ServiceId) -> Self {
+        GetMobileUsage { service_id }
+    }
+}
+
+impl From<&MobileService> for GetMobileUsage {
+    fn from(service: &MobileService) -> Self {
+        GetMobileUsage::new(service.as_ref().id)
+    }
+}
+
+impl Query for GetMobileUsage {
+    type Body = ();
+    type Response = Data<MobileUsage>;
+
+    fn path(&self) -> Cow<'_, str> {
+        format!("/mobile/{}/usage", self.service_id).into()
+    }
+}
+
+/// The usage of a mobile service in the current billing cycle
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MobileUsage {
+    /// Data used, out of that included in the plan
+    pub data_usage: DataUsage,
+    /// Minutes of national calls made
+    pub national_call_minutes: u32,
+    /// Number of SMS messages sent
+    pub sms_count: u32,
+    /// Charges for international calls, messages and roaming
+    pub international_usage: Price,
+    /// The date on which the usage is next reset
+    #[serde(serialize_with = "unparse_date")]
+    #[serde(deserialize_with = "flexible_date")]
+    pub reset_date: NaiveDate,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// A message sent by a mobile service
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmsRecord {
+    /// The number to which the message was sent, when it was sent and what it cost
+    ///
+    /// Messages included in the plan cost nothing.
+    #[serde(flatten)]
+    pub record: UsageRecord,
+    #[serde(flatten)]
+    rest: HashMap<String, Value>,
+}
+
+/// An amount of data used, out of that included, such as `12.5GB of 60GB`
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
+pub struct DataUsage {
+    /// The amount of data used
+    pub used: DataAmount,
+    /// The amount of data included
+    pub included: DataAmount,
+}
+
+impl FromStr for DataUsage {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (used, included) = text
+            .split_once(" of ")
+            .ok_or_else(|| Error::InvalidInput(format!("invalid data usage: {}", text)))?;
+        Ok(DataUsage {
+            used: used.parse()?,
+            included: included.parse()?,
+        })
+    }
+}
+
+impl TryFrom<String> for DataUsage {
+    type Error = Error;
+
+    fn try_from(text: String) -> Result<Self, Error> {
+        text.parse()
+    }
+}
+
+impl From<DataUsage> for String {
+    fn from(usage: DataUsage) -> String {
+        format!("{}", usage)
+    }
+}
+
+impl fmt::Display for DataUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} of {}", self.used, self.included)
+    }
+}
+
+impl fmt::Debug for DataUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// An amount of data, such as `12.5GB`
+///
+/// Amounts are kept in bytes, with each unit being 1000 of the last.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
+pub enum DataAmount {
+    Bytes(u64),
+    Unlimited,
+}
+
+impl DataAmount {
+    const UNITS: [(&'static str, u64); 5] = [
+        ("TB", 1_000_000_000_000),
+        ("GB", 1_000_000_000),
+        ("MB", 1_000_000),
+        ("KB", 1_000),
+        ("B", 1),
+    ];
+}
+
+impl FromStr for DataAmount {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("unlimited") {
+            return Ok(DataAmount::Unlimited);
+        }
+
+        let invalid = || Error::InvalidInput(format!("invalid data amount: {}", text));
+        let upper = text.to_ascii_uppercase();
+        let (amount, scale) = Self::UNITS
+            .iter()
+            .find_map(|(unit, scale)| Some((upper.strip_suffix(unit)?, *scale)))
+            .ok_or_else(invalid)?;
+        let amount: f64 = amount.trim().parse().map_err(|_| invalid())?;
+        if !amount.is_finite() || amount < 0.0 {
+            return Err(invalid());
+        }
+        Ok(DataAmount::Bytes((amount * scale as f64).round() as u64))
+    }
+}
+
+impl TryFrom<String> for DataAmount {
+    type Error = Error;
+
+    fn try_from(text: String) -> Result<Self, Error> {
+        text.parse()
+    }
+}
+
+impl From<DataAmount> for String {
+    fn from(amount: DataAmount) -> String {
+        format!("{}", amount)
+    }
+}
+
+impl fmt::Display for DataAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes = match self {
+            DataAmount::Unlimited => return write!(f, "Unlimited"),
+            DataAmount::Bytes(bytes) => *bytes,
+        };
+        let (unit, scale) = Self::UNITS
+            .iter()
+            .find(|(_, scale)| bytes >= *scale)
+            .unwrap_or(&("B", 1));
+        write!(f, "{}{}", bytes as f64 / *scale as f64, unit)
+    }
+}
+
+impl fmt::Debug for DataAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer::usage::GetRecords;
+    use crate::customer::Direction;
+    use crate::paginate::PagedQuery;
+
+    #[test]
+    fn amounts_are_parsed_in_each_unit() {
+        let cases = [
+            ("512B", DataAmount::Bytes(512)),
+            ("1.5KB", DataAmount::Bytes(1_500)),
+            ("250MB", DataAmount::Bytes(250_000_000)),
+            ("12.5GB", DataAmount::Bytes(12_500_000_000)),
+            ("12.5 gb", DataAmount::Bytes(12_500_000_000)),
+            ("2TB", DataAmount::Bytes(2_000_000_000_000)),
+            ("Unlimited", DataAmount::Unlimited),
+            (" unlimited ", DataAmount::Unlimited),
+        ];
+        for (text, expected) in cases.iter() {
+            assert_eq!(text.parse::<DataAmount>().unwrap(), *expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn invalid_amounts_are_rejected() {
+        for text in ["", "GB", "12.5", "12.5PB", "-1GB", "NaNGB", "infGB"].iter() {
+            let err = text.parse::<DataAmount>().unwrap_err();
+            assert!(matches!(err, Error::InvalidInput(_)), "{:?}: {:?}", text, err);
+        }
+    }
+
+    #[test]
+    fn amounts_are_displayed_in_the_largest_unit() {
+        assert_eq!(DataAmount::Bytes(512).to_string(), "512B");
+        assert_eq!(DataAmount::Bytes(250_000_000).to_string(), "250MB");
+        assert_eq!(DataAmount::Bytes(12_500_000_000).to_string(), "12.5GB");
+        assert_eq!(DataAmount::Bytes(2_000_000_000_000).to_string(), "2TB");
+        assert_eq!(DataAmount::Unlimited.to_string(), "Unlimited");
+    }
+
+    #[test]
+    fn usage_round_trips() {
+        let usage: DataUsage = serde_json::from_str(r#""12.5GB of Unlimited""#).unwrap();
+        assert_eq!(usage.used, DataAmount::Bytes(12_500_000_000));
+        assert_eq!(usage.included, DataAmount::Unlimited);
+        assert_eq!(serde_json::to_string(&usage).unwrap(), r#""12.5GB of Unlimited""#);
+
+        assert!("12.5GB".parse::<DataUsage>().is_err());
+    }
+
+    #[test]
+    fn messages_are_read() {
+        let response = serde_json::from_str(include_str!("../../../fixtures/sms.json")).unwrap();
+        let page = GetRecords::<SmsRecord>::into_page(response);
+        assert!(!page.has_more);
+        let messages = page.items;
+        assert_eq!(messages.len(), 2);
+
+        let included = &messages[0].record;
+        assert_eq!(included.masked_number(), "**** *** 678");
+        assert_eq!(included.direction, Direction::Outbound);
+        assert_eq!(included.time.to_rfc3339(), "2026-10-05T12:00:00+11:00");
+        assert_eq!(included.cost, Price::default());
+
+        assert_eq!(messages[1].record.number, "+1 555 0100");
+        assert_eq!(messages[1].record.cost, Price::from_cents(50));
+        assert_eq!(messages[1].rest["international"], true);
+    }
+}
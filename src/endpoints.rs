@@ -0,0 +1,79 @@
+//! Locations of the services used to access the API
+
+use std::fmt;
+
+/// The URLs used to authenticate and query the API
+///
+/// The default endpoints are those of the production Exetel services. Other endpoints can be used
+/// to access a staging environment or a mock server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    /// Origin of the customer portal, sent with authentication requests
+    pub origin: String,
+    /// Referring page, sent with authentication requests
+    pub referer: String,
+    /// Endpoint used to log in
+    pub login: String,
+    /// Endpoint used to complete a login with a one-time password
+    pub otp: String,
+    /// Endpoint used to refresh an access token
+    pub refresh: String,
+    /// Endpoint used to revoke an access token
+    pub logout: String,
+    /// Base URL of the web services API, to which the version of the API is appended
+    pub api: String,
+}
+
+impl Endpoints {
+    const PORTAL_URL: &'static str = "https://my.exetel.com.au";
+    const API_URL: &'static str = "https://webservices.api.exetel.com.au";
+
+    /// Endpoints for a customer portal and API hosted at the given base URLs
+    pub fn new(portal: &str, api: &str) -> Self {
+        let portal = portal.trim_end_matches('/');
+        Endpoints {
+            origin: portal.to_owned(),
+            referer: format!("{}/login", portal),
+            login: format!("{}/api/auth/postLogin", portal),
+            otp: format!("{}/api/auth/postLoginOtp", portal),
+            refresh: format!("{}/api/auth/postRefreshToken", portal),
+            logout: format!("{}/api/auth/postLogout", portal),
+            api: api.trim_end_matches('/').to_owned(),
+        }
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Endpoints::new(Self::PORTAL_URL, Self::API_URL)
+    }
+}
+
+/// A version of the web services API, given by the first segment of the path of each query
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum ApiVersion {
+    #[default]
+    V1,
+    V2,
+    /// Some other version, inserted into the path as given
+    ///
+    /// An empty version adds nothing to the base URL, such as for a mock server.
+    Custom(String),
+}
+
+impl ApiVersion {
+    /// The segment of the path identifying the version
+    pub fn as_str(&self) -> &str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+            ApiVersion::Custom(version) => version.trim_matches('/'),
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
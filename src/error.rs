@@ -0,0 +1,430 @@
+//! Errors arising from use of the API
+
+use crate::{AuthError, RateLimitInfo};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use bytes::Bytes;
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Errors arising from use of the API
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or its response could not be received
+    Http(reqwest::Error),
+    /// The request did not complete within the timeout of the client
+    Timeout(reqwest::Error),
+    /// The query did not complete before its deadline
+    Deadline {
+        /// The time spent on the query before it was abandoned
+        elapsed: Duration,
+    },
+    /// The API responded with an error
+    Api {
+        /// The status of the response
+        status: StatusCode,
+        /// The code identifying the error, if one was given
+        code: Option<String>,
+        /// The message describing the error, or the body of the response if there was none
+        message: String,
+    },
+    /// A request to the API failed
+    Request {
+        /// The identifier sent with the request
+        request_id: Uuid,
+        /// The identifier the API gave the request, if it responded with one
+        server_request_id: Option<String>,
+        /// The headers of the response, if one was received
+        headers: Option<Box<HeaderMap>>,
+        /// The reason the request failed
+        source: Box<Error>,
+    },
+    /// A query still failed after being retried
+    RetriesExhausted {
+        /// The number of attempts made
+        attempts: u32,
+        /// The failure of the last attempt
+        source: Box<Error>,
+    },
+    /// A response could not be deserialized
+    Deserialize {
+        /// The reason the response could not be deserialized
+        source: serde_json::Error,
+        /// The path of the request
+        path: String,
+        /// The status of the response
+        status: StatusCode,
+        /// The body of the response, truncated to at most 4 KiB
+        body: String,
+    },
+    /// The API has nothing at the path of the request
+    NotFound,
+    /// The response was not of the type of content expected
+    UnexpectedContentType {
+        /// The type of content expected
+        expected: &'static str,
+        /// The type of content received, if one was given
+        received: Option<String>,
+    },
+    /// A change of plan was requested while another is still pending
+    PlanChangePending,
+    /// A purchase was declined because the account has no method of payment that can be charged
+    PaymentDeclined {
+        /// The message describing why the purchase was declined
+        message: String,
+    },
+    /// A quote expired before it was confirmed
+    QuoteExpired,
+    /// A service could not be suspended because it already is
+    AlreadySuspended,
+    /// A service is not eligible for the change requested, such as while it is in contract
+    ServiceIneligible {
+        /// The message describing why the service is not eligible
+        message: String,
+    },
+    /// The authorization was rejected and the user must authenticate again
+    ReauthenticationRequired,
+    /// Too many requests have been made and the API asked for them to be slowed down
+    RateLimited(RateLimitInfo),
+    /// The client has sent as many requests as its budget allows
+    BudgetExhausted,
+    /// Authentication failed
+    Auth(AuthError),
+    /// An argument was not valid
+    InvalidInput(String),
+    /// An offline client has no fixture for the path of a request
+    NoFixture(String),
+    /// A file could not be read or written
+    Io(io::Error),
+    /// A value could not be converted to or from JSON
+    Json(serde_json::Error),
+    /// The credential store of the operating system could not be used
+    #[cfg(feature = "keyring")]
+    Keyring(keyring::Error),
+    /// Some other failure, such as from a credential provider
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// The kind of an [`Error`](enum.Error.html), without its details
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    Http,
+    Timeout,
+    Deadline,
+    Api,
+    RetriesExhausted,
+    Deserialize,
+    NotFound,
+    UnexpectedContentType,
+    PlanChangePending,
+    PaymentDeclined,
+    QuoteExpired,
+    AlreadySuspended,
+    ServiceIneligible,
+    ReauthenticationRequired,
+    RateLimited,
+    BudgetExhausted,
+    Auth,
+    InvalidInput,
+    NoFixture,
+    Io,
+    Json,
+    #[cfg(feature = "keyring")]
+    Keyring,
+    Other,
+}
+
+impl Error {
+    /// Get the kind of the error
+    ///
+    /// A failed request is classified by the reason it failed.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Http(_) => ErrorKind::Http,
+            Error::Timeout(_) => ErrorKind::Timeout,
+            Error::Deadline { .. } => ErrorKind::Deadline,
+            Error::Api { .. } => ErrorKind::Api,
+            Error::Request { source, .. } => source.kind(),
+            Error::RetriesExhausted { .. } => ErrorKind::RetriesExhausted,
+            Error::Deserialize { .. } => ErrorKind::Deserialize,
+            Error::NotFound => ErrorKind::NotFound,
+            Error::UnexpectedContentType { .. } => ErrorKind::UnexpectedContentType,
+            Error::PlanChangePending => ErrorKind::PlanChangePending,
+            Error::PaymentDeclined { .. } => ErrorKind::PaymentDeclined,
+            Error::QuoteExpired => ErrorKind::QuoteExpired,
+            Error::AlreadySuspended => ErrorKind::AlreadySuspended,
+            Error::ServiceIneligible { .. } => ErrorKind::ServiceIneligible,
+            Error::ReauthenticationRequired => ErrorKind::ReauthenticationRequired,
+            Error::RateLimited(_) => ErrorKind::RateLimited,
+            Error::BudgetExhausted => ErrorKind::BudgetExhausted,
+            Error::Auth(_) => ErrorKind::Auth,
+            Error::InvalidInput(_) => ErrorKind::InvalidInput,
+            Error::NoFixture(_) => ErrorKind::NoFixture,
+            Error::Io(_) => ErrorKind::Io,
+            Error::Json(_) => ErrorKind::Json,
+            #[cfg(feature = "keyring")]
+            Error::Keyring(_) => ErrorKind::Keyring,
+            Error::Other(_) => ErrorKind::Other,
+        }
+    }
+
+    /// Get the error without the request that produced it
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::Request { source, .. } => source,
+            err => err,
+        }
+    }
+
+    /// Get the identifier sent with the request that produced the error
+    pub fn request_id(&self) -> Option<Uuid> {
+        match self {
+            Error::Request { request_id, .. } => Some(*request_id),
+            Error::RetriesExhausted { source, .. } => source.request_id(),
+            _ => None,
+        }
+    }
+
+    /// Get the identifier the API gave the request that produced the error
+    pub fn server_request_id(&self) -> Option<&str> {
+        match self {
+            Error::Request { server_request_id, .. } => server_request_id.as_deref(),
+            Error::RetriesExhausted { source, .. } => source.server_request_id(),
+            _ => None,
+        }
+    }
+
+    /// Get the headers of the response to the request that produced the error, if one was received
+    pub fn headers(&self) -> Option<&HeaderMap> {
+        match self {
+            Error::Request { headers, .. } => headers.as_deref(),
+            Error::RetriesExhausted { source, .. } => source.headers(),
+            _ => None,
+        }
+    }
+
+    /// Replace the error, keeping the request that produced it
+    pub(crate) fn map_inner(self, f: impl FnOnce(Error) -> Error) -> Error {
+        match self {
+            Error::Request { request_id, server_request_id, headers, source } => Error::Request {
+                request_id,
+                server_request_id,
+                headers,
+                source: Box::new(f(*source)),
+            },
+            err => f(err),
+        }
+    }
+
+    /// Interpret the body of an error response from the API
+    pub(crate) fn from_response(status: StatusCode, body: String) -> Self {
+        #[derive(Deserialize)]
+        struct Failure {
+            #[serde(default)]
+            code: Option<Value>,
+            #[serde(default, alias = "error")]
+            message: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Text(String),
+            Number(i64),
+        }
+
+        match serde_json::from_str::<Failure>(&body) {
+            Ok(Failure { code, message: Some(message) }) => {
+                let code = code.map(|code| match code {
+                    Value::Text(code) => code,
+                    Value::Number(code) => code.to_string(),
+                });
+                Error::Api { status, code, message }
+            }
+            _ => Error::Api { status, code: None, message: body },
+        }
+    }
+}
+
+/// The body of a response, along with the request that produced it
+#[derive(Clone)]
+pub(crate) struct Body {
+    pub(crate) path: String,
+    pub(crate) status: StatusCode,
+    pub(crate) bytes: Bytes,
+}
+
+impl Body {
+    /// Number of bytes of the body kept when it can not be deserialized
+    const MAX_KEPT: usize = 4096;
+
+    /// Read the body of a response
+    pub(crate) async fn read(response: reqwest::Response) -> Result<Self, Error> {
+        let path = response.url().path().to_owned();
+        let status = response.status();
+        let bytes = response.bytes().await?;
+        Ok(Body { path, status, bytes })
+    }
+
+    /// The body as text, with any invalid UTF-8 replaced
+    pub(crate) fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.bytes)
+    }
+
+    /// Deserialize the body, keeping it in the error if it can not be deserialized
+    ///
+    /// An empty body is treated as `null`, so that it can be deserialized as a unit.
+    pub(crate) fn parse<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        let bytes: &[u8] = if self.bytes.iter().all(u8::is_ascii_whitespace) {
+            b"null"
+        } else {
+            &self.bytes
+        };
+        serde_json::from_slice(bytes).map_err(|source| self.deserialize_error(source))
+    }
+
+    /// Describe a failure to deserialize the body
+    pub(crate) fn deserialize_error(&self, source: serde_json::Error) -> Error {
+        let text = self.text();
+        let mut end = text.len().min(Self::MAX_KEPT);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        Error::Deserialize {
+            source,
+            path: self.path.clone(),
+            status: self.status,
+            body: text[..end].to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(_) => write!(f, "request to the API failed"),
+            Error::Timeout(_) => write!(f, "request to the API timed out"),
+            Error::Deadline { elapsed } => {
+                write!(f, "query abandoned after {} ms", elapsed.as_millis())
+            }
+            Error::Api { status, code: Some(code), message } => {
+                write!(f, "API responded with {} ({}): {}", status, code, message)
+            }
+            Error::Api { status, code: None, message } => {
+                write!(f, "API responded with {}: {}", status, message)
+            }
+            Error::Request { request_id, source, .. } => {
+                write!(f, "{} (request {})", source, request_id)
+            }
+            Error::RetriesExhausted { attempts, .. } => {
+                write!(f, "query failed after {} attempts", attempts)
+            }
+            Error::Deserialize { path, status, .. } => {
+                write!(f, "unable to deserialize the {} response from {}", status, path)
+            }
+            Error::NotFound => write!(f, "not found"),
+            Error::UnexpectedContentType { expected, received: Some(received) } => {
+                write!(f, "expected a response of type {}, received {}", expected, received)
+            }
+            Error::UnexpectedContentType { expected, received: None } => {
+                write!(f, "expected a response of type {}, received no type", expected)
+            }
+            Error::PlanChangePending => write!(f, "a change of plan is already pending"),
+            Error::PaymentDeclined { message } => write!(f, "payment declined: {}", message),
+            Error::QuoteExpired => write!(f, "quote expired before it was confirmed"),
+            Error::AlreadySuspended => write!(f, "service is already suspended"),
+            Error::ServiceIneligible { message } => {
+                write!(f, "service is not eligible: {}", message)
+            }
+            Error::ReauthenticationRequired => {
+                write!(f, "authorization was rejected, reauthentication is required")
+            }
+            Error::RateLimited(RateLimitInfo { retry_after: Some(retry_after), .. }) => {
+                write!(f, "rate limited, retry after {} seconds", retry_after.as_secs())
+            }
+            Error::RateLimited(_) => write!(f, "rate limited"),
+            Error::BudgetExhausted => write!(f, "budget of requests exhausted"),
+            Error::Auth(err) => fmt::Display::fmt(err, f),
+            Error::InvalidInput(message) => write!(f, "invalid input: {}", message),
+            Error::NoFixture(path) => write!(f, "no fixture for {}", path),
+            Error::Io(err) => fmt::Display::fmt(err, f),
+            Error::Json(err) => fmt::Display::fmt(err, f),
+            #[cfg(feature = "keyring")]
+            Error::Keyring(_) => write!(f, "unable to use the keyring"),
+            Error::Other(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(err) | Error::Timeout(err) => Some(err),
+            Error::RetriesExhausted { source, .. } => Some(source.as_ref()),
+            Error::Deserialize { source, .. } => Some(source),
+            #[cfg(feature = "keyring")]
+            Error::Keyring(err) => Some(err),
+            // These are displayed as the underlying error
+            Error::Request { source, .. } => source.source(),
+            Error::Auth(err) => err.source(),
+            Error::Io(err) => err.source(),
+            Error::Json(err) => err.source(),
+            Error::Other(err) => err.source(),
+            Error::Api { .. }
+            | Error::Deadline { .. }
+            | Error::NotFound
+            | Error::UnexpectedContentType { .. }
+            | Error::PlanChangePending
+            | Error::PaymentDeclined { .. }
+            | Error::QuoteExpired
+            | Error::AlreadySuspended
+            | Error::ServiceIneligible { .. }
+            | Error::ReauthenticationRequired
+            | Error::RateLimited(_)
+            | Error::BudgetExhausted
+            | Error::InvalidInput(_)
+            | Error::NoFixture(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Error::Timeout(err)
+        } else {
+            Error::Http(err)
+        }
+    }
+}
+
+impl From<AuthError> for Error {
+    fn from(err: AuthError) -> Self {
+        Error::Auth(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl From<keyring::Error> for Error {
+    fn from(err: keyring::Error) -> Self {
+        Error::Keyring(err)
+    }
+}
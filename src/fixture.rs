@@ -0,0 +1,350 @@
+//! Canned responses for using a client without access to the API, and recording of them
+
+use crate::middleware::{Middleware, Next};
+use crate::Error;
+use async_trait::async_trait;
+use mime::APPLICATION_JSON;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Responses to return instead of querying the API, by the path of the query
+///
+/// Paths are matched against the whole path of each request, including the version of the API,
+/// such as `/v1/service`. A response given for a method is preferred over one given for any
+/// method.
+#[derive(Debug, Clone, Default)]
+pub struct FixtureSet {
+    responses: HashMap<(Option<Method>, String), Fixture>,
+}
+
+/// A response to return instead of querying the API
+#[derive(Debug, Clone)]
+struct Fixture {
+    status: StatusCode,
+    body: String,
+}
+
+impl FixtureSet {
+    /// The services of a sample account
+    const SERVICES: &'static str = include_str!("../fixtures/services.json");
+
+    /// Construct a set with no fixtures
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a set with fixtures for a sample account
+    pub fn sample() -> Self {
+        FixtureSet::new().with("/v1/service", Self::SERVICES)
+    }
+
+    /// Respond to queries for a path with the given JSON body
+    pub fn with(mut self, path: &str, body: impl Into<String>) -> Self {
+        let fixture = Fixture { status: StatusCode::OK, body: body.into() };
+        self.responses.insert((None, path.to_owned()), fixture);
+        self
+    }
+
+    /// Respond to queries for a path with a method with the given status and JSON body
+    pub fn with_response(
+        mut self,
+        method: Method,
+        path: &str,
+        status: StatusCode,
+        body: impl Into<String>,
+    ) -> Self {
+        let fixture = Fixture { status, body: body.into() };
+        self.responses.insert((Some(method), path.to_owned()), fixture);
+        self
+    }
+
+    /// Respond to queries for a path with the JSON body in a file
+    pub fn with_file(self, path: &str, file: impl AsRef<Path>) -> Result<Self, Error> {
+        let body = fs::read_to_string(file)?;
+        Ok(self.with(path, body))
+    }
+
+    /// Load the responses recorded to a directory by a client
+    ///
+    /// Responses are replayed with the status with which they were recorded, for the method with
+    /// which they were requested. If a path was recorded more than once for a method, the last
+    /// response recorded for it is used. See
+    /// [`ClientBuilder::record_to`](struct.ClientBuilder.html#method.record_to).
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        let index: Vec<Recorded> = serde_json::from_str(&fs::read_to_string(dir.join(INDEX))?)?;
+        index.into_iter().try_fold(FixtureSet::new(), |fixtures, recorded| {
+            let method = recorded.method.parse().map_err(|_| {
+                Error::InvalidInput(format!("invalid recorded method: {}", recorded.method))
+            })?;
+            let status = StatusCode::from_u16(recorded.status).map_err(|_| {
+                Error::InvalidInput(format!("invalid recorded status: {}", recorded.status))
+            })?;
+            let body = fs::read_to_string(dir.join(&recorded.file))?;
+            Ok(fixtures.with_response(method, &recorded.path, status, body))
+        })
+    }
+
+    /// Get the response to a request for a path with a method
+    fn get(&self, method: &Method, path: &str) -> Option<&Fixture> {
+        let key = |method| (method, path.to_owned());
+        self.responses.get(&key(Some(method.clone()))).or_else(|| self.responses.get(&key(None)))
+    }
+}
+
+/// Name of the file listing the responses recorded to a directory
+const INDEX: &str = "index.json";
+
+/// A response recorded to a directory
+#[derive(Debug, Serialize, Deserialize)]
+struct Recorded {
+    sequence: usize,
+    method: String,
+    path: String,
+    status: u16,
+    file: String,
+}
+
+/// Middleware that records the body of every response to a directory
+///
+/// Anything resembling an access token is redacted from the bodies.
+pub(crate) struct Recorder {
+    dir: PathBuf,
+    index: Mutex<Vec<Recorded>>,
+}
+
+impl Recorder {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Recorder { dir, index: Mutex::default() }
+    }
+
+    /// Write the body of a response and add it to the index
+    fn record(&self, method: &str, path: &str, status: u16, body: &[u8]) -> Result<(), Error> {
+        // The index is only extended once the body has been written
+        let mut index = self.index.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let sequence = index.len() + 1;
+        let name: String = path
+            .trim_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let file = format!("{:04}-{}.json", sequence, name);
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(&file), scrub(body))?;
+        index.push(Recorded {
+            sequence,
+            method: method.to_owned(),
+            path: path.to_owned(),
+            status,
+            file,
+        });
+        fs::write(self.dir.join(INDEX), serde_json::to_string_pretty(&*index)?)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Middleware for Recorder {
+    async fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, Error> {
+        let method = request.method().to_string();
+        let path = request.url().path().to_owned();
+        let response = next.run(request).await?;
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let version = response.version();
+        let body = response.bytes().await?;
+        self.record(&method, &path, status.as_u16(), &body)?;
+
+        let mut replayed = http::Response::builder().status(status).version(version);
+        if let Some(replayed_headers) = replayed.headers_mut() {
+            *replayed_headers = headers;
+        }
+        let replayed = replayed.body(body).map_err(|err| Error::Other(err.into()))?;
+        Ok(replayed.into())
+    }
+}
+
+/// Redact anything resembling a token from a body
+///
+/// Bodies are only rewritten if something was redacted, so that they are otherwise recorded
+/// exactly as they were received.
+fn scrub(body: &[u8]) -> Vec<u8> {
+    const REDACTED: &str = "REDACTED";
+
+    fn scrub_value(value: &mut Value) -> bool {
+        match value {
+            Value::String(text) if is_token(text) => {
+                *text = REDACTED.to_owned();
+                true
+            }
+            Value::Array(values) => {
+                let mut scrubbed = false;
+                for value in values {
+                    scrubbed |= scrub_value(value);
+                }
+                scrubbed
+            }
+            Value::Object(fields) => {
+                let mut scrubbed = false;
+                for (name, value) in fields {
+                    if name.to_ascii_lowercase().contains("token") && value.is_string() {
+                        *value = Value::String(REDACTED.to_owned());
+                        scrubbed = true;
+                    } else {
+                        scrubbed |= scrub_value(value);
+                    }
+                }
+                scrubbed
+            }
+            _ => false,
+        }
+    }
+
+    if let Ok(mut value) = serde_json::from_slice::<Value>(body) {
+        if scrub_value(&mut value) {
+            return serde_json::to_vec_pretty(&value).unwrap_or_default();
+        }
+        return body.to_vec();
+    }
+
+    let text = String::from_utf8_lossy(body);
+    let words = text.split(|c: char| !(c.is_ascii_alphanumeric() || "-_.".contains(c)));
+    let tokens: Vec<&str> = words.filter(|word| is_token(word)).collect();
+    if tokens.is_empty() {
+        return body.to_vec();
+    }
+    let mut scrubbed = text.to_string();
+    for token in tokens {
+        scrubbed = scrubbed.replace(token, REDACTED);
+    }
+    scrubbed.into_bytes()
+}
+
+/// Check if text resembles a bearer token or JSON web token
+fn is_token(text: &str) -> bool {
+    let jwt = text.strip_prefix("Bearer ").unwrap_or(text);
+    text.starts_with("Bearer ") || (jwt.starts_with("eyJ") && jwt.split('.').count() == 3)
+}
+
+/// Middleware that responds to every request from a set of fixtures
+pub(crate) struct Fixtures(pub(crate) FixtureSet);
+
+#[async_trait]
+impl Middleware for Fixtures {
+    async fn handle(&self, request: Request, _next: Next<'_>) -> Result<Response, Error> {
+        let path = request.url().path();
+        let fixture = match self.0.get(request.method(), path) {
+            Some(fixture) => fixture,
+            None => return Err(Error::NoFixture(path.to_owned())),
+        };
+
+        let response = http::Response::builder()
+            .status(fixture.status)
+            .header(CONTENT_TYPE, APPLICATION_JSON.essence_str())
+            .body(fixture.body.clone())
+            .map_err(|err| Error::Other(err.into()))?;
+        Ok(response.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use crate::{Authorization, Client};
+    use reqwest::Url;
+
+    const SERVICES: &str = "{\"data\": {\"broadband\": []}}\n";
+    const FAILURE: &str = r#"{"message": "Service temporarily unavailable"}"#;
+
+    #[tokio::test]
+    async fn recorded_responses_are_replayed() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, "/v1/raw", MockResponse::json(200, SERVICES));
+        server.respond(Method::POST, "/v1/raw", MockResponse::json(503, FAILURE));
+        let dir = tempfile::tempdir().unwrap();
+
+        let authorization = Authorization::from_tokens("access".to_owned(), None, None);
+        let recording = Client::builder(authorization)
+            .base_url(Url::parse(&server.url()).unwrap())
+            .record_to(dir.path().to_owned())
+            .build()
+            .unwrap();
+        let mut recorded = Vec::new();
+        recording.download("/raw", &mut recorded).await.unwrap();
+        let body = serde_json::json!({});
+        recording.post_raw("/raw", &body).await.unwrap_err();
+        assert_eq!(recorded, SERVICES.as_bytes());
+
+        let offline = Client::offline(FixtureSet::load(dir.path()).unwrap()).unwrap();
+        let mut replayed = Vec::new();
+        offline.download("/raw", &mut replayed).await.unwrap();
+        assert_eq!(replayed, SERVICES.as_bytes());
+
+        let err = offline.post_raw("/raw", &body).await.unwrap_err();
+        match err.inner() {
+            Error::Api { status, message, .. } => {
+                assert_eq!(*status, StatusCode::SERVICE_UNAVAILABLE);
+                assert_eq!(message, "Service temporarily unavailable");
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn fixtures_match_the_method() {
+        let fixtures = FixtureSet::new()
+            .with_response(Method::POST, "/v1/raw", StatusCode::CREATED, "{}")
+            .with("/v1/other", "[]");
+        let offline = Client::offline(fixtures).unwrap();
+
+        let created = offline.post_raw("/raw", &serde_json::json!({})).await.unwrap();
+        assert_eq!(created.status, StatusCode::CREATED);
+        assert_eq!(offline.get_raw("/other").await.unwrap().status, StatusCode::OK);
+
+        let err = offline.get_raw("/raw").await.unwrap_err();
+        assert!(matches!(err.inner(), Error::NoFixture(path) if path == "/v1/raw"), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn services_are_parsed_from_the_sample() {
+        let offline = Client::offline(FixtureSet::sample()).unwrap();
+        let services = offline.services().await.unwrap();
+        assert_eq!(services.broadband().len(), 1);
+        assert_eq!(services.mobile()[0].as_ref().service_number(), "0400000000");
+
+        // Fixtures are served without connecting, even to a server that is listening
+        let server = MockServer::start().await;
+        let authorization = Authorization::from_tokens("access".to_owned(), None, None);
+        let client = Client::builder(authorization)
+            .base_url(Url::parse(&server.url()).unwrap())
+            .middleware(Fixtures(FixtureSet::sample()))
+            .build()
+            .unwrap();
+        client.services().await.unwrap();
+        assert_eq!(client.requests_made(), 1);
+        assert_eq!(server.connections(), 0);
+    }
+
+    #[test]
+    fn tokens_are_scrubbed() {
+        let body = br#"{"access_token": "secret", "nested": [{"auth": "Bearer abc"}]}"#;
+        let scrubbed: Value = serde_json::from_slice(&scrub(body)).unwrap();
+        let expected = serde_json::json!({
+            "access_token": "REDACTED",
+            "nested": [{"auth": "REDACTED"}],
+        });
+        assert_eq!(scrubbed, expected);
+
+        let clean = b"{\"id\":  1}";
+        assert_eq!(scrub(clean), clean.to_vec());
+    }
+}
@@ -3,9 +3,41 @@
 //! See the [API documentation](https://webservices.api.exetel.com.au/doc/) for more information.
 
 mod auth;
+mod cache;
 mod client;
+mod endpoints;
+mod error;
+mod fixture;
+mod middleware;
+#[cfg(test)]
+mod mock;
+mod netrc;
+mod observer;
+mod paginate;
+mod rate_limit;
+mod retry;
+mod transport;
 pub mod customer;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 
-pub use auth::Authorization;
-pub use client::Client;
-use client::Query;
+pub use auth::{
+    AccountInfo, Authorization, AuthError, AuthOptions, CredentialProvider, ExportedTokens, Login,
+    OtpChallenge, Secret, SharedAuthorization, StaticCredentials, StoredAuthorization, Token,
+};
+pub use cache::CachePolicy;
+pub use client::{
+    AccountSnapshot, ApiResponse, Client, ClientBuilder, DownloadInfo, Query, QueryOptions,
+    RawResponse,
+};
+pub use endpoints::{ApiVersion, Endpoints};
+pub use middleware::{InjectHeaders, Middleware, Next};
+pub use observer::{CountingObserver, RequestObserver};
+pub use paginate::{Page, PagedQuery, Pages};
+pub use rate_limit::RateLimitInfo;
+pub use error::{Error, ErrorKind};
+pub use fixture::FixtureSet;
+pub use retry::RetryPolicy;
+pub use transport::{build_request, parse_response, HttpRequest, HttpResponse};
+pub use reqwest::Method;
+pub use uuid::Uuid;
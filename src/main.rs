@@ -1,7 +1,10 @@
-use anyhow::Error;
+use anyhow::{Error, bail};
 use structopt::StructOpt;
-use dialoguer::Password;
-use exetel_api::{Authorization, customer};
+use dialoguer::{Input, Password};
+use exetel_api::{Authorization, AuthError, Login, OtpChallenge, Secret};
+use exetel_api::Error as ApiError;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Command line utility to query the Exetel web API
 #[derive(StructOpt)]
@@ -11,26 +14,167 @@ struct Args {
     username: Option<String>,
     /// Password (prompted interactively)
     #[structopt(skip)]
-    password: Option<String>,
-    /// Authoirzation token
-    #[structopt(skip)]
-    authorization: Option<Authorization>,
+    password: Option<Secret>,
+    /// File in which to keep the authorization between runs
+    #[structopt(short, long, parse(from_os_str))]
+    token_file: Option<PathBuf>,
+    /// Keep the refresh token in the credential store of the operating system
+    #[cfg(feature = "keyring")]
+    #[structopt(long)]
+    keyring: bool,
+    /// Print the tokens as JSON for use by other tools instead of querying the API
+    #[structopt(long)]
+    print_token: bool,
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
+#[derive(StructOpt)]
+enum Command {
+    /// List the services on the account (the default)
+    Services,
+    /// Revoke the session and remove the token file
+    Logout,
+}
+
+/// Service and account under which the refresh token is kept in the credential store
+#[cfg(feature = "keyring")]
+const KEYRING_ENTRY: (&str, &str) = ("exetel-api", "default");
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     let mut args = Args::from_args();
 
-    if args.username.is_some() {
+    if let Some(Command::Logout) = args.command {
+        return logout(args.token_file.as_deref()).await;
+    }
+
+    let mut authorization = match &args.token_file {
+        Some(path) if path.exists() => match Authorization::load_from(path) {
+            Ok(authorization) => Some(authorization),
+            Err(ApiError::Auth(_)) => None,
+            Err(err) => return Err(err.into()),
+        },
+        _ => None,
+    };
+
+    #[cfg(feature = "keyring")]
+    {
+        if authorization.is_none() && args.keyring {
+            let (service, account) = KEYRING_ENTRY;
+            authorization = match Authorization::load_from_keyring(service, account).await {
+                Ok(authorization) => Some(authorization),
+                Err(ApiError::Keyring(keyring::Error::NoEntry)) => None,
+                Err(ApiError::Keyring(err)) => {
+                    eprintln!("warning: unable to use the keyring: {}", err);
+                    None
+                }
+                Err(err) => {
+                    eprintln!("warning: ignoring keyring entry: {}", err);
+                    None
+                }
+            };
+        }
+    }
+
+    if authorization.is_none() && args.username.is_none() {
+        authorization = match Authorization::from_env().await {
+            Ok(login) => Some(complete_login(login).await?),
+            Err(ApiError::Auth(AuthError::MissingEnvironment(_))) => None,
+            Err(err) => return Err(err.into()),
+        };
+    }
+
+    if authorization.is_none() && args.username.is_none() {
+        authorization = match Authorization::from_netrc(Authorization::NETRC_MACHINE).await {
+            Ok(login) => Some(complete_login(login).await?),
+            Err(err @ ApiError::Auth(AuthError::NetrcInsecure(_))) => {
+                eprintln!("warning: ignoring netrc file: {}", err);
+                None
+            }
+            Err(ApiError::Auth(AuthError::NetrcNotFound(_)))
+            | Err(ApiError::Auth(AuthError::NetrcMissingMachine(_)))
+            | Err(ApiError::Auth(AuthError::MissingEnvironment(_))) => None,
+            Err(err) => return Err(err.into()),
+        };
+    }
+
+    if authorization.is_none() && args.username.is_some() {
         let password = Password::new().with_prompt("Enter password").interact()?;
-        args.password = Some(password);
+        args.password = Some(Secret::from(password));
     }
 
     if let (Some(username), Some(password)) = (args.username, args.password) {
-        let authorization = Authorization::authenticate(&username, &password).await?;
+        let login = Authorization::authenticate_secret(&username, &password).await?;
+        authorization = Some(complete_login(login).await?);
+    }
+
+    if let Some(authorization) = authorization {
+        if args.print_token {
+            println!("{}", serde_json::to_string_pretty(&authorization.export())?);
+        }
+
         let client = authorization.into_client()?;
-        println!("Services: {:#?}", client.services().await?);
+        if !args.print_token {
+            println!("Services: {:#?}", client.services().await?);
+        }
+
+        if let Some(path) = &args.token_file {
+            client.authorization().await.save_to(path)?;
+        }
+
+        #[cfg(feature = "keyring")]
+        {
+            if args.keyring {
+                let (service, account) = KEYRING_ENTRY;
+                client.authorization().await.save_to_keyring(service, account)?;
+            }
+        }
     }
 
     Ok(())
 }
+
+/// Complete a login, prompting for a one-time password if required
+async fn complete_login(login: Login) -> Result<Authorization, Error> {
+    match login {
+        Login::Authorized(authorization) => Ok(authorization),
+        Login::OtpRequired(challenge) => complete_otp(challenge).await,
+    }
+}
+
+/// Prompt for one-time passwords until the login is completed
+async fn complete_otp(mut challenge: OtpChallenge) -> Result<Authorization, Error> {
+    if let Some(message) = challenge.message() {
+        println!("{}", message);
+    }
+
+    loop {
+        let code: String = Input::new().with_prompt("Enter one-time password").interact()?;
+        match challenge.submit(&code).await {
+            Ok(authorization) => return Ok(authorization),
+            Err(err @ ApiError::Auth(AuthError::InvalidOtp)) => println!("{}", err),
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Remove the token file and revoke the session it holds
+async fn logout(token_file: Option<&Path>) -> Result<(), Error> {
+    let path = match token_file {
+        Some(path) => path,
+        None => bail!("a token file is required to log out"),
+    };
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let authorization = Authorization::load_from(path);
+    fs::remove_file(path)?;
+
+    match authorization {
+        Ok(authorization) => Ok(authorization.revoke().await?),
+        Err(ApiError::Auth(_)) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
@@ -0,0 +1,144 @@
+//! Interception of the requests sent by a client
+
+use crate::Error;
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::{Request, Response};
+
+/// Handles each request sent by a client, such as to add headers or to log responses
+///
+/// Middleware is run in the order it was added to the client, after the authorization has been
+/// added to the request, so it should take care not to log the `Authorization` header. Each
+/// attempt at a request is handled separately.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Handle a request, passing it on to the rest of the middleware or responding to it directly
+    async fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, Error>;
+}
+
+/// The rest of the middleware, followed by sending the request
+pub struct Next<'a> {
+    inner: &'a reqwest::Client,
+    middleware: &'a [Box<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(inner: &'a reqwest::Client, middleware: &'a [Box<dyn Middleware>]) -> Self {
+        Next { inner, middleware }
+    }
+
+    /// Pass the request on to the rest of the middleware and send it
+    pub async fn run(self, request: Request) -> Result<Response, Error> {
+        match self.middleware.split_first() {
+            Some((first, middleware)) => {
+                first.handle(request, Next { inner: self.inner, middleware }).await
+            }
+            None => Ok(self.inner.execute(request).await?),
+        }
+    }
+}
+
+/// Middleware that adds headers to every request, replacing any with the same name
+#[derive(Debug, Clone)]
+pub struct InjectHeaders {
+    headers: HeaderMap,
+}
+
+impl InjectHeaders {
+    /// Add the given headers to every request
+    pub fn new(headers: HeaderMap) -> Self {
+        InjectHeaders { headers }
+    }
+}
+
+#[async_trait]
+impl Middleware for InjectHeaders {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, Error> {
+        for (name, value) in &self.headers {
+            request.headers_mut().insert(name, value.clone());
+        }
+        next.run(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use reqwest::header::HeaderValue;
+    use reqwest::{Method, StatusCode};
+    use std::sync::{Arc, Mutex};
+
+    /// Middleware recording when it handles a request and the status of its response
+    struct Trace(&'static str, Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Middleware for Trace {
+        async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, Error> {
+            self.1.lock().unwrap().push(format!("{} request", self.0));
+            let value = HeaderValue::from_static(self.0);
+            request.headers_mut().append("x-trace", value);
+            let response = next.run(request).await?;
+            self.1.lock().unwrap().push(format!("{} {}", self.0, response.status().as_u16()));
+            Ok(response)
+        }
+    }
+
+    /// Middleware answering every request itself
+    struct Answer;
+
+    #[async_trait]
+    impl Middleware for Answer {
+        async fn handle(&self, _: Request, _: Next<'_>) -> Result<Response, Error> {
+            let response = http::Response::builder().status(200).body(r#"{"from": "middleware"}"#);
+            Ok(response.unwrap().into())
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_runs_in_order() {
+        let server = MockServer::start().await;
+        server.respond(Method::GET, "/api/v1/test", MockResponse::json(202, "{}"));
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let mut headers = HeaderMap::new();
+        headers.insert("x-proxy-auth", HeaderValue::from_static("proxy"));
+        headers.insert("x-trace", HeaderValue::from_static("replaced"));
+        let client = server
+            .builder()
+            .middleware(Trace("outer", trace.clone()))
+            .middleware(Trace("inner", trace.clone()))
+            .middleware(InjectHeaders::new(headers))
+            .build()
+            .unwrap();
+
+        let response = client.get_raw("/test").await.unwrap();
+        assert_eq!(response.status, StatusCode::ACCEPTED);
+
+        let trace = trace.lock().unwrap();
+        assert_eq!(*trace, ["outer request", "inner request", "inner 202", "outer 202"]);
+        let received = &server.received()[0];
+        assert_eq!(received.headers["x-proxy-auth"], "proxy");
+        // Headers added by earlier middleware are replaced by those injected later
+        let traced: Vec<_> = received.headers.get_all("x-trace").iter().collect();
+        assert_eq!(traced, ["replaced"]);
+        assert!(received.headers.contains_key("authorization"));
+    }
+
+    #[tokio::test]
+    async fn middleware_can_answer_requests_itself() {
+        let server = MockServer::start().await;
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let client = server
+            .builder()
+            .middleware(Trace("outer", trace.clone()))
+            .middleware(Answer)
+            .middleware(Trace("inner", trace.clone()))
+            .build()
+            .unwrap();
+
+        let response = client.get_raw("/test").await.unwrap();
+        assert_eq!(response.body, serde_json::json!({"from": "middleware"}));
+        assert_eq!(*trace.lock().unwrap(), ["outer request", "outer 200"]);
+        assert!(server.received().is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+//! A local server that answers requests with scripted responses, for testing
+
+use crate::{Authorization, Client, ClientBuilder, Endpoints};
+use bytes::Bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Url};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+/// A server answering each path with the responses scripted for it, in order
+///
+/// The last response scripted for a path is repeated once the others have been used. Paths with
+/// no responses are answered with a 404.
+pub(crate) struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    responses: HashMap<(Method, String), VecDeque<MockResponse>>,
+    received: Vec<Received>,
+    connections: usize,
+}
+
+/// A response scripted for a path
+#[derive(Debug, Clone)]
+pub(crate) struct MockResponse {
+    status: StatusCode,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+    delay: Option<Duration>,
+    chunk_size: Option<usize>,
+}
+
+impl MockResponse {
+    /// A response with a JSON body
+    pub(crate) fn json(status: u16, body: &str) -> Self {
+        MockResponse::bytes(status, "application/json", body.as_bytes())
+    }
+
+    /// A response with a body of the given type of content
+    pub(crate) fn bytes(status: u16, content_type: &str, body: &[u8]) -> Self {
+        MockResponse {
+            status: StatusCode::from_u16(status).expect("status is valid"),
+            headers: vec![("content-type", content_type.to_owned())],
+            body: body.to_vec(),
+            delay: None,
+            chunk_size: None,
+        }
+    }
+
+    /// A response with no body
+    pub(crate) fn empty(status: u16) -> Self {
+        MockResponse {
+            headers: Vec::new(),
+            body: Vec::new(),
+            ..MockResponse::json(status, "")
+        }
+    }
+
+    /// Add a header to the response
+    pub(crate) fn header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_owned()));
+        self
+    }
+
+    /// Wait before sending the response
+    pub(crate) fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Send the body in separate chunks of the given size
+    pub(crate) fn chunked(mut self, size: usize) -> Self {
+        self.chunk_size = Some(size);
+        self
+    }
+}
+
+/// A request received by the server
+#[derive(Debug, Clone)]
+pub(crate) struct Received {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) query: Option<String>,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Received {
+    /// The body of the request as JSON
+    pub(crate) fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("request body is JSON")
+    }
+}
+
+impl MockServer {
+    /// Start a server on an unused local port
+    pub(crate) async fn start() -> Self {
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = shared.clone();
+            state.lock().unwrap().connections += 1;
+            async move {
+                let service = service_fn(move |request| respond(state.clone(), request));
+                Ok::<_, Infallible>(service)
+            }
+        });
+
+        let server = Server::try_bind(&([127, 0, 0, 1], 0).into())
+            .expect("local port is available")
+            .serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        MockServer { addr, state }
+    }
+
+    /// The base URL of the server
+    pub(crate) fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Endpoints of a portal and API both served by the server
+    pub(crate) fn endpoints(&self) -> Endpoints {
+        Endpoints::new(&self.url(), &format!("{}/api", self.url()))
+    }
+
+    /// A client sending queries to the server with an authorization that never expires
+    ///
+    /// Queries are received under `/api`, such as `/api/v1/service`.
+    pub(crate) fn client(&self) -> Client {
+        self.builder().build().expect("client is valid")
+    }
+
+    /// A builder for a client like [`client`](#method.client), to be configured further
+    pub(crate) fn builder(&self) -> ClientBuilder {
+        let authorization = Authorization::from_tokens("access".to_owned(), None, None);
+        let base_url = Url::parse(&format!("{}/api", self.url())).expect("URL is valid");
+        Client::builder(authorization).base_url(base_url)
+    }
+
+    /// Script the next response to requests for a path
+    pub(crate) fn respond(&self, method: Method, path: &str, response: MockResponse) {
+        let mut state = self.state.lock().unwrap();
+        let responses = state.responses.entry((method, path.to_owned())).or_default();
+        responses.push_back(response);
+    }
+
+    /// The requests received so far, in the order they were received
+    pub(crate) fn received(&self) -> Vec<Received> {
+        self.state.lock().unwrap().received.clone()
+    }
+
+    /// The number of connections accepted so far
+    pub(crate) fn connections(&self) -> usize {
+        self.state.lock().unwrap().connections
+    }
+
+    /// The number of requests received for a path
+    pub(crate) fn count(&self, path: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state.received.iter().filter(|received| received.path == path).count()
+    }
+}
+
+/// Record a request and answer it with the next response scripted for its path
+async fn respond(
+    state: Arc<Mutex<State>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+    let path = parts.uri.path().to_owned();
+
+    let scripted = {
+        let mut state = state.lock().unwrap();
+        state.received.push(Received {
+            method: parts.method.clone(),
+            path: path.clone(),
+            query: parts.uri.query().map(str::to_owned),
+            headers: parts.headers,
+            body: body.to_vec(),
+        });
+        let responses = state.responses.get_mut(&(parts.method, path));
+        match responses {
+            Some(responses) if responses.len() > 1 => responses.pop_front(),
+            Some(responses) => responses.front().cloned(),
+            None => None,
+        }
+    };
+    let scripted = scripted.unwrap_or_else(|| MockResponse::empty(404));
+    if let Some(delay) = scripted.delay {
+        time::delay_for(delay).await;
+    }
+
+    let mut response = Response::builder().status(scripted.status);
+    for (name, value) in scripted.headers {
+        response = response.header(name, value);
+    }
+    let body = match scripted.chunk_size {
+        Some(size) => {
+            let (mut sender, body) = Body::channel();
+            let chunks: Vec<_> = scripted.body.chunks(size).map(Bytes::copy_from_slice).collect();
+            tokio::spawn(async move {
+                for chunk in chunks {
+                    if sender.send_data(chunk).await.is_err() {
+                        break;
+                    }
+                    time::delay_for(Duration::from_millis(1)).await;
+                }
+            });
+            body
+        }
+        None => Body::from(scripted.body),
+    };
+    Ok(response.body(body).expect("response is valid"))
+}
+
+/// A span or event recorded by a [`Capture`]
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Captured {
+    pub(crate) name: &'static str,
+    pub(crate) fields: Vec<(&'static str, String)>,
+}
+
+#[cfg(feature = "tracing")]
+impl Captured {
+    /// The recorded value of a field, formatted with `Debug`
+    pub(crate) fn field(&self, name: &str) -> Option<&str> {
+        let mut fields = self.fields.iter().rev();
+        fields.find(|(field, _)| *field == name).map(|(_, value)| value.as_str())
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl tracing::field::Visit for Captured {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.fields.push((field.name(), format!("{:?}", value)));
+    }
+}
+
+/// A subscriber recording every span and event, for checking what is traced
+///
+/// Install it on the current thread with `tracing::subscriber::set_default`.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Capture {
+    spans: Arc<Mutex<Vec<(Captured, &'static tracing::Metadata<'static>)>>>,
+    events: Arc<Mutex<Vec<Captured>>>,
+    entered: Arc<Mutex<Vec<tracing::span::Id>>>,
+}
+
+#[cfg(feature = "tracing")]
+impl Capture {
+    /// The spans created so far, with the fields recorded for them
+    pub(crate) fn spans(&self) -> Vec<Captured> {
+        self.spans.lock().unwrap().iter().map(|(span, _)| span.clone()).collect()
+    }
+
+    /// The events recorded so far
+    pub(crate) fn events(&self) -> Vec<Captured> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// The first span with the given name
+    pub(crate) fn span(&self, name: &str) -> Option<Captured> {
+        self.spans().into_iter().find(|span| span.name == name)
+    }
+
+    /// Every value recorded for any span or event
+    pub(crate) fn values(&self) -> Vec<String> {
+        let recorded = self.spans().into_iter().chain(self.events());
+        recorded.flat_map(|captured| captured.fields).map(|(_, value)| value).collect()
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl tracing::Subscriber for Capture {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let metadata = attributes.metadata();
+        let mut span = Captured { name: metadata.name(), ..Default::default() };
+        attributes.record(&mut span);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push((span, metadata));
+        tracing::span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, id: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        values.record(&mut spans[id.into_u64() as usize - 1].0);
+    }
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        let mut captured = Captured { name: event.metadata().name(), ..Default::default() };
+        event.record(&mut captured);
+        self.events.lock().unwrap().push(captured);
+    }
+
+    fn enter(&self, id: &tracing::span::Id) {
+        self.entered.lock().unwrap().push(id.clone());
+    }
+
+    fn exit(&self, id: &tracing::span::Id) {
+        let mut entered = self.entered.lock().unwrap();
+        if let Some(position) = entered.iter().rposition(|entered| entered == id) {
+            entered.remove(position);
+        }
+    }
+
+    fn current_span(&self) -> tracing_core::span::Current {
+        match self.entered.lock().unwrap().last() {
+            Some(id) => {
+                let metadata = self.spans.lock().unwrap()[id.into_u64() as usize - 1].1;
+                tracing_core::span::Current::new(id.clone(), metadata)
+            }
+            None => tracing_core::span::Current::none(),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Reading of credentials from netrc files
+
+use std::path::PathBuf;
+use std::env;
+
+/// Credentials for a machine in a netrc file
+#[derive(Debug, Default)]
+pub(crate) struct Entry {
+    pub(crate) login: Option<String>,
+    pub(crate) password: Option<String>,
+}
+
+/// Find the credentials for a machine, falling back to the default entry
+pub(crate) fn find(netrc: &str, machine: &str) -> Option<Entry> {
+    let mut entry: Option<Entry> = None;
+    let mut keyword = None;
+    let mut in_macro = false;
+
+    for line in netrc.lines() {
+        // Macro definitions continue until the next blank line
+        if in_macro {
+            in_macro = !line.trim().is_empty();
+            continue;
+        }
+
+        for token in line.split_whitespace() {
+            match (keyword.take(), &mut entry) {
+                (Some("machine"), None) if token == machine => entry = Some(Entry::default()),
+                (Some("login"), Some(entry)) => entry.login = Some(token.to_owned()),
+                (Some("password"), Some(entry)) => entry.password = Some(token.to_owned()),
+                (Some("macdef"), _) => {
+                    in_macro = true;
+                    break;
+                }
+                (Some(_), _) => {}
+                (None, Some(_)) if token == "machine" || token == "default" => return entry,
+                (None, None) if token == "default" => entry = Some(Entry::default()),
+                (None, _) => keyword = Some(token),
+            }
+        }
+    }
+
+    entry
+}
+
+/// The location of the netrc file of the current user
+pub(crate) fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("NETRC") {
+        return Some(PathBuf::from(path));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".netrc"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn machine_entries_are_found() {
+        let netrc = "machine one login first password one-pass\n\
+                     machine two\n  login second\n  password two-pass\n";
+        let entry = find(netrc, "two").unwrap();
+        assert_eq!(entry.login.as_deref(), Some("second"));
+        assert_eq!(entry.password.as_deref(), Some("two-pass"));
+
+        let entry = find(netrc, "one").unwrap();
+        assert_eq!(entry.login.as_deref(), Some("first"));
+        assert_eq!(entry.password.as_deref(), Some("one-pass"));
+    }
+
+    #[test]
+    fn default_entry_is_a_fallback() {
+        let netrc = "machine one login first password one-pass\n\
+                     default login anonymous password guest\n";
+        let entry = find(netrc, "other").unwrap();
+        assert_eq!(entry.login.as_deref(), Some("anonymous"));
+        assert_eq!(entry.password.as_deref(), Some("guest"));
+
+        let entry = find(netrc, "one").unwrap();
+        assert_eq!(entry.login.as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn missing_machine_has_no_entry() {
+        let netrc = "machine one login first password one-pass\n";
+        assert!(find(netrc, "two").is_none());
+        assert!(find("", "one").is_none());
+
+        let entry = find("machine two login second\n", "two").unwrap();
+        assert_eq!(entry.login.as_deref(), Some("second"));
+        assert!(entry.password.is_none());
+    }
+
+    #[test]
+    fn macros_are_skipped() {
+        let netrc = "macdef init\nmachine two login wrong password wrong\n\n\
+                     machine two login second password two-pass\n";
+        let entry = find(netrc, "two").unwrap();
+        assert_eq!(entry.login.as_deref(), Some("second"));
+    }
+}
@@ -0,0 +1,65 @@
+//! Observation of the requests sent by a client
+
+use crate::error::ErrorKind;
+use reqwest::StatusCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Receives the outcome of every request sent by a client, such as to export metrics
+///
+/// Each attempt at a request is observed separately, numbered from 1. An attempt that receives
+/// an error response is reported to both methods.
+///
+/// Observers are called before the response is returned to the caller, so implementations
+/// should be cheap and must not block.
+pub trait RequestObserver: Send + Sync {
+    /// A response was received for the request to the given path
+    fn on_response(&self, path: &str, status: StatusCode, elapsed: Duration, attempt: u32);
+
+    /// The request to the given path failed
+    fn on_error(&self, path: &str, kind: &ErrorKind, attempt: u32);
+}
+
+/// An observer that counts the responses and errors of requests
+#[derive(Debug, Default)]
+pub struct CountingObserver {
+    responses: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl CountingObserver {
+    /// Construct an observer that has counted nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of responses received
+    pub fn responses(&self) -> u64 {
+        self.responses.load(Ordering::Relaxed)
+    }
+
+    /// The number of requests that failed
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+impl RequestObserver for CountingObserver {
+    fn on_response(&self, _path: &str, _status: StatusCode, _elapsed: Duration, _attempt: u32) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, _path: &str, _kind: &ErrorKind, _attempt: u32) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<T: RequestObserver + ?Sized> RequestObserver for std::sync::Arc<T> {
+    fn on_response(&self, path: &str, status: StatusCode, elapsed: Duration, attempt: u32) {
+        (**self).on_response(path, status, elapsed, attempt)
+    }
+
+    fn on_error(&self, path: &str, kind: &ErrorKind, attempt: u32) {
+        (**self).on_error(path, kind, attempt)
+    }
+}
@@ -0,0 +1,246 @@
+//! Queries for results that are split across pages
+
+use crate::{Client, Error, Query};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::stream::{Stream, StreamExt};
+
+/// A query for one page of a paged list of results
+pub trait PagedQuery: Query + Sized {
+    /// Type of each result
+    type Item;
+
+    /// Get the query for the given page, numbered from 1
+    fn page_param(&self, page: u32) -> Self;
+
+    /// Get the results in the response to the query and whether more pages follow
+    fn into_page(response: Self::Response) -> Page<Self::Item>;
+}
+
+/// The results in one page of a paged list
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    /// The results in the page
+    pub items: Vec<T>,
+    /// Whether more pages follow this one
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Construct a page from the numbering of pages reported by the API
+    ///
+    /// More pages follow if fewer than `total` results have been given up to the end of `page`.
+    pub fn from_totals(items: Vec<T>, page: u32, per_page: u32, total: u64) -> Self {
+        let has_more = u64::from(page) * u64::from(per_page) < total;
+        Page { items, has_more }
+    }
+}
+
+type Fetch<T> = Pin<Box<dyn Future<Output = Result<Page<T>, Error>> + Send>>;
+
+/// The results of a paged query, fetching each page as the previous one is consumed
+///
+/// The stream ends after the first failure.
+pub struct Pages<Q: PagedQuery> {
+    client: Client,
+    query: Q,
+    next_page: Option<u32>,
+    items: VecDeque<Q::Item>,
+    fetching: Option<Fetch<Q::Item>>,
+}
+
+// The fields of the stream are never pinned
+impl<Q: PagedQuery> Unpin for Pages<Q> {}
+
+impl<Q> Pages<Q>
+where
+    Q: PagedQuery + Send + Sync + 'static,
+    Q::Response: Send,
+    Q::Item: Send,
+{
+    pub(crate) fn new(client: Client, query: Q) -> Self {
+        Pages {
+            client,
+            query,
+            next_page: Some(1),
+            items: VecDeque::new(),
+            fetching: None,
+        }
+    }
+
+    /// Fetch every page and collect all of the results
+    pub async fn collect_all(mut self) -> Result<Vec<Q::Item>, Error> {
+        let mut items = Vec::new();
+        while let Some(item) = self.next().await {
+            items.push(item?);
+        }
+        Ok(items)
+    }
+
+    fn fetch(&self, page: u32) -> Fetch<Q::Item> {
+        let client = self.client.clone();
+        let query = self.query.page_param(page);
+        Box::pin(async move { client.execute(&query).await.map(Q::into_page) })
+    }
+}
+
+impl<Q> Stream for Pages<Q>
+where
+    Q: PagedQuery + Send + Sync + 'static,
+    Q::Response: Send,
+    Q::Item: Send,
+{
+    type Item = Result<Q::Item, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(item) = self.items.pop_front() {
+                return Poll::Ready(Some(Ok(item)));
+            }
+
+            if let Some(fetching) = &mut self.fetching {
+                let page = match fetching.as_mut().poll(cx) {
+                    Poll::Ready(page) => page,
+                    Poll::Pending => return Poll::Pending,
+                };
+                self.fetching = None;
+                match page {
+                    Ok(page) => {
+                        self.items.extend(page.items);
+                        if !page.has_more {
+                            self.next_page = None;
+                        }
+                    }
+                    Err(err) => {
+                        self.next_page = None;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                }
+                continue;
+            }
+
+            match self.next_page {
+                Some(page) => {
+                    self.next_page = page.checked_add(1);
+                    self.fetching = Some(self.fetch(page));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer};
+    use crate::Method;
+    use serde::Deserialize;
+    use std::borrow::Cow;
+
+    const NUMBERS_PATH: &str = "/api/v1/numbers";
+
+    /// Query for a page of numbers
+    #[derive(Clone)]
+    struct Numbers {
+        page: u32,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct NumberPage {
+        items: Vec<u32>,
+        page: u32,
+        per_page: u32,
+        total: u64,
+    }
+
+    impl Query for Numbers {
+        type Body = ();
+        type Response = NumberPage;
+
+        fn path(&self) -> Cow<'_, str> {
+            "/numbers".into()
+        }
+
+        fn query_params(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+            vec![("page".into(), self.page.to_string().into())]
+        }
+    }
+
+    impl PagedQuery for Numbers {
+        type Item = u32;
+
+        fn page_param(&self, page: u32) -> Self {
+            Numbers { page }
+        }
+
+        fn into_page(response: NumberPage) -> Page<u32> {
+            let NumberPage { items, page, per_page, total } = response;
+            Page::from_totals(items, page, per_page, total)
+        }
+    }
+
+    /// Script the pages of six numbers, two to a page
+    fn serve_pages(server: &MockServer) {
+        for page in 1..=3 {
+            let body = format!(
+                r#"{{"items": [{}, {}], "page": {}, "perPage": 2, "total": 6}}"#,
+                2 * page - 1,
+                2 * page,
+                page
+            );
+            server.respond(Method::GET, NUMBERS_PATH, MockResponse::json(200, &body));
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_are_collected_in_order() {
+        let server = MockServer::start().await;
+        serve_pages(&server);
+
+        let numbers = server.client().paginate(Numbers { page: 1 }).collect_all().await;
+        assert_eq!(numbers.unwrap(), [1, 2, 3, 4, 5, 6]);
+
+        let pages: Vec<_> = server.received().into_iter().map(|received| received.query).collect();
+        let expected = ["page=1", "page=2", "page=3"];
+        assert_eq!(pages, expected.iter().map(|page| Some(page.to_string())).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn pages_are_fetched_as_they_are_consumed() {
+        let server = MockServer::start().await;
+        serve_pages(&server);
+        let mut numbers = server.client().paginate(Numbers { page: 1 });
+
+        assert_eq!(server.count(NUMBERS_PATH), 0);
+        assert_eq!(numbers.next().await.unwrap().unwrap(), 1);
+        assert_eq!(numbers.next().await.unwrap().unwrap(), 2);
+        assert_eq!(server.count(NUMBERS_PATH), 1);
+        assert_eq!(numbers.next().await.unwrap().unwrap(), 3);
+        assert_eq!(server.count(NUMBERS_PATH), 2);
+    }
+
+    #[tokio::test]
+    async fn stream_ends_after_a_failure() {
+        let server = MockServer::start().await;
+        let first = r#"{"items": [1, 2], "page": 1, "perPage": 2, "total": 6}"#;
+        server.respond(Method::GET, NUMBERS_PATH, MockResponse::json(200, first));
+        server.respond(Method::GET, NUMBERS_PATH, MockResponse::json(500, "{}"));
+        let mut numbers = server.client().paginate(Numbers { page: 1 });
+
+        assert_eq!(numbers.next().await.unwrap().unwrap(), 1);
+        assert_eq!(numbers.next().await.unwrap().unwrap(), 2);
+        assert!(numbers.next().await.unwrap().is_err());
+        assert!(numbers.next().await.is_none());
+        assert_eq!(server.count(NUMBERS_PATH), 2);
+
+        let server = MockServer::start().await;
+        server.respond(Method::GET, NUMBERS_PATH, MockResponse::json(200, first));
+        server.respond(Method::GET, NUMBERS_PATH, MockResponse::json(500, "{}"));
+        let collected = server.client().paginate(Numbers { page: 1 }).collect_all().await;
+        assert!(collected.is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Limiting of the rate at which requests are sent
+
+use crate::retry;
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{self, Instant};
+
+/// The limits on requests reported by the API when it rejected a request
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitInfo {
+    /// The time to wait before making another request, from the `Retry-After` header
+    pub retry_after: Option<Duration>,
+    /// The number of requests allowed, from the `X-RateLimit-Limit` header
+    pub limit: Option<u64>,
+    /// The number of requests remaining, from the `X-RateLimit-Remaining` header
+    pub remaining: Option<u64>,
+    /// When the limit is reset, as given by the `X-RateLimit-Reset` header
+    pub reset: Option<u64>,
+}
+
+impl RateLimitInfo {
+    /// Read the limits from the headers of a response
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let number = |name| headers.get(name)?.to_str().ok()?.trim().parse().ok();
+        RateLimitInfo {
+            retry_after: retry::retry_after(headers),
+            limit: number("x-ratelimit-limit"),
+            remaining: number("x-ratelimit-remaining"),
+            reset: number("x-ratelimit-reset"),
+        }
+    }
+}
+
+/// A bucket of requests that is refilled at a fixed interval
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// Number of requests that may be sent in each interval
+    requests: u32,
+    /// Interval at which the bucket is refilled
+    interval: Duration,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    remaining: u32,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(requests: u32, interval: Duration) -> Self {
+        RateLimiter {
+            requests,
+            interval,
+            bucket: Mutex::new(Bucket {
+                remaining: requests,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until another request may be sent
+    ///
+    /// Waiting callers are let through in the order in which they arrived.
+    pub(crate) async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        loop {
+            let refill = bucket.refilled + self.interval;
+            if Instant::now() >= refill {
+                bucket.remaining = self.requests;
+                bucket.refilled = Instant::now();
+            }
+
+            if bucket.remaining > 0 {
+                bucket.remaining -= 1;
+                return;
+            }
+            time::delay_until(refill).await;
+        }
+    }
+}
+
+/// A limit on the number of requests that may ever be sent, or sent in each interval
+#[derive(Debug)]
+pub(crate) struct Budget {
+    requests: u32,
+    /// Interval at which the budget is replenished, if it ever is
+    interval: Option<Duration>,
+    bucket: std::sync::Mutex<Bucket>,
+}
+
+impl Budget {
+    pub(crate) fn new(requests: u32, interval: Option<Duration>) -> Self {
+        Budget {
+            requests,
+            interval,
+            bucket: std::sync::Mutex::new(Bucket {
+                remaining: requests,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Spend part of the budget on a request, if any remains
+    pub(crate) fn spend(&self) -> bool {
+        // The bucket is never left partially updated
+        let mut bucket = self.bucket.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(interval) = self.interval {
+            if Instant::now() >= bucket.refilled + interval {
+                bucket.remaining = self.requests;
+                bucket.refilled = Instant::now();
+            }
+        }
+
+        if bucket.remaining == 0 {
+            return false;
+        }
+        bucket.remaining -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn limits_are_read_from_headers() {
+        let info = RateLimitInfo::from_headers(&headers(&[
+            ("retry-after", "30"),
+            ("x-ratelimit-limit", "100"),
+            ("x-ratelimit-remaining", " 0 "),
+            ("x-ratelimit-reset", "1600000000"),
+        ]));
+        assert_eq!(info.retry_after, Some(Duration::from_secs(30)));
+        assert_eq!(info.limit, Some(100));
+        assert_eq!(info.remaining, Some(0));
+        assert_eq!(info.reset, Some(1_600_000_000));
+    }
+
+    #[test]
+    fn retry_after_may_be_a_date() {
+        let date = (Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        let info = RateLimitInfo::from_headers(&headers(&[("retry-after", &date)]));
+        let retry_after = info.retry_after.unwrap();
+        assert!(retry_after > Duration::from_secs(115), "{:?}", retry_after);
+        assert!(retry_after <= Duration::from_secs(120), "{:?}", retry_after);
+
+        // A date that has already passed needs no wait
+        let date = (Utc::now() - chrono::Duration::seconds(120)).to_rfc2822();
+        let info = RateLimitInfo::from_headers(&headers(&[("retry-after", &date)]));
+        assert_eq!(info.retry_after, Some(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn invalid_limits_are_ignored() {
+        let info = RateLimitInfo::from_headers(&headers(&[
+            ("retry-after", "soon"),
+            ("x-ratelimit-limit", "-1"),
+            ("x-ratelimit-remaining", "many"),
+        ]));
+        assert_eq!(info.retry_after, None);
+        assert_eq!(info.limit, None);
+        assert_eq!(info.remaining, None);
+        assert_eq!(info.reset, None);
+    }
+}
@@ -0,0 +1,225 @@
+//! Retrying of operations that fail transiently
+
+use rand::Rng;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use crate::Error;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::delay_for;
+
+/// The manner in which a failed operation is retried
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Backoff {
+    /// Number of times to retry after the first attempt
+    pub(crate) retries: u32,
+    /// Delay before the first retry, doubling for each subsequent retry
+    pub(crate) delay: Duration,
+}
+
+impl Backoff {
+    /// Limit on the doubling of the delay between retries
+    const MAX_DOUBLINGS: u32 = 16;
+
+    /// Get the delay before the given retry, with jitter of up to half the delay
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let delay = self.delay * (1 << retry.min(Self::MAX_DOUBLINGS));
+        let half = delay / 2;
+        half + half.mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+/// The manner in which failed queries are retried by a client
+///
+/// Queries are only retried if they are idempotent. Delays are chosen at random up to a limit
+/// that doubles with each retry, unless the API asked for a particular delay. No delay is longer
+/// than `max_delay`, even if the API asked for one.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first
+    pub max_attempts: u32,
+    /// Limit on the delay before the first retry
+    pub base_delay: Duration,
+    /// Limit on the delay before any retry
+    pub max_delay: Duration,
+    /// Statuses of responses that are retried, in addition to failures to connect and timeouts
+    pub retry_on: Vec<StatusCode>,
+}
+
+impl RetryPolicy {
+    /// Check if a query that failed with the given error should be retried
+    pub(crate) fn should_retry(&self, err: &Error) -> bool {
+        match err.inner() {
+            Error::Http(err) => is_transient(err),
+            Error::Timeout(_) => true,
+            Error::RateLimited(_) => self.retry_on.contains(&StatusCode::TOO_MANY_REQUESTS),
+            Error::Api { status, .. } => self.retry_on.contains(status),
+            _ => false,
+        }
+    }
+
+    /// Get the delay before retrying after the given number of attempts
+    pub(crate) fn delay(&self, err: &Error, attempts: u32) -> Duration {
+        if let Error::RateLimited(info) = err.inner() {
+            if let Some(retry_after) = info.retry_after {
+                return retry_after.min(self.max_delay);
+            }
+        }
+
+        let limit = self.base_delay * (1 << (attempts - 1).min(Backoff::MAX_DOUBLINGS));
+        limit.min(self.max_delay).mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retry_on: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+}
+
+/// Run an operation, retrying it with backoff after failures that should be retried
+pub(crate) async fn retry<T, E, F, R, P>(
+    backoff: Backoff,
+    should_retry: P,
+    mut operation: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> R,
+    R: Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
+{
+    let mut retry = 0;
+    loop {
+        match operation().await {
+            Err(err) if retry < backoff.retries && should_retry(&err) => {
+                delay_for(backoff.delay(retry)).await;
+                retry += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Check if a request failed in a manner that may succeed if attempted again
+pub(crate) fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout()
+}
+
+/// Get the delay requested by the `Retry-After` header of a response
+///
+/// The header may be given either as a number of seconds or as a date.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let retry_after = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = retry_after.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(retry_after).ok()?;
+    let delay = date.with_timezone(&Utc) - Utc::now();
+    Some(delay.to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RateLimitInfo;
+
+    fn rate_limited(retry_after: u64) -> Error {
+        Error::RateLimited(RateLimitInfo {
+            retry_after: Some(Duration::from_secs(retry_after)),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn retry_after_is_honoured_up_to_max_delay() {
+        let policy = RetryPolicy { max_delay: Duration::from_secs(30), ..Default::default() };
+
+        assert_eq!(policy.delay(&rate_limited(5), 1), Duration::from_secs(5));
+        assert_eq!(policy.delay(&rate_limited(3600), 1), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn backoff_is_limited_by_max_delay() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(4),
+            ..Default::default()
+        };
+        let err = Error::ReauthenticationRequired;
+
+        for attempts in 1..20 {
+            let limit = Duration::from_secs(1 << (attempts - 1).min(2));
+            assert!(policy.delay(&err, attempts) <= limit, "after {} attempts", attempts);
+        }
+    }
+
+    /// Retry up to `retries` times without waiting long
+    fn quick(retries: u32) -> Backoff {
+        Backoff { retries, delay: Duration::from_millis(1) }
+    }
+
+    /// Run an operation that fails with the given errors in turn before succeeding
+    async fn attempts_until_success(
+        backoff: Backoff,
+        failures: &[&'static str],
+    ) -> (u32, Result<u32, &'static str>) {
+        let mut attempts = 0;
+        let result = retry(backoff, |err: &&str| *err == "transient", || {
+            attempts += 1;
+            let failure = failures.get(attempts as usize - 1);
+            let outcome = failure.map_or(Ok(attempts), |err| Err(*err));
+            async move { outcome }
+        })
+        .await;
+        (attempts, result)
+    }
+
+    #[tokio::test]
+    async fn operation_is_retried_until_it_succeeds() {
+        let failures = ["transient", "transient"];
+        let (attempts, result) = attempts_until_success(quick(3), &failures).await;
+        assert_eq!(attempts, 3);
+        assert_eq!(result, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn retrying_gives_up_after_the_last_retry() {
+        let failures = ["transient"; 5];
+        let (attempts, result) = attempts_until_success(quick(2), &failures).await;
+        assert_eq!(attempts, 3);
+        assert_eq!(result, Err("transient"));
+    }
+
+    #[tokio::test]
+    async fn failures_that_should_not_be_retried_are_returned() {
+        let (attempts, result) = attempts_until_success(quick(3), &["rejected"]).await;
+        assert_eq!(attempts, 1);
+        assert_eq!(result, Err("rejected"));
+
+        let (attempts, result) = attempts_until_success(quick(0), &["transient"]).await;
+        assert_eq!(attempts, 1);
+        assert_eq!(result, Err("transient"));
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter() {
+        let backoff = Backoff { retries: 5, delay: Duration::from_millis(100) };
+        for retry in 0..5 {
+            let limit = Duration::from_millis(100 << retry);
+            let delay = backoff.delay(retry);
+            assert!(delay >= limit / 2 && delay <= limit, "retry {}: {:?}", retry, delay);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Construction of requests and parsing of responses, independent of how they are sent
+
+use crate::client::{self, Query};
+use crate::error::Body;
+use crate::{ApiVersion, Authorization, Error, RateLimitInfo};
+use mime::{APPLICATION_JSON, TEXT_PLAIN};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Method, StatusCode, Url};
+
+/// A request to send to the API by any means
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    /// The method with which to send the request
+    pub method: Method,
+    /// The URL to which to send the request
+    pub url: Url,
+    /// The headers to send with the request, including its authorization
+    pub headers: HeaderMap,
+    /// The body of the request, if it has one
+    pub body: Option<Vec<u8>>,
+}
+
+/// A response received from the API by any means
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    /// The status of the response
+    pub status: StatusCode,
+    /// The headers of the response
+    pub headers: HeaderMap,
+    /// The body of the response, after any decompression
+    pub body: Vec<u8>,
+}
+
+/// Construct the request for a query to the API at a base URL, authorized with the given
+/// authorization
+///
+/// The request is sent to the given version of the API unless the query chooses its own. The
+/// authorization is not refreshed, so it should be refreshed beforehand if necessary.
+pub fn build_request<Q: Query>(
+    api: &str,
+    version: &ApiVersion,
+    authorization: &Authorization,
+    query: &Q,
+) -> Result<HttpRequest, Error> {
+    let mut request = unauthorized_request(api, version, query)?;
+    let bearer = HeaderValue::from_str(&authorization.bearer()?)
+        .map_err(|_| Error::InvalidInput("access token is not a valid header".to_owned()))?;
+    request.headers.insert(AUTHORIZATION, bearer);
+    Ok(request)
+}
+
+/// Construct the request for a query without its authorization, which is added as it is sent
+pub(crate) fn unauthorized_request<Q: Query>(
+    api: &str,
+    version: &ApiVersion,
+    query: &Q,
+) -> Result<HttpRequest, Error> {
+    let url = client::url(api, version, query)?;
+    let (body, content_type) = encode_body(query)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static(APPLICATION_JSON.essence_str()));
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+
+    Ok(HttpRequest {
+        method: query.method(),
+        url,
+        headers,
+        body: body.map(String::into_bytes),
+    })
+}
+
+/// Parse the response to a query
+pub fn parse_response<Q: Query>(query: &Q, response: HttpResponse) -> Result<Q::Response, Error> {
+    let HttpResponse { status, headers, body } = response;
+    check_status(status, &headers)?;
+
+    let body = Body {
+        path: query.path().into_owned(),
+        status,
+        bytes: body.into(),
+    };
+    if !status.is_success() {
+        return Err(failure(&body));
+    }
+    body.parse()
+}
+
+/// Check the status of a response for failures that are reported without reading the body
+pub(crate) fn check_status(status: StatusCode, headers: &HeaderMap) -> Result<(), Error> {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(Error::ReauthenticationRequired),
+        StatusCode::NOT_FOUND => Err(Error::NotFound),
+        StatusCode::TOO_MANY_REQUESTS => {
+            Err(Error::RateLimited(RateLimitInfo::from_headers(headers)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Interpret the body of an unsuccessful response that passed [`check_status`]
+///
+/// Only successful responses are parsed, so that error pages aren't reported as nonsense.
+pub(crate) fn failure(body: &Body) -> Error {
+    Error::from_response(body.status, body.text().into_owned())
+}
+
+/// Encode the body of a query, along with its content type
+pub(crate) fn encode_body<Q: Query>(query: &Q) -> Result<(Option<String>, &'static str), Error> {
+    match query.body() {
+        Some(body) => Ok((Some(serde_json::to_string(body)?), APPLICATION_JSON.essence_str())),
+        None => Ok((None, TEXT_PLAIN.essence_str())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::customer::{self, GetServices};
+    use serde_json::Value;
+    use std::borrow::Cow;
+
+    /// A query posting a body
+    struct Post(Value);
+
+    impl Post {
+        fn new() -> Self {
+            Post(serde_json::json!({"key": "value"}))
+        }
+    }
+
+    impl Query for Post {
+        type Body = Value;
+        type Response = ();
+
+        fn path(&self) -> Cow<'_, str> {
+            "/post".into()
+        }
+
+        fn version(&self) -> Option<ApiVersion> {
+            Some(ApiVersion::V2)
+        }
+
+        fn body(&self) -> Option<&Value> {
+            Some(&self.0)
+        }
+    }
+
+    fn authorization() -> Authorization {
+        Authorization::from_tokens("access".to_owned(), None, None)
+    }
+
+    fn response(status: StatusCode, body: &str) -> HttpResponse {
+        HttpResponse { status, headers: HeaderMap::new(), body: body.as_bytes().to_vec() }
+    }
+
+    #[test]
+    fn requests_are_built_for_the_base_url_and_version() {
+        let api = "https://api.invalid/base";
+        let request = build_request(api, &ApiVersion::V1, &authorization(), &GetServices).unwrap();
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.url.as_str(), "https://api.invalid/base/v1/service");
+        assert_eq!(request.headers[AUTHORIZATION], "Bearer access");
+        assert_eq!(request.headers[ACCEPT], "application/json");
+        assert_eq!(request.headers[CONTENT_TYPE], "text/plain");
+        assert_eq!(request.body, None);
+
+        let custom = ApiVersion::Custom(String::new());
+        let request = build_request(api, &custom, &authorization(), &GetServices).unwrap();
+        assert_eq!(request.url.as_str(), "https://api.invalid/base/service");
+    }
+
+    #[test]
+    fn requests_use_the_version_and_body_of_the_query() {
+        let api = "https://api.invalid";
+        let request = build_request(api, &ApiVersion::V1, &authorization(), &Post::new()).unwrap();
+
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.url.as_str(), "https://api.invalid/v2/post");
+        assert_eq!(request.headers[CONTENT_TYPE], "application/json");
+        assert_eq!(request.body.as_deref(), Some(&br#"{"key":"value"}"#[..]));
+    }
+
+    #[test]
+    fn responses_are_parsed() {
+        let body = include_str!("../fixtures/services.json");
+        let services = parse_response(&GetServices, response(StatusCode::OK, body)).unwrap();
+        let services: customer::Services = services.unwrap();
+        assert_eq!(services.of_type(customer::ServiceType::Mobile).count(), 1);
+
+        parse_response(&Post::new(), response(StatusCode::NO_CONTENT, "")).unwrap();
+    }
+
+    #[test]
+    fn failed_responses_are_reported() {
+        let err = parse_response(&Post::new(), response(StatusCode::UNAUTHORIZED, "")).unwrap_err();
+        assert!(matches!(err, Error::ReauthenticationRequired), "{:?}", err);
+
+        let body = r#"{"code": 42, "message": "Broken"}"#;
+        let failed = response(StatusCode::BAD_GATEWAY, body);
+        let err = parse_response(&Post::new(), failed).unwrap_err();
+        match err {
+            Error::Api { status, code, message } => {
+                assert_eq!(status, StatusCode::BAD_GATEWAY);
+                assert_eq!(code.as_deref(), Some("42"));
+                assert_eq!(message, "Broken");
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+
+        let err = parse_response(&GetServices, response(StatusCode::OK, "<html>")).unwrap_err();
+        match err {
+            Error::Deserialize { path, body, .. } => {
+                assert_eq!(path, "/service");
+                assert_eq!(body, "<html>");
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+}